@@ -121,6 +121,26 @@ impl AudioFile {
 
         Ok(result)
     }
+
+    /// Creates a new `AudioFile` from an unencrypted reader.
+    ///
+    /// Wraps the reader in a 32 KiB buffer. Used for audio that does not
+    /// come from Deezer, like local files, which are never encrypted.
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - Seekable audio data
+    /// * `byte_len` - Total size of the audio data in bytes, if known
+    pub fn from_reader<R>(reader: R, byte_len: Option<u64>) -> Self
+    where
+        R: ReadSeek + 'static,
+    {
+        Self {
+            inner: Box::new(BufReader::with_capacity(BUFFER_LEN, reader)),
+            is_seekable: true,
+            byte_len,
+        }
+    }
 }
 
 /// Implements reading from the audio stream.
@@ -3,7 +3,7 @@
 //! This module handles:
 //! * Authentication methods (email/password or ARL)
 //! * Device identification and settings
-//! * Network configuration (interface binding)
+//! * Network configuration (interface binding, hostname resolution)
 //! * Audio configuration (volume, normalization)
 //! * Track decryption configuration
 //! * API client settings
@@ -14,40 +14,43 @@
 //! use pleezer::config::{Config, Credentials};
 //! use pleezer::arl::Arl;
 //! use pleezer::protocol::connect::Percentage;
-//! use std::net::IpAddr;
 //!
-//! // Configure with ARL authentication, initial volume, and specific network binding
+//! // Configure with ARL authentication and default settings
+//! let config = Config::new(Credentials::Arl(arl))?;
+//!
+//! // Override settings like initial volume and network binding
 //! let config = Config {
-//!     credentials: Credentials::Arl(arl),
 //!     device_name: "My Player".to_string(),
 //!     normalization: true,
-//!     initial_volume: Some(Percentage::from_percent_f32(50.0)), // Start at 50% volume
-//!     bind_address: "192.168.1.2".parse().unwrap(), // Bind to specific interface
-//!     // ... other settings ...
-//! };
-//!
-//! // Configure with email/password
-//! let config = Config {
-//!     credentials: Credentials::Login {
+//!     initial_volume: Some(Percentage::from_percent(50.0)), // Start at 50% volume
+//!     bind_address: "192.168.1.2".parse()?, // Bind to specific interface
+//!     ..Config::new(Credentials::Login {
 //!         email: "user@example.com".to_string(),
 //!         password: "secret".to_string(),
-//!     },
-//!     // ... other settings ...
+//!     })?
 //! };
+//!
+//! // Check overridden settings
+//! config.validate()?;
 //! ```
 
-use std::net::IpAddr;
+use std::{fmt, net::IpAddr, path::PathBuf, str::FromStr, time::Duration};
 
+use md5::{Digest, Md5};
 use regex_lite::Regex;
+use url::Url;
 use uuid::Uuid;
 use veil::Redact;
 
 use crate::{
     arl::Arl,
     decrypt::{Key, KEY_LENGTH},
+    dither::Dither,
+    dns::Nameserver,
     error::{Error, Result},
-    http,
-    protocol::connect::{DeviceType, Percentage},
+    http, now_playing,
+    protocol::connect::{AudioQuality, DeviceType, MessageType, Percentage},
+    tls,
 };
 
 /// Authentication methods for Deezer.
@@ -81,6 +84,234 @@ pub enum Credentials {
     Arl(Arl),
 }
 
+/// How hook scripts are executed.
+#[derive(Copy, Clone, Debug, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum HookMode {
+    /// Spawn the hook script and continue without waiting for it.
+    #[default]
+    Detached,
+
+    /// Wait for the hook script to finish before handling the next event.
+    ///
+    /// Scripts that do not finish within the hook timeout are killed.
+    Blocking,
+}
+
+/// Formats the hook mode as its lowercase name.
+impl fmt::Display for HookMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Detached => write!(f, "detached"),
+            Self::Blocking => write!(f, "blocking"),
+        }
+    }
+}
+
+/// Parses a hook mode from its name, case-insensitively.
+///
+/// # Errors
+///
+/// Returns error if the name is not `detached` or `blocking`.
+impl FromStr for HookMode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "detached" => Ok(Self::Detached),
+            "blocking" => Ok(Self::Blocking),
+            _ => Err(Error::invalid_argument(format!(
+                "invalid hook mode {s}: expected detached or blocking"
+            ))),
+        }
+    }
+}
+
+/// Format of the device ID on the wire.
+///
+/// Deezer apps differ in how they format their UUID device IDs: iOS uses
+/// uppercase with hyphens, Android lowercase without hyphens. Some
+/// controllers may be picky about the format of other devices.
+#[derive(Copy, Clone, Debug, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum UuidFormat {
+    /// Lowercase with hyphens, like the web player.
+    #[default]
+    Hyphenated,
+
+    /// Lowercase without hyphens, like Android.
+    Simple,
+
+    /// Uppercase with hyphens, like iOS.
+    UpperHyphenated,
+
+    /// Uppercase without hyphens.
+    UpperSimple,
+}
+
+impl UuidFormat {
+    /// Formats a UUID in this format.
+    #[must_use]
+    pub fn format(self, uuid: &Uuid) -> String {
+        match self {
+            Self::Hyphenated => uuid.hyphenated().to_string(),
+            Self::Simple => uuid.simple().to_string(),
+            Self::UpperHyphenated => uuid.hyphenated().to_string().to_uppercase(),
+            Self::UpperSimple => uuid.simple().to_string().to_uppercase(),
+        }
+    }
+}
+
+/// Formats the UUID format as its lowercase name.
+impl fmt::Display for UuidFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Hyphenated => write!(f, "hyphenated"),
+            Self::Simple => write!(f, "simple"),
+            Self::UpperHyphenated => write!(f, "upper-hyphenated"),
+            Self::UpperSimple => write!(f, "upper-simple"),
+        }
+    }
+}
+
+/// Parses a UUID format from its name, case-insensitively.
+///
+/// # Errors
+///
+/// Returns error if the name is not `hyphenated`, `simple`,
+/// `upper-hyphenated` or `upper-simple`.
+impl FromStr for UuidFormat {
+    type Err = Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "hyphenated" => Ok(Self::Hyphenated),
+            "simple" => Ok(Self::Simple),
+            "upper-hyphenated" => Ok(Self::UpperHyphenated),
+            "upper-simple" => Ok(Self::UpperSimple),
+            _ => Err(Error::invalid_argument(format!(
+                "invalid uuid format {s}: expected hyphenated, simple, upper-hyphenated or upper-simple"
+            ))),
+        }
+    }
+}
+
+/// When the initial volume yields to the volume of the controller.
+#[derive(Copy, Clone, Debug, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum InitialVolumeMode {
+    /// Apply the initial volume on every connection, until the controller
+    /// sets a volume below maximum.
+    #[default]
+    Sticky,
+
+    /// Apply the initial volume once, when playback first starts. From then
+    /// on, the volume of the controller is used, even when at maximum.
+    Once,
+}
+
+/// Formats the initial volume mode as its lowercase name.
+impl fmt::Display for InitialVolumeMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Sticky => write!(f, "sticky"),
+            Self::Once => write!(f, "once"),
+        }
+    }
+}
+
+/// Parses an initial volume mode from its name, case-insensitively.
+///
+/// # Errors
+///
+/// Returns error if the name is not `sticky` or `once`.
+impl FromStr for InitialVolumeMode {
+    type Err = Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "sticky" => Ok(Self::Sticky),
+            "once" => Ok(Self::Once),
+            _ => Err(Error::invalid_argument(format!(
+                "invalid initial volume mode {s}: expected sticky or once"
+            ))),
+        }
+    }
+}
+
+/// Limit after which to stop playback, like a sleep timer.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum StopAfter {
+    /// Stop when this many tracks have played. Livestreams do not count.
+    Tracks(u32),
+
+    /// Stop when playback has run for this long.
+    Time(Duration),
+}
+
+/// Formats the limit as a number of tracks, or as seconds with an `s` suffix.
+impl fmt::Display for StopAfter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Tracks(tracks) => write!(f, "{tracks}"),
+            Self::Time(duration) => write!(f, "{}s", duration.as_secs()),
+        }
+    }
+}
+
+/// Parses a limit from a number of tracks, or a time with an `s`, `m` or `h`
+/// suffix for seconds, minutes or hours.
+///
+/// # Errors
+///
+/// Returns error if the limit is not a positive number with an optional
+/// valid suffix.
+impl FromStr for StopAfter {
+    type Err = Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        let invalid = || {
+            Error::invalid_argument(format!(
+                "invalid stop limit {s}: expected a number of tracks, or a time like 30m"
+            ))
+        };
+
+        let s = s.trim().to_lowercase();
+        let (number, unit) = match s.find(|c: char| !c.is_ascii_digit()) {
+            Some(index) => s.split_at(index),
+            None => (s.as_str(), ""),
+        };
+        let number: u32 = number.parse().map_err(|_| invalid())?;
+        if number == 0 {
+            return Err(invalid());
+        }
+
+        let seconds = u64::from(number);
+        match unit {
+            "" => Ok(Self::Tracks(number)),
+            "s" => Ok(Self::Time(Duration::from_secs(seconds))),
+            "m" => Ok(Self::Time(Duration::from_secs(seconds * 60))),
+            "h" => Ok(Self::Time(Duration::from_secs(seconds * 60 * 60))),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// Icecast mountpoint to rebroadcast playback to.
+///
+/// # Security
+///
+/// The password is automatically redacted in debug output.
+#[derive(Clone, Hash, PartialEq, Eq, PartialOrd, Ord, Redact)]
+pub struct IcecastServer {
+    /// URL of the mountpoint, for example `http://localhost:8000/pleezer.mp3`
+    pub url: Url,
+
+    /// Source username, usually "source"
+    pub username: String,
+
+    /// Source password
+    #[redact]
+    pub password: String,
+}
+
 /// Complete configuration for pleezer.
 ///
 /// Contains all settings needed to:
@@ -128,28 +359,256 @@ pub struct Config {
     /// could not be retrieved.
     pub device_id: Uuid,
 
+    /// Format of the device ID on the wire.
+    ///
+    /// By default this is `UuidFormat::Hyphenated`.
+    pub device_id_format: UuidFormat,
+
     /// Whether to normalize the audio.
     ///
     /// By default this is `false`.
     pub normalization: bool,
 
+    /// Constant gain in dB applied to all output.
+    ///
+    /// Applied on top of volume normalization, if enabled, and passed through
+    /// the same limiter to prevent clipping. Must be within
+    /// [`PREAMP_RANGE_DB`](Self::PREAMP_RANGE_DB). By default this is 0 dB.
+    pub preamp_db: f32,
+
     /// Initial volume level.
     ///
     /// Used when no volume is reported by Deezer client or when reported as maximum.
     /// None means no volume override.
     pub initial_volume: Option<Percentage>,
 
+    /// When the initial volume yields to the volume of the controller.
+    ///
+    /// By default this is `InitialVolumeMode::Sticky`.
+    pub initial_volume_mode: InitialVolumeMode,
+
+    /// Audio quality to request before user data is loaded.
+    ///
+    /// Applies to tracks loaded before the user settings are known. Once
+    /// they are, the quality is lowered to the casting quality of the user
+    /// if it exceeds it. By default this is `AudioQuality::Standard`.
+    pub initial_quality: AudioQuality,
+
+    /// Maximum number of tracks to download concurrently.
+    ///
+    /// Bounds parallel downloads when tracks are prefetched, to prevent
+    /// saturating the network or hitting gateway limits. Must be at least 1.
+    /// By default this is 2: the current and the next track.
+    pub max_concurrent_downloads: usize,
+
+    /// Whether to stream tracks instead of downloading them completely.
+    ///
+    /// When enabled, only a window of each track is kept in memory, trading
+    /// seek latency for lower peak memory usage. Useful on devices with
+    /// little memory. By default this is `false`.
+    pub streaming: bool,
+
+    /// Whether to reload the current track when the audio quality changes.
+    ///
+    /// When the user's audio quality changes, for example on reconnection,
+    /// the current track keeps playing in its downloaded quality by default,
+    /// and the new quality applies to tracks not yet downloaded. When
+    /// enabled, the current track is reloaded in the new quality instead.
+    /// By default this is `false`.
+    pub reload_on_quality_change: bool,
+
+    /// Maximum playback time of a track before it is skipped.
+    ///
+    /// Guards against tracks with malformed metadata that would otherwise
+    /// play indefinitely. Livestreams are never skipped. By default this is
+    /// `None`: tracks play until they end.
+    pub max_track_duration: Option<Duration>,
+
+    /// Maximum time to wait for the audio output device at startup.
+    ///
+    /// When set, the player checks that the device is available when it is
+    /// created, and retries with backoff until this time has passed. Helps
+    /// when starting at boot before the device is ready. By default this is
+    /// `None`: the device is only opened when playback starts.
+    pub device_open_timeout: Option<Duration>,
+
+    /// Dithering method for output devices that take 16-bit or smaller samples.
+    ///
+    /// By default this is `Dither::None`.
+    pub dither: Dither,
+
+    /// Whether to output inaudible noise while idle to keep the device awake.
+    ///
+    /// Works around DACs and AV receivers that power down on digital silence
+    /// and clip the start of the next track. Only applies while the output
+    /// device is open; the device is still released on disconnect.
+    /// By default this is `false`.
+    pub keepalive_audio: bool,
+
+    /// Whether to discard audio instead of opening an output device.
+    ///
+    /// Audio is still downloaded and decoded at real-time pace, so that
+    /// playback progress and events behave like real playback. For testing
+    /// and for machines without audio hardware. By default this is `false`.
+    pub no_audio: bool,
+
     /// Whether other clients may take over an existing connection.
     ///
     /// By default this is `true`.
     pub interruptions: bool,
 
+    /// Whether to stop responding to discovery while connected without
+    /// interruptions.
+    ///
+    /// Hides the device from other controllers while it is occupied,
+    /// instead of showing it and refusing their connections. Only applies
+    /// when `interruptions` is `false`. By default this is `false`.
+    pub hide_when_taken: bool,
+
+    /// Whether to connect without the stream channel when subscribing to
+    /// it fails.
+    ///
+    /// Subscribing to the stream channel receives stream limitation notices
+    /// of playback on other devices. Without it, the device can still be
+    /// discovered and controlled, and playback progress is still reported
+    /// to the controller. By default this is `false`: the connection is
+    /// retried.
+    pub allow_degraded: bool,
+
+    /// Time after connecting during which other controllers cannot take over.
+    ///
+    /// Prevents controllers from taking over back and forth in busy
+    /// households. Only applies when `interruptions` is `true`. By default
+    /// this is `None`: takeovers are allowed at any time.
+    pub takeover_cooldown: Option<Duration>,
+
+    /// Interval at which to re-announce availability while not connected.
+    ///
+    /// Controllers that sent discovery requests earlier are sent a fresh
+    /// connection offer at this interval, for controllers that missed the
+    /// initial offer. By default this is `None`: only respond to discovery
+    /// requests.
+    pub announce_interval: Option<Duration>,
+
+    /// How long to remember discovery sessions across reconnections.
+    ///
+    /// Controllers that were offered a connection within this time are not
+    /// sent a duplicate offer after reconnecting, which older Deezer apps
+    /// would show as a duplicate remote. The cache stays bounded in size.
+    /// Must not exceed [`DISCOVERY_GRACE_MAX`](Self::DISCOVERY_GRACE_MAX).
+    /// By default this is zero: the cache is cleared on every connection.
+    pub discovery_grace: Duration,
+
+    /// Maximum time to keep a connection before reconnecting.
+    ///
+    /// Long-lived connections may go stale on the server without erroring.
+    /// When this time has passed, the websocket is torn down and
+    /// re-established with fresh authentication, as soon as playback is not
+    /// running so that it is not interrupted. Must be at least
+    /// [`MAX_SESSION_DURATION_MIN`](Self::MAX_SESSION_DURATION_MIN). By
+    /// default this is `None`: connections are kept until they fail.
+    pub max_session_duration: Option<Duration>,
+
+    /// Minimum time between extensions of the Flow queue.
+    ///
+    /// Skipping rapidly through Flow would otherwise fetch new
+    /// recommendations on every track near the end of the queue. Extensions
+    /// requested within this time of the previous one are coalesced into a
+    /// single extension when it has passed. Zero disables throttling. By
+    /// default this is [`FLOW_EXTEND_INTERVAL_DEFAULT`](Self::FLOW_EXTEND_INTERVAL_DEFAULT).
+    pub flow_extend_interval: Duration,
+
+    /// Number of Flow tracks to keep resolved ahead of the current track.
+    ///
+    /// The Flow queue is extended when at most this many tracks are left,
+    /// with at most this many new tracks per extension. This bounds the
+    /// queue and the tracks resolved ahead, as Flow never ends. Further
+    /// recommendations are kept for later extensions. Downloads are not
+    /// affected: the player only preloads the next track, which is always
+    /// within the lookahead.
+    /// By default this is [`FLOW_LOOKAHEAD_DEFAULT`](Self::FLOW_LOOKAHEAD_DEFAULT).
+    pub flow_lookahead: usize,
+
+    /// Maximum size of a websocket frame payload in bytes.
+    ///
+    /// Should be at most a quarter of the maximum message size, so that
+    /// large messages are chunked. By default this is
+    /// [`WEBSOCKET_FRAME_SIZE_MAX_DEFAULT`](Self::WEBSOCKET_FRAME_SIZE_MAX_DEFAULT).
+    pub websocket_frame_size_max: usize,
+
+    /// Maximum size of a websocket message in bytes.
+    ///
+    /// Larger messages, like the queues of huge playlists, are ignored.
+    /// Should be at most half of the write buffer size, to provide
+    /// backpressure. By default this is
+    /// [`WEBSOCKET_MESSAGE_SIZE_MAX_DEFAULT`](Self::WEBSOCKET_MESSAGE_SIZE_MAX_DEFAULT).
+    pub websocket_message_size_max: usize,
+
+    /// Maximum size of the websocket write buffer in bytes.
+    ///
+    /// Bounds the memory used for outgoing messages. Writes are buffered up
+    /// to the maximum message size before they are flushed. By default this is
+    /// [`WEBSOCKET_BUFFER_MAX_DEFAULT`](Self::WEBSOCKET_BUFFER_MAX_DEFAULT).
+    pub websocket_buffer_max: usize,
+
+    /// Limit after which to stop playback and disconnect.
+    ///
+    /// Counted from when a controller connects. Playback fades out over
+    /// the exit fade duration. By default this is `None`: play until the
+    /// controller stops.
+    pub stop_after: Option<StopAfter>,
+
+    /// Whether to exit once the stop limit is reached.
+    ///
+    /// By default this is `false`: stay available for new connections.
+    pub exit_after_stop: bool,
+
     /// Script to execute when events occur
     pub hook: Option<String>,
 
+    /// Script that decides whether to accept controller connections.
+    ///
+    /// Run on every connection attempt with the controller in its
+    /// environment; a zero exit status accepts the connection. By default
+    /// this is `None`: all connections are accepted.
+    pub connect_policy: Option<String>,
+
+    /// Whether to wait for hook scripts to finish.
+    ///
+    /// By default this is `HookMode::Detached`.
+    pub hook_mode: HookMode,
+
+    /// Maximum time to wait for a hook script in blocking mode.
+    ///
+    /// By default this is [`HOOK_TIMEOUT_DEFAULT`](Self::HOOK_TIMEOUT_DEFAULT).
+    pub hook_timeout: Duration,
+
+    /// File to write the current track and queue to.
+    ///
+    /// Rewritten atomically whenever the track or queue changes.
+    /// By default this is `None`.
+    pub now_playing_file: Option<PathBuf>,
+
+    /// Format of the now-playing file.
+    ///
+    /// By default this is `now_playing::Format::M3u`.
+    pub now_playing_format: now_playing::Format,
+
+    /// File to export the complete queue to on request.
+    ///
+    /// On Unix, exports are requested by sending SIGUSR1. By default this
+    /// is `None`: exports are disabled.
+    pub export_file: Option<PathBuf>,
+
+    /// Format of the queue export.
+    ///
+    /// By default this is `now_playing::Format::M3u`.
+    pub export_format: now_playing::Format,
+
     /// The client ID used in API requests.
     ///
-    /// By default this is a random number of 9 digits.
+    /// By default this is a random number of 9 digits, generated on every
+    /// start. Must be within [`CLIENT_ID_RANGE`](Self::CLIENT_ID_RANGE).
     pub client_id: usize,
 
     /// The `User-Agent` string used in API requests.
@@ -167,16 +626,517 @@ pub struct Config {
     /// Whether to eavesdrop on the network traffic.
     pub eavesdrop: bool,
 
+    /// Whether to emit events for playback observed while eavesdropping.
+    ///
+    /// Progress reports of other devices on the account are turned into
+    /// events for hook scripts, without acting on them. By default this is
+    /// `false`.
+    pub eavesdrop_events: bool,
+
+    /// Whether to log played tracks to the user's listening history.
+    ///
+    /// Deezer uses the listening history for recommendations.
+    /// By default this is `true`.
+    pub listening_history: bool,
+
+    /// Whether to report playback progress to the controller.
+    ///
+    /// When disabled, the controller shows frozen progress and playback
+    /// state, but cannot override the player with stale state either.
+    /// By default this is `true`.
+    pub progress_reports: bool,
+
+    /// Duration to fade out playback when shutting down.
+    ///
+    /// Gives the controller a final pause report and prevents speakers from
+    /// popping. None means stop immediately. By default this is 500 ms.
+    pub exit_fade: Option<Duration>,
+
+    /// Message types to drop before handling.
+    ///
+    /// For protocol research only: withholding responses to controllers
+    /// breaks normal operation. By default this is empty.
+    pub ignore_message_types: Vec<MessageType>,
+
+    /// Whether to print events and their hook variables to stdout.
+    ///
+    /// For developing hook scripts; does not require a hook to be set.
+    /// By default this is `false`.
+    pub print_events: bool,
+
     /// The address to bind for outgoing connections.
+    ///
+    /// Applies to all outgoing connections: the HTTP clients for the
+    /// gateway and downloads, the websocket, and the connection to a proxy.
     pub bind_address: IpAddr,
+
+    /// Nameserver to resolve hostnames with.
+    ///
+    /// None means using the system resolver. See the [`dns`](crate::dns)
+    /// module for the privacy and reliability tradeoffs. By default this is
+    /// None.
+    pub resolver: Option<Nameserver>,
+
+    /// PEM file with additional certificate authorities to trust.
+    ///
+    /// For networks with TLS-intercepting proxies. Added to the certificate
+    /// authorities of the operating system, see the [`tls`](crate::tls)
+    /// module. By default this is None.
+    pub ca_cert: Option<PathBuf>,
+
+    /// Extra HTTP headers to send with gateway requests, as name and value.
+    ///
+    /// For passing through proxies or CDNs with header-based access control.
+    /// Headers that the client manages itself, see
+    /// [`PROTECTED_HEADERS`](Self::PROTECTED_HEADERS), cannot be set.
+    /// By default this is empty.
+    pub gateway_headers: Vec<(String, String)>,
+
+    /// Icecast server to rebroadcast playback to.
+    ///
+    /// Only used when built with the `icecast` feature.
+    pub icecast: Option<IcecastServer>,
+
+    /// Whether to show a desktop notification when the track changes.
+    ///
+    /// Only used when built with the `notify` feature. By default this is
+    /// `false`.
+    pub notify: bool,
 }
 
 impl Config {
+    /// Default maximum number of tracks to download concurrently.
+    pub const MAX_CONCURRENT_DOWNLOADS_DEFAULT: usize = 2;
+
+    /// Default duration to fade out playback when shutting down.
+    pub const EXIT_FADE_DEFAULT: Duration = Duration::from_millis(500);
+
+    /// Headers managed by the HTTP client that cannot be set as extra
+    /// gateway headers.
+    pub const PROTECTED_HEADERS: [reqwest::header::HeaderName; 6] = [
+        reqwest::header::AUTHORIZATION,
+        reqwest::header::CONTENT_LENGTH,
+        reqwest::header::CONTENT_TYPE,
+        reqwest::header::COOKIE,
+        reqwest::header::HOST,
+        reqwest::header::USER_AGENT,
+    ];
+
+    /// Maximum time to remember discovery sessions across reconnections.
+    pub const DISCOVERY_GRACE_MAX: Duration = Duration::from_secs(600);
+
+    /// Minimum time to keep a connection before reconnecting.
+    pub const MAX_SESSION_DURATION_MIN: Duration = Duration::from_secs(60);
+
+    /// Default minimum time between extensions of the Flow queue.
+    pub const FLOW_EXTEND_INTERVAL_DEFAULT: Duration = Duration::from_secs(10);
+
+    /// Default number of Flow tracks to keep resolved ahead.
+    pub const FLOW_LOOKAHEAD_DEFAULT: usize = 2;
+
+    /// Default maximum websocket frame size: 32 KiB (message size / 4).
+    pub const WEBSOCKET_FRAME_SIZE_MAX_DEFAULT: usize =
+        Self::WEBSOCKET_MESSAGE_SIZE_MAX_DEFAULT / 4;
+
+    /// Default maximum websocket message size: 128 KiB (buffer size / 2).
+    pub const WEBSOCKET_MESSAGE_SIZE_MAX_DEFAULT: usize = Self::WEBSOCKET_BUFFER_MAX_DEFAULT / 2;
+
+    /// Default maximum websocket write buffer size: 256 KiB.
+    pub const WEBSOCKET_BUFFER_MAX_DEFAULT: usize = 256 * 1024;
+
+    /// Valid range of client IDs: numbers of 9 digits.
+    pub const CLIENT_ID_RANGE: std::ops::RangeInclusive<usize> = 100_000_000..=999_999_999;
+
+    /// Valid range of the pre-amp in dB.
+    pub const PREAMP_RANGE_DB: std::ops::RangeInclusive<f32> = -20.0..=20.0;
+
+    /// Default time to wait for a hook script in blocking mode.
+    pub const HOOK_TIMEOUT_DEFAULT: Duration = Duration::from_secs(5);
+
+    /// Creates a configuration with default settings.
+    ///
+    /// Sets up the application and device identification like the official
+    /// Deezer Desktop client:
+    /// * Application name and version from `Cargo.toml`, in English
+    /// * Device ID from the machine ID, or a random UUID if unavailable
+    /// * Device name from the hostname, or the application name if unavailable
+    /// * `User-Agent` string from the application and operating system
+    /// * Random client ID of 9 digits
+    ///
+    /// All other settings have their documented defaults. Fields may be
+    /// overridden afterwards, in which case [`validate`](Self::validate)
+    /// checks them.
+    ///
+    /// # Arguments
+    ///
+    /// * `credentials` - Credentials to authenticate with Deezer
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if the `User-Agent` string cannot be constructed
+    /// from the application and operating system details.
+    pub fn new(credentials: Credentials) -> Result<Self> {
+        let app_name = env!("CARGO_PKG_NAME").to_owned();
+        let app_version = env!("CARGO_PKG_VERSION").to_owned();
+        let app_lang = "en".to_owned();
+
+        let device_id = *machine_uid::get()
+            .and_then(|uid| uid.parse().map_err(Into::into))
+            .unwrap_or_else(|_| {
+                warn!("could not get machine uuid, using random device id");
+                crate::Uuid::fast_v4()
+            });
+        trace!("device uuid: {device_id}");
+
+        let user_agent = Self::user_agent(&app_name, &app_version, &app_lang)?;
+        trace!("user agent: {user_agent}");
+
+        // Deezer on desktop uses a new `cid` on every start.
+        let client_id = fastrand::usize(Self::CLIENT_ID_RANGE);
+        trace!("client id: {client_id}");
+
+        Ok(Self {
+            device_name: sysinfo::System::host_name().unwrap_or_else(|| app_name.clone()),
+            device_type: DeviceType::default(),
+            device_id,
+            device_id_format: UuidFormat::Hyphenated,
+
+            app_name,
+            app_version,
+            app_lang,
+
+            normalization: false,
+            preamp_db: 0.0,
+            initial_volume: None,
+            initial_volume_mode: InitialVolumeMode::Sticky,
+            initial_quality: AudioQuality::Standard,
+            max_concurrent_downloads: Self::MAX_CONCURRENT_DOWNLOADS_DEFAULT,
+            streaming: false,
+            reload_on_quality_change: false,
+            max_track_duration: None,
+            device_open_timeout: None,
+            dither: Dither::None,
+            keepalive_audio: false,
+            no_audio: false,
+
+            interruptions: true,
+            hide_when_taken: false,
+            allow_degraded: false,
+            takeover_cooldown: None,
+            announce_interval: None,
+            discovery_grace: Duration::ZERO,
+            max_session_duration: None,
+            flow_extend_interval: Self::FLOW_EXTEND_INTERVAL_DEFAULT,
+            flow_lookahead: Self::FLOW_LOOKAHEAD_DEFAULT,
+            websocket_frame_size_max: Self::WEBSOCKET_FRAME_SIZE_MAX_DEFAULT,
+            websocket_message_size_max: Self::WEBSOCKET_MESSAGE_SIZE_MAX_DEFAULT,
+            websocket_buffer_max: Self::WEBSOCKET_BUFFER_MAX_DEFAULT,
+            stop_after: None,
+            exit_after_stop: false,
+            hook: None,
+            connect_policy: None,
+            hook_mode: HookMode::Detached,
+            hook_timeout: Self::HOOK_TIMEOUT_DEFAULT,
+            now_playing_file: None,
+            now_playing_format: now_playing::Format::M3u,
+            export_file: None,
+            export_format: now_playing::Format::M3u,
+
+            client_id,
+            user_agent,
+
+            credentials,
+            bf_secret: None,
+
+            eavesdrop: false,
+            eavesdrop_events: false,
+            listening_history: true,
+            progress_reports: true,
+            exit_fade: Some(Self::EXIT_FADE_DEFAULT),
+            ignore_message_types: Vec::new(),
+            print_events: false,
+            bind_address: IpAddr::V4(std::net::Ipv4Addr::UNSPECIFIED),
+            resolver: None,
+            ca_cert: None,
+            gateway_headers: Vec::new(),
+
+            icecast: None,
+            notify: false,
+        })
+    }
+
+    /// Constructs a `User-Agent` string like the official Deezer Desktop client.
+    ///
+    /// Uses the detected operating system name and version, see
+    /// [`os_name`](Self::os_name) and [`os_version`](Self::os_version).
+    ///
+    /// # Arguments
+    ///
+    /// * `app_name` - Application name, must not contain `/` or `;`
+    /// * `app_version` - Application version, must not contain `/` or `;`
+    /// * `app_lang` - Two-letter application language code
+    ///
+    /// # Errors
+    ///
+    /// Returns `InvalidArgument` if:
+    /// * Application name, version or language is invalid
+    /// * Operating system name or version is invalid
+    pub fn user_agent(app_name: &str, app_version: &str, app_lang: &str) -> Result<String> {
+        Self::user_agent_for_os(
+            app_name,
+            app_version,
+            app_lang,
+            Self::os_name(),
+            &Self::os_version(),
+        )
+    }
+
+    /// Returns the detected operating system name as Deezer reports it.
+    #[must_use]
+    pub fn os_name() -> &'static str {
+        match std::env::consts::OS {
+            "macos" => "osx",
+            other => other,
+        }
+    }
+
+    /// Returns the detected operating system version.
+    ///
+    /// This is the kernel version on Linux, or "0" if it cannot be detected.
+    #[must_use]
+    pub fn os_version() -> String {
+        match std::env::consts::OS {
+            "linux" => sysinfo::System::kernel_version(),
+            _ => sysinfo::System::os_version(),
+        }
+        .unwrap_or("0".to_string())
+    }
+
+    /// Constructs a `User-Agent` string for a given operating system.
+    ///
+    /// # Arguments
+    ///
+    /// * `app_name` - Application name, must not contain `/` or `;`
+    /// * `app_version` - Application version, must not contain `/` or `;`
+    /// * `app_lang` - Two-letter application language code
+    /// * `os_name` - Operating system name, must not contain `/` or `;`
+    /// * `os_version` - Operating system version, must not contain `/` or `;`
+    ///
+    /// # Errors
+    ///
+    /// Returns `InvalidArgument` if:
+    /// * Application name, version or language is invalid
+    /// * Operating system name or version is invalid
+    pub fn user_agent_for_os(
+        app_name: &str,
+        app_version: &str,
+        app_lang: &str,
+        os_name: &str,
+        os_version: &str,
+    ) -> Result<String> {
+        // Additional `User-Agent` string checks on top of what
+        // `reqwest::HeaderValue` already checks.
+        let illegal_chars = |chr| chr == '/' || chr == ';';
+        if app_name.is_empty()
+            || app_name.contains(illegal_chars)
+            || app_version.is_empty()
+            || app_version.contains(illegal_chars)
+            || app_lang.chars().count() != 2
+            || app_lang.contains(illegal_chars)
+        {
+            return Err(Error::invalid_argument(format!(
+                "application name, version and/or language invalid (\"{app_name}\"; \"{app_version}\"; \"{app_lang}\")"
+            )));
+        }
+
+        if os_name.is_empty()
+            || os_name.contains(illegal_chars)
+            || os_version.is_empty()
+            || os_version.contains(illegal_chars)
+        {
+            return Err(Error::invalid_argument(format!(
+                "os name and/or version invalid (\"{os_name}\"; \"{os_version}\")"
+            )));
+        }
+
+        // Set `User-Agent` to be served like Deezer on desktop.
+        Ok(format!(
+            "{app_name}/{app_version} (Rust; {os_name}/{os_version}; like Desktop; {app_lang})"
+        ))
+    }
+
+    /// Checks that the settings are valid.
+    ///
+    /// Useful after overriding fields of a configuration created with
+    /// [`new`](Self::new).
+    ///
+    /// # Errors
+    ///
+    /// Returns `InvalidArgument` if:
+    /// * Device name is empty
+    /// * `User-Agent` string is empty or not a valid header value
+    /// * Extra gateway headers are invalid or protected
+    /// * CA certificate file holds no valid certificates
+    ///
+    /// Returns `OutOfRange` if:
+    /// * Initial volume is not between 0% and 100%
+    /// * Pre-amp is outside [`PREAMP_RANGE_DB`](Self::PREAMP_RANGE_DB)
+    /// * Client ID is outside [`CLIENT_ID_RANGE`](Self::CLIENT_ID_RANGE)
+    /// * Discovery grace exceeds [`DISCOVERY_GRACE_MAX`](Self::DISCOVERY_GRACE_MAX)
+    /// * Maximum session duration is less than
+    ///   [`MAX_SESSION_DURATION_MIN`](Self::MAX_SESSION_DURATION_MIN)
+    /// * Maximum concurrent downloads is zero
+    ///
+    /// Returns an I/O error if the CA certificate file cannot be read.
+    pub fn validate(&self) -> Result<()> {
+        if self.device_name.is_empty() {
+            return Err(Error::invalid_argument("device name is empty"));
+        }
+
+        if self.user_agent.is_empty()
+            || reqwest::header::HeaderValue::from_str(&self.user_agent).is_err()
+        {
+            return Err(Error::invalid_argument(format!(
+                "user agent invalid (\"{}\")",
+                self.user_agent
+            )));
+        }
+
+        if let Some(initial_volume) = self.initial_volume {
+            if !(0.0..=1.0).contains(&initial_volume.as_ratio()) {
+                return Err(Error::out_of_range(format!(
+                    "initial volume {initial_volume} should be between 0% and 100%"
+                )));
+            }
+        }
+
+        if !Self::PREAMP_RANGE_DB.contains(&self.preamp_db) {
+            return Err(Error::out_of_range(format!(
+                "pre-amp {} dB should be between {} and {} dB",
+                self.preamp_db,
+                Self::PREAMP_RANGE_DB.start(),
+                Self::PREAMP_RANGE_DB.end()
+            )));
+        }
+
+        if !Self::CLIENT_ID_RANGE.contains(&self.client_id) {
+            return Err(Error::out_of_range(format!(
+                "client id {} should be a number of 9 digits",
+                self.client_id
+            )));
+        }
+
+        if self.discovery_grace > Self::DISCOVERY_GRACE_MAX {
+            return Err(Error::out_of_range(format!(
+                "discovery grace should be at most {}s",
+                Self::DISCOVERY_GRACE_MAX.as_secs()
+            )));
+        }
+
+        if self
+            .max_session_duration
+            .is_some_and(|duration| duration < Self::MAX_SESSION_DURATION_MIN)
+        {
+            return Err(Error::out_of_range(format!(
+                "maximum session duration should be at least {}s",
+                Self::MAX_SESSION_DURATION_MIN.as_secs()
+            )));
+        }
+
+        if self.max_concurrent_downloads == 0 {
+            return Err(Error::out_of_range(
+                "maximum concurrent downloads should be at least 1",
+            ));
+        }
+
+        if self.flow_lookahead == 0 {
+            return Err(Error::out_of_range("flow lookahead should be at least 1"));
+        }
+
+        if self.websocket_frame_size_max == 0 {
+            return Err(Error::out_of_range(
+                "websocket frame size should be more than zero",
+            ));
+        }
+
+        if self.websocket_frame_size_max > self.websocket_message_size_max / 4 {
+            return Err(Error::out_of_range(format!(
+                "websocket frame size {} should be at most a quarter of the message size {}",
+                self.websocket_frame_size_max, self.websocket_message_size_max
+            )));
+        }
+
+        if self.websocket_message_size_max > self.websocket_buffer_max / 2 {
+            return Err(Error::out_of_range(format!(
+                "websocket message size {} should be at most half of the buffer size {}",
+                self.websocket_message_size_max, self.websocket_buffer_max
+            )));
+        }
+
+        if self.hook_mode == HookMode::Blocking && self.hook_timeout.is_zero() {
+            return Err(Error::out_of_range("hook timeout should be more than zero"));
+        }
+
+        self.gateway_header_map()?;
+
+        if let Some(ca_cert) = &self.ca_cert {
+            tls::load_certificates(ca_cert)?;
+        }
+
+        Ok(())
+    }
+
+    /// Returns the extra gateway headers as a header map.
+    ///
+    /// # Errors
+    ///
+    /// Returns `InvalidArgument` if:
+    /// * A header name or value is invalid
+    /// * A header is one of [`PROTECTED_HEADERS`](Self::PROTECTED_HEADERS)
+    pub fn gateway_header_map(&self) -> Result<reqwest::header::HeaderMap> {
+        let mut headers = reqwest::header::HeaderMap::new();
+        for (name, value) in &self.gateway_headers {
+            let name = reqwest::header::HeaderName::from_bytes(name.as_bytes())
+                .map_err(|e| Error::invalid_argument(format!("header name {name}: {e}")))?;
+            if Self::PROTECTED_HEADERS.contains(&name) {
+                return Err(Error::invalid_argument(format!(
+                    "header {name} is managed by the client and cannot be set"
+                )));
+            }
+
+            let value = reqwest::header::HeaderValue::from_str(value)
+                .map_err(|e| Error::invalid_argument(format!("header {name} value: {e}")))?;
+            headers.append(name, value);
+        }
+
+        Ok(headers)
+    }
+
     /// MD5 checksum of the correct Blowfish secret key.
     ///
     /// Used to verify that an extracted or provided key is valid.
     pub const BF_SECRET_MD5: &'static str = "7ebf40da848f4a0fb3cc56ddbe6c2d09";
 
+    /// Verifies a Blowfish secret key against [`BF_SECRET_MD5`](Self::BF_SECRET_MD5).
+    ///
+    /// A wrong key does not fail decryption itself, but produces garbled
+    /// audio that fails to decode. Checking the key up front turns that into
+    /// a clear error. As a second line of defense, the first decrypted block
+    /// of every track is checked to start like an audio file.
+    ///
+    /// # Errors
+    ///
+    /// Returns `PermissionDenied` if the key is not the correct key.
+    pub fn verify_key(key: &Key) -> Result<()> {
+        if format!("{:x}", Md5::digest(**key)) == Self::BF_SECRET_MD5 {
+            Ok(())
+        } else {
+            Err(Error::permission_denied(
+                "the bf_secret is not valid: tracks would not decrypt correctly",
+            ))
+        }
+    }
+
     /// URL of Deezer's web player interface.
     ///
     /// Used to locate and extract the app-web JavaScript that
@@ -156,6 +156,31 @@ impl Decoder {
     /// * Required track is not found
     /// * Stream parameters are invalid
     pub fn new(track: &Track, file: AudioFile) -> Result<Self> {
+        Self::with_codec(file, track.codec(), track.typ().default_channels())
+    }
+
+    /// Creates a new decoder for an audio file without track metadata.
+    ///
+    /// Used for audio that does not come from Deezer, like local files.
+    /// When the codec is unknown, all supported formats are probed.
+    ///
+    /// # Arguments
+    /// * `file` - Audio file to decode
+    /// * `codec` - Codec of the audio file, if known
+    /// * `default_channels` - Channels to assume if the codec does not tell
+    ///
+    /// # Errors
+    ///
+    /// Returns error if:
+    /// * Format detection fails
+    /// * Codec initialization fails
+    /// * Required track is not found
+    /// * Stream parameters are invalid
+    pub fn with_codec(
+        file: AudioFile,
+        codec: Option<Codec>,
+        default_channels: u16,
+    ) -> Result<Self> {
         // Twice the buffer length to allow for Symphonia's read-ahead behavior,
         // and 64 kB minimum that Symphonia asserts for its ring buffer.
         let buffer_len = usize::max(64 * 1024, BUFFER_LEN * 2);
@@ -166,7 +191,7 @@ impl Decoder {
         let mut hint = Hint::new();
         let mut codecs = CodecRegistry::default();
         let mut probes = Probe::default();
-        let (codecs, probe) = if let Some(codec) = track.codec() {
+        let (codecs, probe) = if let Some(codec) = codec {
             match codec {
                 Codec::ADTS => {
                     codecs.register_all::<AacDecoder>();
@@ -225,11 +250,9 @@ impl Decoder {
         // This may yield information not available before decoder initialization.
         let codec_params = decoder.codec_params();
         let total_duration = Self::calc_total_duration(codec_params);
-        let channels = Self::calc_channels(codec_params).unwrap_or(track.typ().default_channels());
+        let channels = Self::calc_channels(codec_params).unwrap_or(default_channels);
         let sample_rate = Self::calc_sample_rate(codec_params);
-        let max_frame_length = track
-            .codec()
-            .map(|codec| codec.max_frame_length(sample_rate, channels));
+        let max_frame_length = codec.map(|codec| codec.max_frame_length(sample_rate, channels));
         let total_samples = Self::calc_total_samples(codec_params, max_frame_length);
 
         Ok(Self {
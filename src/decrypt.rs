@@ -226,6 +226,17 @@ const CBC_STRIPE_COUNT: usize = 3;
 /// Supported encryption methods.
 const SUPPORTED_CIPHERS: [Cipher; 2] = [Cipher::NONE, Cipher::BF_CBC_STRIPE];
 
+/// Returns whether a decrypted first block starts like an audio file.
+///
+/// Deezer encrypts MP3 and FLAC files, which start with an ID3 tag, a FLAC
+/// stream marker or an MPEG frame sync. Decrypting with a wrong key yields
+/// random data instead.
+fn is_audio_header(block: &[u8]) -> bool {
+    block.starts_with(b"ID3")
+        || block.starts_with(b"fLaC")
+        || matches!(block, [0xff, second, ..] if second & 0xe0 == 0xe0)
+}
+
 thread_local! {
     /// Global decryption key, set once and used for all decryption.
     static BF_SECRET: OnceCell<Key> = const { OnceCell::new() };
@@ -261,6 +272,35 @@ fn bf_secret() -> Result<Key> {
     })
 }
 
+/// Derives a track-specific decryption key.
+///
+/// The key is generated using:
+/// 1. MD5 hash of the track ID
+/// 2. XOR with the master decryption key (salt)
+///
+/// Exposed so that tools can reproduce the key derivation, for example to
+/// verify a user-provided `bf_secret` against a known track.
+///
+/// # Arguments
+///
+/// * `track_id` - Unique identifier for the track
+/// * `salt` - Master decryption key
+///
+/// # Returns
+///
+/// A new `Key` specific to this track for decryption.
+#[must_use]
+pub fn key_for_track_id(track_id: TrackId, salt: &Key) -> Key {
+    let track_hash = format!("{:x}", Md5::digest(track_id.to_string()));
+    let track_hash = track_hash.as_bytes();
+
+    let mut key = RawKey::default();
+    for i in 0..KEY_LENGTH {
+        key[i] = track_hash[i] ^ track_hash[i + KEY_LENGTH] ^ salt[i];
+    }
+    Key(key)
+}
+
 impl<R> Decrypt<R>
 where
     R: ReadSeek,
@@ -288,7 +328,7 @@ where
 
         // Calculate decryption key.
         let salt = bf_secret()?;
-        let key = Self::key_for_track_id(track.id(), &salt);
+        let key = key_for_track_id(track.id(), &salt);
 
         Ok(Self {
             file,
@@ -303,28 +343,11 @@ where
 
     /// Derives a track-specific decryption key.
     ///
-    /// The key is generated using:
-    /// 1. MD5 hash of the track ID
-    /// 2. XOR with the master decryption key (salt)
-    ///
-    /// # Arguments
-    ///
-    /// * `track_id` - Unique identifier for the track
-    /// * `salt` - Master decryption key
-    ///
-    /// # Returns
-    ///
-    /// A new `Key` specific to this track for decryption.
+    /// See [`key_for_track_id`](crate::decrypt::key_for_track_id).
     #[must_use]
+    #[inline]
     pub fn key_for_track_id(track_id: TrackId, salt: &Key) -> Key {
-        let track_hash = format!("{:x}", Md5::digest(track_id.to_string()));
-        let track_hash = track_hash.as_bytes();
-
-        let mut key = RawKey::default();
-        for i in 0..KEY_LENGTH {
-            key[i] = track_hash[i] ^ track_hash[i + KEY_LENGTH] ^ salt[i];
-        }
-        Key(key)
+        key_for_track_id(track_id, salt)
     }
 
     /// Whether the track is encrypted.
@@ -458,6 +481,15 @@ where
                     cipher
                         .decrypt_padded_mut::<NoPadding>(&mut temp_buffer)
                         .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+
+                    // A wrong key does not fail decryption itself, but would make
+                    // the decoder fail obscurely on garbled audio.
+                    if block == 0 && !is_audio_header(&temp_buffer) {
+                        return Err(io::Error::new(
+                            io::ErrorKind::PermissionDenied,
+                            "decrypted data is not audio: the bf_secret is not valid",
+                        ));
+                    }
                 }
 
                 self.buffer = temp_buffer[..length].to_vec();
@@ -578,3 +610,32 @@ where
         Ok(amt)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn audio_header_accepts_id3_tag() {
+        assert!(is_audio_header(b"ID3\x04\x00\x00"));
+    }
+
+    #[test]
+    fn audio_header_accepts_flac_marker() {
+        assert!(is_audio_header(b"fLaC\x00\x00\x00\x22"));
+    }
+
+    #[test]
+    fn audio_header_accepts_mpeg_frame_sync() {
+        assert!(is_audio_header(&[0xff, 0xfb, 0x90, 0x64]));
+        assert!(is_audio_header(&[0xff, 0xf3, 0x90, 0x64]));
+    }
+
+    #[test]
+    fn audio_header_rejects_garbled_data() {
+        assert!(!is_audio_header(&[0x3a, 0x91, 0x0c, 0xd7]));
+        assert!(!is_audio_header(&[0xff, 0x1b, 0x90, 0x64]));
+        assert!(!is_audio_header(b"ID"));
+        assert!(!is_audio_header(&[]));
+    }
+}
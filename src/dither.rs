@@ -0,0 +1,310 @@
+//! Dithering for bit depth reduction.
+//!
+//! Audio is processed as 32-bit floating point samples. When the output device
+//! takes 16-bit or smaller integer samples, samples are rounded to that bit
+//! depth. For 24-bit FLAC or decoded MP3, rounding correlates the error with
+//! the signal, which is audible as distortion in quiet passages and fades.
+//!
+//! Dithering adds a small amount of noise before rounding. This turns the
+//! distortion into a constant noise floor at the level of the least
+//! significant bit, which is far less objectionable.
+//!
+//! # Tradeoffs
+//!
+//! * Volume control is applied before dithering, so the noise stays at one
+//!   least significant bit of the output at any volume.
+//! * 16-bit audio played on a 16-bit device is no longer bit-perfect when
+//!   dithered.
+//! * Floating point and 24/32-bit integer outputs are never dithered.
+//!
+//! # Example
+//!
+//! ```rust
+//! use pleezer::dither::{dither, Dither, Volume};
+//!
+//! let volume = Volume::default();
+//! let dithered = dither(source, Dither::Triangular, cpal::SampleFormat::I16, volume.clone());
+//! volume.set(0.5);
+//! ```
+
+use std::{
+    fmt,
+    str::FromStr,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use rodio::{source::SeekError, Source};
+
+use crate::error::Error;
+
+/// Dithering method for bit depth reduction.
+#[derive(Copy, Clone, Debug, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Dither {
+    /// No dithering: samples are rounded to the output bit depth.
+    #[default]
+    None,
+
+    /// Triangular probability density function (TPDF) dither of ±1 LSB.
+    ///
+    /// Fully decorrelates the rounding error from the signal.
+    Triangular,
+}
+
+/// Formats the dithering method as its lowercase name.
+impl fmt::Display for Dither {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::None => write!(f, "none"),
+            Self::Triangular => write!(f, "triangular"),
+        }
+    }
+}
+
+/// Parses a dithering method from its name, case-insensitively.
+///
+/// # Errors
+///
+/// Returns error if the name is not `none` or `triangular`.
+impl FromStr for Dither {
+    type Err = Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "none" => Ok(Self::None),
+            "triangular" => Ok(Self::Triangular),
+            _ => Err(Error::invalid_argument(format!(
+                "invalid dither {s}: expected none or triangular"
+            ))),
+        }
+    }
+}
+
+/// Output volume shared between the player and the dithering filter.
+///
+/// Cloning yields a handle to the same volume, so that it can be changed
+/// while the filter plays. Defaults to unity gain.
+#[derive(Clone, Debug)]
+pub struct Volume(Arc<AtomicU32>);
+
+impl Volume {
+    /// Creates a volume control at the given amplitude.
+    #[must_use]
+    pub fn new(volume: f32) -> Self {
+        Self(Arc::new(AtomicU32::new(volume.to_bits())))
+    }
+
+    /// Returns the current amplitude.
+    #[must_use]
+    #[inline]
+    pub fn get(&self) -> f32 {
+        f32::from_bits(self.0.load(Ordering::Relaxed))
+    }
+
+    /// Sets the amplitude, taking effect from the next sample.
+    #[inline]
+    pub fn set(&self, volume: f32) {
+        self.0.store(volume.to_bits(), Ordering::Relaxed);
+    }
+}
+
+impl Default for Volume {
+    fn default() -> Self {
+        Self::new(1.0)
+    }
+}
+
+/// Creates a dithering filter for an output sample format.
+///
+/// # Arguments
+///
+/// * `input` - Audio source to process
+/// * `dither` - Dithering method
+/// * `sample_format` - Sample format of the output device
+/// * `volume` - Output volume, applied before the noise is added
+///
+/// # Returns
+///
+/// A `Dithered` filter that scales samples by the volume and adds noise of
+/// one least significant bit of the output sample format. Adds no noise when
+/// dithering is disabled or the output is not a 16-bit or smaller integer
+/// format.
+pub fn dither<I>(
+    input: I,
+    dither: Dither,
+    sample_format: cpal::SampleFormat,
+    volume: Volume,
+) -> Dithered<I>
+where
+    I: Source<Item = f32>,
+{
+    let bits = sample_format.sample_size() * 8;
+    let amplitude = if dither == Dither::Triangular && !sample_format.is_float() && bits <= 16 {
+        // Full scale is -1.0 to 1.0, so one LSB is two divided by 2^bits.
+        1.0 / f32::from(1_u16 << (bits - 1))
+    } else {
+        0.0
+    };
+
+    Dithered {
+        input,
+        amplitude,
+        volume,
+    }
+}
+
+/// Audio filter that adds triangular dither noise.
+///
+/// # Type Parameters
+///
+/// * `I` - Input audio source type
+#[derive(Clone, Debug)]
+pub struct Dithered<I> {
+    /// Input audio source.
+    input: I,
+
+    /// Peak amplitude of the noise, or zero to add no noise.
+    amplitude: f32,
+
+    /// Output volume.
+    volume: Volume,
+}
+
+impl<I> Dithered<I>
+where
+    I: Source<Item = f32>,
+{
+    /// Returns whether noise is added to the samples.
+    #[must_use]
+    #[inline]
+    pub fn is_active(&self) -> bool {
+        self.amplitude > 0.0
+    }
+
+    /// Returns a reference to the inner audio source.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.input
+    }
+
+    /// Returns a mutable reference to the inner audio source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    /// Consumes the filter and returns the inner audio source.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input
+    }
+}
+
+impl<I> Iterator for Dithered<I>
+where
+    I: Source<Item = f32>,
+{
+    type Item = f32;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let sample = self.input.next()? * self.volume.get();
+        if self.amplitude == 0.0 {
+            return Some(sample);
+        }
+
+        // Difference of two uniform distributions yields triangular noise.
+        Some(sample + (fastrand::f32() - fastrand::f32()) * self.amplitude)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+impl<I> Source for Dithered<I>
+where
+    I: Source<Item = f32>,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        self.input.current_frame_len()
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+
+    #[inline]
+    fn try_seek(&mut self, pos: Duration) -> Result<(), SeekError> {
+        self.input.try_seek(pos)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rodio::buffer::SamplesBuffer;
+
+    fn source() -> SamplesBuffer<f32> {
+        SamplesBuffer::new(2, 44_100, vec![0.5; 1024])
+    }
+
+    #[test]
+    fn volume_is_applied_without_dither() {
+        let output = dither(
+            source(),
+            Dither::Triangular,
+            cpal::SampleFormat::F32,
+            Volume::new(0.5),
+        );
+        assert!(!output.is_active());
+        assert!(output.into_iter().all(|sample| sample == 0.25));
+    }
+
+    #[test]
+    fn volume_is_applied_before_dither() {
+        let output = dither(
+            source(),
+            Dither::Triangular,
+            cpal::SampleFormat::I16,
+            Volume::new(0.0),
+        );
+        assert!(output.is_active());
+
+        // Silence stays within one LSB of noise, instead of being scaled down.
+        let lsb = 1.0 / 32_768.0;
+        let samples: Vec<f32> = output.collect();
+        assert!(samples.iter().all(|sample| sample.abs() <= lsb));
+        assert!(samples.iter().any(|sample| *sample != 0.0));
+    }
+
+    #[test]
+    fn volume_changes_while_playing() {
+        let volume = Volume::default();
+        let mut output = dither(
+            source(),
+            Dither::None,
+            cpal::SampleFormat::I16,
+            volume.clone(),
+        );
+        assert_eq!(output.next(), Some(0.5));
+        volume.set(0.0);
+        assert_eq!(output.next(), Some(0.0));
+    }
+}
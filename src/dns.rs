@@ -0,0 +1,679 @@
+//! Hostname resolution through a custom nameserver or DNS-over-HTTPS.
+//!
+//! By default, hostnames are resolved by the operating system. In censored or
+//! split-horizon networks the system resolver may not resolve Deezer hosts
+//! correctly. This module resolves them through either:
+//! * A nameserver over plain DNS, like `1.1.1.1` or `9.9.9.9:53`
+//! * DNS-over-HTTPS (RFC 8484), like `https://cloudflare-dns.com/dns-query`
+//!
+//! The resolver is used by the HTTP clients for the gateway and downloads, and
+//! for the websocket connection. When connecting through an HTTPS proxy, the
+//! proxy resolves the websocket host instead.
+//!
+//! # Tradeoffs
+//!
+//! * Plain DNS is unencrypted: queries are not sent to the system resolver,
+//!   but can still be observed and tampered with on the network path.
+//! * DNS-over-HTTPS encrypts queries and bypasses interception, but reveals
+//!   every lookup to the DoH provider.
+//! * The DoH server itself is resolved by the system resolver. Use a URL with
+//!   an IP address when system DNS cannot be relied on at all.
+//! * Lookups are not cached. Connections are kept alive, so lookups only
+//!   happen when opening new connections.
+//! * Only IPv4 addresses are resolved, unless outgoing connections are bound
+//!   to an IPv6 address.
+//!
+//! # Example
+//!
+//! ```rust
+//! use pleezer::dns::{Nameserver, Resolver};
+//!
+//! let nameserver: Nameserver = "https://cloudflare-dns.com/dns-query".parse()?;
+//! let resolver = Resolver::new(nameserver, "0.0.0.0".parse()?)?;
+//!
+//! let addresses = resolver.lookup("api.deezer.com").await?;
+//! ```
+
+use std::{
+    fmt,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    str::FromStr,
+    time::Duration,
+};
+
+use reqwest::{
+    dns::{Addrs, Name, Resolve, Resolving},
+    header::{HeaderValue, ACCEPT, CONTENT_TYPE},
+};
+use tokio::net::{TcpSocket, TcpStream, UdpSocket};
+use url::Url;
+
+use crate::error::{Error, Result};
+
+/// Nameserver to resolve hostnames with.
+#[derive(Clone, Debug, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Nameserver {
+    /// Plain DNS over UDP.
+    Udp(SocketAddr),
+
+    /// DNS-over-HTTPS (RFC 8484).
+    Https(Url),
+}
+
+impl Nameserver {
+    /// Default port for plain DNS.
+    pub const DNS_PORT: u16 = 53;
+}
+
+/// Parses a nameserver from an IP address, socket address or URL.
+///
+/// Formats:
+/// * `1.1.1.1` or `2606:4700:4700::1111` - plain DNS on port 53
+/// * `1.1.1.1:5353` or `[2606:4700:4700::1111]:5353` - plain DNS on a custom port
+/// * `https://cloudflare-dns.com/dns-query` - DNS-over-HTTPS
+///
+/// # Errors
+///
+/// Returns error if the string is neither a valid address nor an HTTPS URL.
+impl FromStr for Nameserver {
+    type Err = Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        if s.starts_with("https://") {
+            return Ok(Self::Https(Url::parse(s)?));
+        }
+
+        if let Ok(ip) = s.parse::<IpAddr>() {
+            return Ok(Self::Udp(SocketAddr::new(ip, Self::DNS_PORT)));
+        }
+
+        s.parse::<SocketAddr>().map(Self::Udp).map_err(|_| {
+            Error::invalid_argument(format!(
+                "invalid resolver {s}: expected an IP address or https:// URL"
+            ))
+        })
+    }
+}
+
+/// Formats the nameserver as its address or URL.
+impl fmt::Display for Nameserver {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Udp(addr) => write!(f, "{addr}"),
+            Self::Https(url) => write!(f, "{url}"),
+        }
+    }
+}
+
+/// Hostname resolver using a custom nameserver.
+///
+/// Can be installed into `reqwest` clients through [`Resolve`], or used
+/// directly to open TCP connections.
+#[derive(Clone, Debug)]
+pub struct Resolver {
+    /// Nameserver to send queries to.
+    nameserver: Nameserver,
+
+    /// Local address to send queries from.
+    ///
+    /// Also determines whether IPv4 or IPv6 addresses are resolved.
+    bind_address: IpAddr,
+
+    /// HTTP client for DNS-over-HTTPS queries.
+    client: reqwest::Client,
+}
+
+impl Resolver {
+    /// Duration to wait for a nameserver to respond.
+    const TIMEOUT: Duration = Duration::from_secs(5);
+
+    /// Maximum size of a plain DNS response over UDP.
+    ///
+    /// Larger responses are truncated by the nameserver.
+    const UDP_PAYLOAD_MAX: usize = 512;
+
+    /// Length of the DNS message header.
+    const HEADER_LEN: usize = 12;
+
+    /// Header flag requesting recursive resolution.
+    const FLAG_RECURSION_DESIRED: u16 = 0x0100;
+
+    /// Header flag set when the response was truncated.
+    const FLAG_TRUNCATED: u16 = 0x0200;
+
+    /// Header mask of the response code.
+    const RCODE_MASK: u16 = 0x000f;
+
+    /// Response code for a non-existent domain.
+    const RCODE_NXDOMAIN: u16 = 3;
+
+    /// Record type of an IPv4 address.
+    const RECORD_A: u16 = 1;
+
+    /// Record type of an IPv6 address.
+    const RECORD_AAAA: u16 = 28;
+
+    /// Record class of the internet.
+    const CLASS_IN: u16 = 1;
+
+    /// Content type of DNS-over-HTTPS messages.
+    const CONTENT_TYPE_DNS: HeaderValue = HeaderValue::from_static("application/dns-message");
+
+    /// Creates a new resolver.
+    ///
+    /// # Arguments
+    ///
+    /// * `nameserver` - Nameserver to send queries to
+    /// * `bind_address` - Local address to send queries from
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the HTTP client for DNS-over-HTTPS cannot be created.
+    pub fn new(nameserver: Nameserver, bind_address: IpAddr) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .connect_timeout(Self::TIMEOUT)
+            .timeout(Self::TIMEOUT)
+            .local_address(bind_address)
+            .build()?;
+
+        Ok(Self {
+            nameserver,
+            bind_address,
+            client,
+        })
+    }
+
+    /// Resolves a hostname into IP addresses.
+    ///
+    /// IP address literals are returned as-is without a query.
+    ///
+    /// # Arguments
+    ///
+    /// * `host` - Hostname to resolve
+    ///
+    /// # Errors
+    ///
+    /// Returns error if:
+    /// * Hostname is invalid
+    /// * Nameserver cannot be reached or times out
+    /// * Response is malformed or reports an error
+    /// * No addresses were found
+    pub async fn lookup(&self, host: &str) -> Result<Vec<IpAddr>> {
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        if let Ok(ip) = host.parse::<IpAddr>() {
+            return Ok(vec![ip]);
+        }
+
+        let record = if self.bind_address.is_ipv6() {
+            Self::RECORD_AAAA
+        } else {
+            Self::RECORD_A
+        };
+
+        let addresses = match &self.nameserver {
+            Nameserver::Udp(addr) => {
+                let id = fastrand::u16(..);
+                let query = Self::query(id, host, record)?;
+                Self::parse(id, &self.exchange_udp(*addr, &query).await?, record)?
+            }
+            Nameserver::Https(url) => {
+                // RFC 8484 recommends an ID of zero for cache friendliness.
+                let query = Self::query(0, host, record)?;
+                Self::parse(0, &self.exchange_https(url, query).await?, record)?
+            }
+        };
+
+        if addresses.is_empty() {
+            return Err(Error::not_found(format!("no addresses found for {host}")));
+        }
+
+        trace!(
+            "resolved {host} to {addresses:?} through {}",
+            self.nameserver
+        );
+        Ok(addresses)
+    }
+
+    /// Opens a TCP connection to the host of a target URL.
+    ///
+    /// Tries each resolved address in turn until one connects.
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - Target URL to connect to
+    ///
+    /// # Errors
+    ///
+    /// Returns error if:
+    /// * Target URL is invalid
+    /// * Hostname cannot be resolved
+    /// * No address can be connected to
+    pub async fn connect_async(&self, target: &str) -> Result<TcpStream> {
+        let (host, port) = host_port(target)?;
+        let addresses = self
+            .lookup(&host)
+            .await?
+            .into_iter()
+            .map(|ip| SocketAddr::new(ip, port));
+
+        connect(addresses, self.bind_address).await
+    }
+
+    /// Sends a query over plain DNS and returns the response.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the socket fails or the nameserver times out.
+    async fn exchange_udp(&self, nameserver: SocketAddr, query: &[u8]) -> Result<Vec<u8>> {
+        // Bind to the unspecified address when the nameserver is of the other
+        // address family.
+        let local_address = match (self.bind_address, nameserver) {
+            (IpAddr::V4(_), SocketAddr::V6(_)) => IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+            (IpAddr::V6(_), SocketAddr::V4(_)) => IpAddr::V4(Ipv4Addr::UNSPECIFIED),
+            (local_address, _) => local_address,
+        };
+
+        let socket = UdpSocket::bind(SocketAddr::new(local_address, 0)).await?;
+        socket.connect(nameserver).await?;
+        socket.send(query).await?;
+
+        let mut response = vec![0; Self::UDP_PAYLOAD_MAX];
+        let len = tokio::time::timeout(Self::TIMEOUT, socket.recv(&mut response)).await??;
+        response.truncate(len);
+
+        Ok(response)
+    }
+
+    /// Sends a query over DNS-over-HTTPS and returns the response.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the request fails or the server returns an error status.
+    async fn exchange_https(&self, url: &Url, query: Vec<u8>) -> Result<Vec<u8>> {
+        let response = self
+            .client
+            .post(url.clone())
+            .header(CONTENT_TYPE, Self::CONTENT_TYPE_DNS)
+            .header(ACCEPT, Self::CONTENT_TYPE_DNS)
+            .body(query)
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(response.bytes().await?.to_vec())
+    }
+
+    /// Builds a query for a single record of a hostname.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the hostname has empty or too long labels.
+    fn query(id: u16, host: &str, record: u16) -> Result<Vec<u8>> {
+        let mut query = Vec::with_capacity(Self::HEADER_LEN + host.len() + 6);
+        query.extend_from_slice(&id.to_be_bytes());
+        query.extend_from_slice(&Self::FLAG_RECURSION_DESIRED.to_be_bytes());
+        // One question, no answer, authority or additional records.
+        query.extend_from_slice(&[0, 1, 0, 0, 0, 0, 0, 0]);
+
+        for label in host.trim_end_matches('.').split('.') {
+            let len = u8::try_from(label.len())
+                .ok()
+                .filter(|len| (1..=63).contains(len))
+                .ok_or_else(|| Error::invalid_argument(format!("invalid hostname {host}")))?;
+            query.push(len);
+            query.extend_from_slice(label.as_bytes());
+        }
+        query.push(0);
+
+        query.extend_from_slice(&record.to_be_bytes());
+        query.extend_from_slice(&Self::CLASS_IN.to_be_bytes());
+
+        Ok(query)
+    }
+
+    /// Parses the addresses of a record type from a response.
+    ///
+    /// Other records, like CNAME records, are skipped.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if:
+    /// * Response is truncated or does not match the query
+    /// * Nameserver reports an error
+    fn parse(id: u16, response: &[u8], record: u16) -> Result<Vec<IpAddr>> {
+        let read_u16 = |offset: usize| {
+            response
+                .get(offset..offset + 2)
+                .map(|bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+                .ok_or_else(|| Error::data_loss("dns response truncated"))
+        };
+
+        if read_u16(0)? != id {
+            return Err(Error::data_loss("dns response does not match query"));
+        }
+
+        let flags = read_u16(2)?;
+        if flags & Self::FLAG_TRUNCATED != 0 {
+            return Err(Error::unavailable("dns response truncated by nameserver"));
+        }
+        match flags & Self::RCODE_MASK {
+            0 => {}
+            Self::RCODE_NXDOMAIN => return Err(Error::not_found("domain does not exist")),
+            rcode => {
+                return Err(Error::unavailable(format!(
+                    "nameserver returned response code {rcode}"
+                )))
+            }
+        }
+
+        let questions = read_u16(4)?;
+        let answers = read_u16(6)?;
+
+        let mut offset = Self::HEADER_LEN;
+        for _ in 0..questions {
+            // Skip the name, type and class.
+            offset = Self::skip_name(response, offset)? + 4;
+        }
+
+        let mut addresses = Vec::new();
+        for _ in 0..answers {
+            offset = Self::skip_name(response, offset)?;
+            let typ = read_u16(offset)?;
+            let class = read_u16(offset + 2)?;
+            // Skip the time to live.
+            let len = usize::from(read_u16(offset + 8)?);
+            offset += 10;
+
+            let data = response
+                .get(offset..offset + len)
+                .ok_or_else(|| Error::data_loss("dns response truncated"))?;
+            offset += len;
+
+            if typ != record || class != Self::CLASS_IN {
+                continue;
+            }
+
+            if let Ok(octets) = <[u8; 4]>::try_from(data) {
+                addresses.push(IpAddr::from(octets));
+            } else if let Ok(octets) = <[u8; 16]>::try_from(data) {
+                addresses.push(IpAddr::from(octets));
+            }
+        }
+
+        Ok(addresses)
+    }
+
+    /// Returns the offset after a possibly compressed name.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the name runs past the end of the response.
+    fn skip_name(response: &[u8], mut offset: usize) -> Result<usize> {
+        loop {
+            let len = *response
+                .get(offset)
+                .ok_or_else(|| Error::data_loss("dns response truncated"))?;
+            match len {
+                0 => return Ok(offset + 1),
+                // A pointer to a name elsewhere in the response ends the name.
+                len if len & 0xc0 == 0xc0 => return Ok(offset + 2),
+                len => offset += 1 + usize::from(len),
+            }
+        }
+    }
+}
+
+/// Resolves hostnames for `reqwest` clients.
+///
+/// Ports are set by `reqwest` when connecting.
+impl Resolve for Resolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let resolver = self.clone();
+        Box::pin(async move {
+            let addresses = resolver.lookup(name.as_str()).await?;
+            let addrs: Addrs = Box::new(addresses.into_iter().map(|ip| SocketAddr::new(ip, 0)));
+            Ok::<_, Box<dyn std::error::Error + Send + Sync>>(addrs)
+        })
+    }
+}
+
+/// Opens a TCP connection to the host of a target URL from a local address.
+///
+/// Resolves the host through the system resolver. Use
+/// [`Resolver::connect_async`] to resolve through a custom nameserver.
+///
+/// # Arguments
+///
+/// * `target` - Target URL to connect to
+/// * `bind_address` - Local address to connect from
+///
+/// # Errors
+///
+/// Returns error if:
+/// * Target URL is invalid
+/// * Hostname cannot be resolved
+/// * No address can be connected to
+pub async fn connect_async(target: &str, bind_address: IpAddr) -> Result<TcpStream> {
+    let (host, port) = host_port(target)?;
+    let addresses = tokio::net::lookup_host((host.as_str(), port)).await?;
+    connect(addresses, bind_address).await
+}
+
+/// Opens a TCP connection to the first reachable address from a local address.
+///
+/// Tries each address in turn until one connects. Unless `bind_address` is
+/// unspecified, addresses of the other address family are skipped, as they
+/// cannot be reached from it.
+///
+/// # Arguments
+///
+/// * `addresses` - Addresses to connect to, in order of preference
+/// * `bind_address` - Local address to connect from
+///
+/// # Errors
+///
+/// Returns error if no address can be connected to.
+pub async fn connect(
+    addresses: impl IntoIterator<Item = SocketAddr>,
+    bind_address: IpAddr,
+) -> Result<TcpStream> {
+    let mut last_error = None;
+    for address in addresses {
+        if !bind_address.is_unspecified() && address.is_ipv4() != bind_address.is_ipv4() {
+            continue;
+        }
+
+        let socket = if address.is_ipv4() {
+            TcpSocket::new_v4()?
+        } else {
+            TcpSocket::new_v6()?
+        };
+        if !bind_address.is_unspecified() {
+            socket.bind(SocketAddr::new(bind_address, 0))?;
+        }
+
+        match socket.connect(address).await {
+            Ok(tcp_stream) => return Ok(tcp_stream),
+            Err(e) => last_error = Some(e),
+        }
+    }
+
+    Err(last_error.map_or_else(
+        || Error::not_found(format!("no addresses reachable from {bind_address}")),
+        Error::from,
+    ))
+}
+
+/// Extracts the host and port of a target URL.
+///
+/// # Errors
+///
+/// Returns error if the URL is invalid or has no host or known port.
+fn host_port(target: &str) -> Result<(String, u16)> {
+    let target_url = Url::parse(target)?;
+    let host = target_url
+        .host_str()
+        .ok_or_else(|| Error::invalid_argument("target host not available"))?;
+    let port = target_url
+        .port_or_known_default()
+        .ok_or_else(|| Error::invalid_argument("target port not available"))?;
+
+    Ok((host.to_string(), port))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+
+    const ID: u16 = 0x1234;
+
+    /// Builds a response to a query for `example.com`, with the given flags
+    /// and answers of type, data and name pointer to the question.
+    fn response(flags: u16, answers: &[(u16, &[u8])]) -> Vec<u8> {
+        let mut response = Resolver::query(ID, "example.com", Resolver::RECORD_A).unwrap();
+        response[2..4].copy_from_slice(&(0x8000 | flags).to_be_bytes());
+        let count = u16::try_from(answers.len()).unwrap();
+        response[6..8].copy_from_slice(&count.to_be_bytes());
+
+        for (typ, data) in answers {
+            // Compressed name pointing to the question name.
+            response.extend_from_slice(&[0xc0, 0x0c]);
+            response.extend_from_slice(&typ.to_be_bytes());
+            response.extend_from_slice(&Resolver::CLASS_IN.to_be_bytes());
+            response.extend_from_slice(&300_u32.to_be_bytes());
+            let len = u16::try_from(data.len()).unwrap();
+            response.extend_from_slice(&len.to_be_bytes());
+            response.extend_from_slice(data);
+        }
+
+        response
+    }
+
+    #[test]
+    fn query_encodes_header_and_question() {
+        let query = Resolver::query(ID, "example.com.", Resolver::RECORD_AAAA).unwrap();
+        assert_eq!(
+            query,
+            [
+                &[0x12, 0x34, 0x01, 0x00, 0, 1, 0, 0, 0, 0, 0, 0][..],
+                b"\x07example\x03com\x00",
+                &[0, 28, 0, 1],
+            ]
+            .concat()
+        );
+    }
+
+    #[test]
+    fn query_rejects_invalid_labels() {
+        let long = "a".repeat(64);
+        for host in ["", "example..com", long.as_str()] {
+            let e = Resolver::query(ID, host, Resolver::RECORD_A).unwrap_err();
+            assert_eq!(e.kind, ErrorKind::InvalidArgument, "{host}");
+        }
+    }
+
+    #[test]
+    fn parse_returns_addresses_of_record_type() {
+        let response = response(
+            0,
+            &[
+                // CNAME record
+                (5, &b"\x03www\xc0\x0c"[..]),
+                (Resolver::RECORD_A, &[192, 0, 2, 1][..]),
+                (Resolver::RECORD_A, &[192, 0, 2, 2][..]),
+            ],
+        );
+        assert_eq!(
+            Resolver::parse(ID, &response, Resolver::RECORD_A).unwrap(),
+            [
+                IpAddr::from(Ipv4Addr::new(192, 0, 2, 1)),
+                IpAddr::from(Ipv4Addr::new(192, 0, 2, 2)),
+            ]
+        );
+    }
+
+    #[test]
+    fn parse_returns_ipv6_addresses() {
+        let address = Ipv6Addr::new(0x2001, 0xdb8, 0, 0, 0, 0, 0, 1);
+        let response = response(0, &[(Resolver::RECORD_AAAA, &address.octets()[..])]);
+        assert_eq!(
+            Resolver::parse(ID, &response, Resolver::RECORD_AAAA).unwrap(),
+            [IpAddr::from(address)]
+        );
+    }
+
+    #[test]
+    fn parse_skips_other_record_types() {
+        let response = response(0, &[(Resolver::RECORD_A, &[192, 0, 2, 1][..])]);
+        assert!(Resolver::parse(ID, &response, Resolver::RECORD_AAAA)
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn parse_rejects_mismatched_id() {
+        let response = response(0, &[(Resolver::RECORD_A, &[192, 0, 2, 1][..])]);
+        let e = Resolver::parse(ID + 1, &response, Resolver::RECORD_A).unwrap_err();
+        assert_eq!(e.kind, ErrorKind::DataLoss);
+    }
+
+    #[test]
+    fn parse_rejects_truncated_responses() {
+        let response = response(0, &[(Resolver::RECORD_A, &[192, 0, 2, 1][..])]);
+        for len in [0, 1, Resolver::HEADER_LEN, response.len() - 1] {
+            let e = Resolver::parse(ID, &response[..len], Resolver::RECORD_A).unwrap_err();
+            assert_eq!(e.kind, ErrorKind::DataLoss, "truncated at {len}");
+        }
+    }
+
+    #[test]
+    fn parse_rejects_truncated_flag() {
+        let response = response(Resolver::FLAG_TRUNCATED, &[]);
+        let e = Resolver::parse(ID, &response, Resolver::RECORD_A).unwrap_err();
+        assert_eq!(e.kind, ErrorKind::Unavailable);
+    }
+
+    #[test]
+    fn parse_reports_response_codes() {
+        let response_nxdomain = response(Resolver::RCODE_NXDOMAIN, &[]);
+        let e = Resolver::parse(ID, &response_nxdomain, Resolver::RECORD_A).unwrap_err();
+        assert_eq!(e.kind, ErrorKind::NotFound);
+
+        // Server failure
+        let response_servfail = response(2, &[]);
+        let e = Resolver::parse(ID, &response_servfail, Resolver::RECORD_A).unwrap_err();
+        assert_eq!(e.kind, ErrorKind::Unavailable);
+    }
+
+    #[test]
+    fn parse_rejects_answer_past_end() {
+        let mut response = response(0, &[(Resolver::RECORD_A, &[192, 0, 2, 1][..])]);
+        // Claim more answers than the response holds.
+        response[6..8].copy_from_slice(&2_u16.to_be_bytes());
+        let e = Resolver::parse(ID, &response, Resolver::RECORD_A).unwrap_err();
+        assert_eq!(e.kind, ErrorKind::DataLoss);
+    }
+
+    #[tokio::test]
+    async fn connect_binds_to_bind_address() {
+        let loopback = IpAddr::V4(Ipv4Addr::LOCALHOST);
+        let listener = tokio::net::TcpListener::bind(SocketAddr::new(loopback, 0))
+            .await
+            .unwrap();
+        let address = listener.local_addr().unwrap();
+
+        let tcp_stream = connect([address], loopback).await.unwrap();
+        assert_eq!(tcp_stream.local_addr().unwrap().ip(), loopback);
+        assert_eq!(tcp_stream.peer_addr().unwrap(), address);
+    }
+
+    #[tokio::test]
+    async fn connect_skips_other_address_family() {
+        let address = SocketAddr::new(IpAddr::V6(Ipv6Addr::LOCALHOST), 443);
+        let e = connect([address], IpAddr::V4(Ipv4Addr::LOCALHOST))
+            .await
+            .unwrap_err();
+        assert_eq!(e.kind, ErrorKind::NotFound);
+    }
+}
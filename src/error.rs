@@ -12,6 +12,20 @@
 //! * Server errors (500, 501, 503)
 //! * Timeouts and cancellation (499, 504)
 //!
+//! # Exit Codes
+//!
+//! Each error kind maps to a process exit code, so that scripts and
+//! supervisors can decide whether a restart makes sense:
+//!
+//! | Code | Category       | Error kinds                                                               | Restart? |
+//! |------|----------------|---------------------------------------------------------------------------|----------|
+//! | 0    | Clean shutdown | -                                                                         | -        |
+//! | 1    | Other          | `Unknown`, `Internal`, `DataLoss`                                         | Maybe    |
+//! | 2    | Configuration  | `InvalidArgument`, `OutOfRange`, `FailedPrecondition`, `NotFound`, `AlreadyExists` | No |
+//! | 3    | Authentication | `Unauthenticated`, `PermissionDenied`                                     | No       |
+//! | 4    | Account limits | `ResourceExhausted`, `Unimplemented`                                      | No       |
+//! | 5    | Network        | `Unavailable`, `DeadlineExceeded`, `Cancelled`, `Aborted`                 | Yes      |
+//!
 //! # Example
 //!
 //! ```rust
@@ -166,6 +180,31 @@ pub enum ErrorKind {
     DataLoss = 15,
 }
 
+impl ErrorKind {
+    /// Returns the process exit code for this error kind.
+    ///
+    /// See the [module documentation](self#exit-codes) for the mapping.
+    /// Exit code 0 is reserved for clean shutdown and never returned.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// assert_eq!(ErrorKind::PermissionDenied.exit_code(), 3);
+    /// assert_eq!(ErrorKind::Unavailable.exit_code(), 5);
+    /// ```
+    #[must_use]
+    pub const fn exit_code(self) -> i32 {
+        use ErrorKind::*;
+        match self {
+            Unknown | Internal | DataLoss => 1,
+            InvalidArgument | OutOfRange | FailedPrecondition | NotFound | AlreadyExists => 2,
+            Unauthenticated | PermissionDenied => 3,
+            ResourceExhausted | Unimplemented => 4,
+            Unavailable | DeadlineExceeded | Cancelled | Aborted => 5,
+        }
+    }
+}
+
 impl Error {
     /// Creates a new error with specified kind and details.
     ///
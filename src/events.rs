@@ -4,7 +4,7 @@
 //! and remote control operations. These events can be used to:
 //! * Monitor playback state changes
 //! * Track remote control connections
-//! * React to track changes
+//! * React to track and volume changes
 //!
 //! # Example
 //!
@@ -14,37 +14,55 @@
 //! fn handle_event(event: Event) {
 //!     match event {
 //!         Event::Play => println!("Playback started"),
-//!         Event::TrackChanged => println!("New track playing"),
-//!         Event::Connected => println!("Remote control connected"),
+//!         Event::TrackChanged(track) => println!("Now playing {}", track.artist),
+//!         Event::Connected(controller) => println!("Remote control connected from {controller}"),
 //!         // ... handle other events ...
 //!     }
 //! }
 //! ```
 
+use std::{fmt, time::Duration};
+
+use crate::{
+    protocol::{
+        connect::{AudioQuality, DeviceId},
+        Codec,
+    },
+    track::{Track, TrackId, TrackType},
+};
+
 /// Events that can be emitted by the Deezer Connect player or remote.
 ///
 /// These events represent significant state changes in playback
-/// or remote control status.
+/// or remote control status. Volumes and progress are carried in per mille,
+/// so that events can be compared exactly and hashed.
 ///
 /// # Events
 ///
-/// Events fall into two categories:
+/// Events fall into three categories:
 ///
 /// Playback Events:
 /// * [`Play`](Self::Play) - Playback starts
 /// * [`Pause`](Self::Pause) - Playback pauses
+/// * [`Buffering`](Self::Buffering) - Playback stalls waiting for data
+/// * [`Buffered`](Self::Buffered) - Playback no longer stalls
 /// * [`TrackChanged`](Self::TrackChanged) - Current track changes
+/// * [`Skipped`](Self::Skipped) - Track is skipped without playing in full
 ///
 /// Connection Events:
 /// * [`Connected`](Self::Connected) - Remote connects
 /// * [`Disconnected`](Self::Disconnected) - Remote disconnects
 ///
+/// Library Events:
+/// * [`Favorite`](Self::Favorite) - Song added to favorites
+/// * [`Unfavorite`](Self::Unfavorite) - Song removed from favorites
+///
 /// # Example
 ///
 /// ```rust
 /// use pleezer::events::Event;
 ///
-/// // Events can be copied and compared
+/// // Events can be cloned and compared
 /// let event = Event::Play;
 /// assert_eq!(event, Event::Play);
 /// assert_ne!(event, Event::Pause);
@@ -56,7 +74,7 @@
 ///     _ => "Other event",
 /// };
 /// ```
-#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Event {
     /// Playback has started.
     ///
@@ -70,21 +88,192 @@ pub enum Event {
     /// from the current position.
     Pause,
 
+    /// Playback has stalled waiting for data.
+    ///
+    /// Emitted when the current track plays faster than it downloads, and
+    /// playback has caught up with the downloaded audio.
+    Buffering,
+
+    /// Playback has resumed after buffering.
+    ///
+    /// Emitted when enough audio has been downloaded again, or when playback
+    /// stopped while buffering.
+    Buffered,
+
     /// Current track has changed.
     ///
     /// Emitted when switching to a different track, whether through
     /// manual selection, automatic progression, or remote control.
-    TrackChanged,
+    /// Emitted again once the track is loaded, with its format details.
+    ///
+    /// Contains a snapshot of the track at the time of the change.
+    TrackChanged(Box<TrackInfo>),
+
+    /// Track has been skipped.
+    ///
+    /// Emitted once when a track is marked unavailable, because it failed
+    /// to load, played longer than the maximum track duration, or its
+    /// download kept being truncated. Playback
+    /// moves on to the next track.
+    ///
+    /// Contains the ID of the track and why it was skipped.
+    Skipped(TrackId, SkipReason),
+
+    /// Volume has changed.
+    ///
+    /// Emitted when the volume is set to a different level, whether by the
+    /// remote controller or by applying the initial volume.
+    ///
+    /// Contains the new volume in per mille (0 to 1000), before logarithmic
+    /// scaling.
+    VolumeChanged(u16),
 
     /// Remote control has connected.
     ///
     /// Emitted when a Deezer client establishes a remote control
-    /// connection to this player.
-    Connected,
+    /// connection to this player. Contains the device ID of the
+    /// controller.
+    Connected(DeviceId),
 
     /// Remote control has disconnected.
     ///
     /// Emitted when a connected Deezer client ends its remote
     /// control session with this player.
     Disconnected,
+
+    /// Song has been added to the user's favorites.
+    ///
+    /// Contains the ID of the song.
+    Favorite(TrackId),
+
+    /// Song has been removed from the user's favorites.
+    ///
+    /// Contains the ID of the song.
+    Unfavorite(TrackId),
+
+    /// Another device has reported its playback.
+    ///
+    /// Emitted when eavesdropping with observed events enabled, for every
+    /// playback progress report between other devices on the account. The
+    /// player itself is not affected.
+    ///
+    /// Contains the reported playback state.
+    Observed(Box<ObservedPlayback>),
+}
+
+/// Reason for skipping a track.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum SkipReason {
+    /// Track could not be loaded.
+    Unavailable,
+
+    /// Track played longer than the maximum track duration.
+    TooLong,
+
+    /// Track download was truncated, also after reloading it.
+    Truncated,
+}
+
+impl fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Unavailable => write!(f, "unavailable"),
+            Self::TooLong => write!(f, "too_long"),
+            Self::Truncated => write!(f, "truncated"),
+        }
+    }
+}
+
+/// Snapshot of track metadata and format at the time of an event.
+///
+/// Decouples event consumers from the player's current track, which may
+/// already have changed again by the time an event is handled.
+///
+/// Format details are only known once the track has been loaded.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TrackInfo {
+    /// Track identifier.
+    pub id: TrackId,
+
+    /// Content type (song, episode or livestream).
+    pub typ: TrackType,
+
+    /// Track title, if any.
+    pub title: Option<String>,
+
+    /// Artist name, or podcast or station name.
+    pub artist: String,
+
+    /// Album title, for songs only.
+    pub album_title: Option<String>,
+
+    /// Cover art identifier.
+    pub cover_id: String,
+
+    /// Track duration, if known.
+    pub duration: Option<Duration>,
+
+    /// Audio quality of the download.
+    pub quality: AudioQuality,
+
+    /// Audio codec, if loaded.
+    pub codec: Option<Codec>,
+
+    /// Bitrate in kbps, if known.
+    pub bitrate: Option<usize>,
+
+    /// Sample rate in Hz, if loaded.
+    pub sample_rate: Option<u32>,
+
+    /// Bits per sample, if loaded and reported by the codec.
+    pub bits_per_sample: Option<u32>,
+
+    /// Number of audio channels, if loaded.
+    pub channels: Option<u16>,
+}
+
+/// Playback state reported by another device on the account.
+///
+/// Observed while eavesdropping, from the progress reports that players
+/// send to their controllers.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct ObservedPlayback {
+    /// Device that reported its playback.
+    pub device: DeviceId,
+
+    /// Track being played.
+    pub track_id: TrackId,
+
+    /// Whether playback is active.
+    pub is_playing: bool,
+
+    /// Playback position in the track in per mille (0 to 1000), if known.
+    pub progress: Option<u16>,
+
+    /// Track duration, if known.
+    pub duration: Option<Duration>,
+
+    /// Volume level of the device in per mille (0 to 1000).
+    pub volume: u16,
+}
+
+/// Takes a snapshot of a track.
+impl From<&Track> for TrackInfo {
+    fn from(track: &Track) -> Self {
+        Self {
+            id: track.id(),
+            typ: track.typ(),
+            title: track.title().map(ToOwned::to_owned),
+            artist: track.artist().to_owned(),
+            album_title: track.album_title().map(ToOwned::to_owned),
+            cover_id: track.cover_id().to_owned(),
+            duration: track.playback_duration(),
+            quality: track.quality(),
+            codec: track.codec(),
+            bitrate: track.bitrate(),
+            sample_rate: track.sample_rate,
+            bits_per_sample: track.bits_per_sample,
+            channels: track.channels,
+        }
+    }
 }
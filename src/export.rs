@@ -0,0 +1,51 @@
+//! Queue export to standard playlist formats.
+//!
+//! Saves the complete queue on demand, so that users can keep what they were
+//! listening to. Unlike the [now-playing file](crate::now_playing), which is
+//! rewritten continuously and lists only the tracks still to come, an export
+//! holds every track in the queue in playback order:
+//! * M3U - Extended playlist with the Deezer web page of every track, in
+//!   UTF-8 like M3U8
+//! * JSON - The position of the current track and the complete queue with
+//!   metadata
+//!
+//! On Unix, exports are triggered by sending `SIGUSR1` to the process.
+//!
+//! # Example
+//!
+//! ```rust
+//! use pleezer::{export, now_playing::Format};
+//!
+//! export::write(path, Format::M3u, player.queue(), player.position())?;
+//! ```
+
+use std::path::Path;
+
+use crate::{
+    error::Result,
+    now_playing::{self, Format},
+    track::Track,
+};
+
+/// Writes the complete queue to a file atomically.
+///
+/// # Arguments
+///
+/// * `path` - File to write
+/// * `format` - File format
+/// * `queue` - Tracks in the queue, in playback order
+/// * `position` - Index of the current track in the queue
+///
+/// # Errors
+///
+/// Returns error if:
+/// * Serialization fails
+/// * The file cannot be written
+pub fn write(path: &Path, format: Format, queue: &[Track], position: usize) -> Result<()> {
+    let contents = match format {
+        Format::M3u => now_playing::m3u(queue),
+        Format::Json => now_playing::json(queue, position)?,
+    };
+
+    now_playing::write_atomic(path, contents)
+}
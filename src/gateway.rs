@@ -10,6 +10,8 @@
 //! * Media streaming configuration
 //! * Queue and track information
 //! * Flow recommendations
+//! * Favorite songs, also as a playable queue
+//! * Playlist songs
 //!
 //! # Authentication Flow
 //!
@@ -56,7 +58,7 @@
 //! let user_data = gateway.refresh().await?;
 //! ```
 
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime};
 
 use cookie_store::RawCookie;
 use futures_util::TryFutureExt;
@@ -71,7 +73,7 @@ use url::Url;
 use crate::{
     arl::Arl,
     config::{Config, Credentials},
-    error::{Error, ErrorKind, Result},
+    error::{Error, Result},
     http::Client as HttpClient,
     protocol::{
         self, auth,
@@ -81,18 +83,23 @@ use crate::{
         },
         gateway::{
             self,
+            favorites::{self, AddFavorite, FavoriteSong, RemoveFavorite},
             list_data::{
                 episodes::{self, EpisodeData},
                 livestream::{self, LivestreamData},
                 songs::{self, SongData},
                 ListData,
             },
+            listen::{self, Listen},
+            playlist::{self, PlaylistSong},
+            search::{self, SearchAlbum, SearchArtist, SearchPlaylist, SearchTrack},
             user_radio::{self, UserRadio},
-            MediaUrl, Queue, Response, UserData,
+            MediaUrl, Method, Queue, Response, SearchResults, SearchType, UserData,
         },
         Codec,
     },
     tokens::UserToken,
+    track::{TrackId, TrackType},
 };
 
 /// Gateway client for Deezer API access.
@@ -114,6 +121,19 @@ pub struct Gateway {
 
     /// Client identifier for API requests.
     client_id: usize,
+
+    /// Extra headers to send with every gateway request.
+    headers: HeaderMap,
+
+    /// Cached IDs of the favorite songs of the user.
+    ///
+    /// Invalidated when favorites are changed through this gateway.
+    favorite_ids: Option<Vec<TrackId>>,
+
+    /// Cached favorite songs of the user.
+    ///
+    /// Invalidated when favorites are changed through this gateway.
+    favorites: Option<Queue>,
 }
 
 impl Gateway {
@@ -188,6 +208,33 @@ impl Gateway {
     /// Prevents having to create empty JSON objects repeatedly.
     const EMPTY_JSON_OBJECT: &'static str = "{}";
 
+    /// Maximum number of favorite songs to retrieve per request.
+    ///
+    /// Large enough to cover the favorites of nearly all users
+    /// in a single request. Larger favorites are fetched in pages.
+    const FAVORITES_MAX: u64 = 10_000;
+
+    /// Maximum number of songs to request in a single `song.getListData` call.
+    ///
+    /// Larger queues are split into multiple batches to keep request
+    /// and response sizes reasonable.
+    const LIST_DATA_BATCH_MAX: usize = 200;
+
+    /// Error codes with which the gateway rejects a revoked session.
+    ///
+    /// Returned after the account logged out elsewhere or the session was
+    /// revoked. Retrying with the same session is futile: it needs a new
+    /// login.
+    const SESSION_ERRORS: [&'static str; 2] = ["VALID_TOKEN_REQUIRED", "NEED_USER_AUTH_REQUIRED"];
+
+    /// Maximum number of playlist songs to retrieve per request.
+    ///
+    /// Larger playlists are fetched in pages.
+    const PLAYLIST_PAGE_MAX: u64 = 1_000;
+
+    /// Maximum number of results to retrieve in a search.
+    const SEARCH_MAX: u64 = 40;
+
     /// Returns the cookie origin URL for Deezer services.
     ///
     /// # Panics
@@ -261,6 +308,7 @@ impl Gateway {
     /// * User-Agent header cannot be created from config
     /// * OS information cannot be detected
     /// * Cookie creation fails
+    /// * Extra gateway headers are invalid
     pub fn new(config: &Config) -> Result<Self> {
         // Create a new cookie jar and put the cookies in.
         let cookie_jar = Self::cookie_jar(config)?;
@@ -270,6 +318,9 @@ impl Gateway {
             client_id: config.client_id,
             http_client,
             user_data: None,
+            headers: config.gateway_header_map()?,
+            favorite_ids: None,
+            favorites: None,
         })
     }
 
@@ -304,48 +355,60 @@ impl Gateway {
     /// * Network request fails
     pub async fn refresh(&mut self) -> Result<()> {
         // Send an empty JSON map
-        match self
-            .request::<UserData>(Self::EMPTY_JSON_OBJECT, None)
-            .await
-        {
-            Ok(response) => {
-                if let Some(data) = response.first() {
-                    if !data.gatekeeps.remote_control {
-                        return Err(Error::permission_denied(
-                            "remote control is disabled for this account; upgrade your Deezer subscription",
-                        ));
-                    }
-                    if data.user.options.too_many_devices {
-                        return Err(Error::resource_exhausted(
-                            "too many devices; remove one or more in your account settings",
-                        ));
-                    }
-                    if data.user.options.ads_audio {
-                        return Err(Error::unimplemented(
-                            "ads are not implemented; upgrade your Deezer subscription",
-                        ));
-                    }
-
-                    self.set_user_data(data.clone());
-                } else {
-                    return Err(Error::not_found("no user data received".to_string()));
-                }
-
-                Ok(())
+        let body = self
+            .request_body(UserData::METHOD, Self::EMPTY_JSON_OBJECT, None)
+            .await?;
+        let response: Response<UserData> = protocol::json(&body, UserData::METHOD)
+            .map_err(|e| Self::auth_error(&body, &e, "arl invalid or expired"))?;
+
+        if let Some(data) = response.first() {
+            if !data.gatekeeps.remote_control {
+                return Err(Error::permission_denied(
+                    "remote control is disabled for this account; upgrade your Deezer subscription",
+                ));
+            }
+            if data.user.options.too_many_devices {
+                return Err(Error::resource_exhausted(
+                    "too many devices; remove one or more in your account settings",
+                ));
+            }
+            if data.user.options.ads_audio {
+                return Err(Error::unimplemented(
+                    "free-tier accounts are not supported: playback requires a Deezer Premium, Family or HiFi subscription without ads",
+                ));
             }
 
-            Err(e) => {
-                if e.kind == ErrorKind::InvalidArgument {
-                    // For an invalid or expired `arl`, the response has some
-                    // fields as integer `0` which are normally typed as string,
-                    // which causes JSON deserialization to fail.
-                    return Err(Error::permission_denied(
-                        "arl invalid or expired".to_string(),
-                    ));
-                }
+            self.set_user_data(data.clone());
+        } else {
+            return Err(Error::not_found("no user data received".to_string()));
+        }
 
-                Err(e)
-            }
+        Ok(())
+    }
+
+    /// Classifies an authentication response that failed to parse.
+    ///
+    /// For invalid or expired credentials, Deezer responds with JSON that has
+    /// some fields as integer `0` which are normally typed as string, which
+    /// causes deserialization to fail. Responses that are not JSON at all,
+    /// like error pages of an overloaded server, say nothing about the
+    /// credentials.
+    ///
+    /// # Arguments
+    ///
+    /// * `body` - Response body that failed to parse
+    /// * `error` - Parse error
+    /// * `denied` - Message to use when the credentials were rejected
+    ///
+    /// # Returns
+    ///
+    /// * `PermissionDenied` - Response was JSON: credentials were rejected
+    /// * `Unavailable` - Response was not JSON: authentication can be retried
+    fn auth_error(body: &str, error: &Error, denied: &str) -> Error {
+        if serde_json::from_str::<serde_json::Value>(body).is_ok() {
+            Error::permission_denied(denied.to_string())
+        } else {
+            Error::unavailable(format!("unexpected authentication response: {error}"))
         }
     }
 
@@ -374,6 +437,7 @@ impl Gateway {
     /// * HTTP status code is not successful (not 2xx)
     /// * Response isn't valid JSON
     /// * Response can't be parsed as type T
+    /// * Session was revoked (`Unauthenticated`)
     pub async fn request<T>(
         &mut self,
         body: impl Into<reqwest::Body>,
@@ -382,6 +446,75 @@ impl Gateway {
     where
         T: std::fmt::Debug + gateway::Method + for<'de> Deserialize<'de>,
     {
+        let body = self.request_body(T::METHOD, body, headers).await?;
+        Self::parse_response(&body)
+    }
+
+    /// Parses a gateway response body, rejecting revoked sessions.
+    ///
+    /// Session errors are looked up in parsed responses as well as in
+    /// bodies that do not parse as `Response<T>`, since rejections usually
+    /// carry no results of the expected type.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// * Session was revoked (`Unauthenticated`)
+    /// * Response isn't valid JSON or can't be parsed as type T
+    fn parse_response<T>(body: &str) -> Result<Response<T>>
+    where
+        T: std::fmt::Debug + gateway::Method + for<'de> Deserialize<'de>,
+    {
+        match protocol::json::<Response<T>>(body, T::METHOD) {
+            Ok(response) => match Self::session_error(response.errors().keys()) {
+                Some(e) => Err(e),
+                None => Ok(response),
+            },
+            Err(e) => {
+                // Rejections usually have no results of the expected type.
+                let json = serde_json::from_str::<serde_json::Value>(body).unwrap_or_default();
+                let codes = json
+                    .get("error")
+                    .and_then(serde_json::Value::as_object)
+                    .into_iter()
+                    .flat_map(serde_json::Map::keys);
+                Err(Self::session_error(codes).unwrap_or(e))
+            }
+        }
+    }
+
+    /// Classifies gateway error codes that reject a revoked session.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(Unauthenticated)` - One of the codes rejects the session
+    /// * `None` - None of the codes reject the session
+    fn session_error<'a>(mut codes: impl Iterator<Item = &'a String>) -> Option<Error> {
+        codes
+            .find(|code| Self::SESSION_ERRORS.contains(&code.as_str()))
+            .map(|code| Error::unauthenticated(format!("session revoked: {code}")))
+    }
+
+    /// Sends a request to the Deezer gateway API and returns the raw body.
+    ///
+    /// # Arguments
+    ///
+    /// * `method` - Gateway method name
+    /// * `body` - Request body content
+    /// * `headers` - Optional additional headers
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// * URL construction fails
+    /// * Network request fails
+    /// * HTTP status code is not successful (not 2xx)
+    async fn request_body(
+        &mut self,
+        method: &str,
+        body: impl Into<reqwest::Body>,
+        headers: Option<HeaderMap>,
+    ) -> Result<String> {
         // Get the API token from the user data or use an empty string.
         let api_token = self
             .user_data
@@ -393,7 +526,7 @@ impl Gateway {
         let url_str = format!(
             "{}?method={}&input={}&api_version={}&api_token={api_token}&cid={}",
             Self::GATEWAY_URL,
-            T::METHOD,
+            method,
             Self::GATEWAY_INPUT,
             Self::GATEWAY_VERSION,
             self.client_id,
@@ -403,14 +536,14 @@ impl Gateway {
         // Although the bodies of all gateway requests are JSON, the
         // `Content-Type` is not.
         let mut request = self.http_client.text(url, body);
+        request.headers_mut().extend(self.headers.clone());
         if let Some(headers) = headers {
             // Add any headers that were passed in.
             request.headers_mut().extend(headers);
         }
 
         let response = self.http_client.execute(request).await?;
-        let body = response.text().await?;
-        protocol::json(&body, T::METHOD)
+        response.text().await.map_err(Into::into)
     }
 
     /// Returns the current license token if available.
@@ -542,13 +675,7 @@ impl Gateway {
 
         if let Some(first) = list.tracks.first() {
             let response: Response<ListData> = match first.typ.enum_value_or_default() {
-                queue::TrackType::TRACK_TYPE_SONG => {
-                    let songs = songs::Request { song_ids: ids };
-                    let request = serde_json::to_string(&songs)?;
-                    self.request::<SongData>(request, None)
-                        .map_ok(Into::into)
-                        .await?
-                }
+                queue::TrackType::TRACK_TYPE_SONG => return self.songs(&ids).await,
                 queue::TrackType::TRACK_TYPE_EPISODE => {
                     let episodes = episodes::Request { episode_ids: ids };
                     let request = serde_json::to_string(&episodes)?;
@@ -579,6 +706,76 @@ impl Gateway {
         }
     }
 
+    /// Fetches list data for songs in batches.
+    ///
+    /// Requests up to `LIST_DATA_BATCH_MAX` songs per request, which makes
+    /// resolving large queues and refreshing track tokens much faster than
+    /// requesting songs one by one. The returned queue keeps the order and
+    /// length of `song_ids`.
+    ///
+    /// Partial failures are tolerated: songs that Deezer does not return
+    /// and batches that fail are logged and kept as
+    /// [`ListData::unavailable_song`] placeholders, so that queue positions
+    /// stay in step with the controller.
+    ///
+    /// # Arguments
+    ///
+    /// * `song_ids` - IDs of the songs to fetch
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if no songs could be fetched at all because
+    /// every batch failed.
+    pub async fn songs(&mut self, song_ids: &[TrackId]) -> Result<Queue> {
+        let mut queue = Queue::with_capacity(song_ids.len());
+        let mut fetched = false;
+        let mut last_error = None;
+
+        for batch in song_ids.chunks(Self::LIST_DATA_BATCH_MAX) {
+            let songs = songs::Request {
+                song_ids: batch.to_vec(),
+            };
+            let request = serde_json::to_string(&songs)?;
+            match self.request::<SongData>(request, None).await {
+                Ok(response) => {
+                    let response: Response<ListData> = response.into();
+                    let errors = response.errors();
+                    if !errors.is_empty() {
+                        warn!("partial failure fetching songs: {errors:?}");
+                    }
+
+                    let results = response.all();
+                    let mut missing = Vec::new();
+                    for id in batch {
+                        match results.iter().find(|item| item.id() == *id) {
+                            Some(item) => {
+                                queue.push(item.clone());
+                                fetched = true;
+                            }
+                            None => {
+                                missing.push(id.to_string());
+                                queue.push(ListData::unavailable_song(*id));
+                            }
+                        }
+                    }
+                    if !missing.is_empty() {
+                        warn!("songs not returned: {}", missing.join(", "));
+                    }
+                }
+                Err(e) => {
+                    error!("failed to fetch batch of {} songs: {e}", batch.len());
+                    queue.extend(batch.iter().copied().map(ListData::unavailable_song));
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        match last_error {
+            Some(e) if !fetched => Err(e),
+            _ => Ok(queue),
+        }
+    }
+
     /// Fetches Flow recommendations for a user.
     ///
     /// Flow is Deezer's personalized radio feature.
@@ -610,6 +807,313 @@ impl Gateway {
         }
     }
 
+    /// Fetches all songs of a playlist.
+    ///
+    /// Pages are requested one after another until all songs have been
+    /// received, so that large playlists do not exceed the rate limit.
+    ///
+    /// # Arguments
+    ///
+    /// * `playlist_id` - ID of the playlist
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// * Network request fails
+    /// * Response parsing fails
+    pub async fn playlist(&mut self, playlist_id: u64) -> Result<Queue> {
+        let mut queue = Queue::new();
+        loop {
+            let request = playlist::Request {
+                playlist_id,
+                start: queue.len() as u64,
+                count: Self::PLAYLIST_PAGE_MAX,
+            };
+            let body = serde_json::to_string(&request)?;
+            let response = self.request::<PlaylistSong>(body, None).await?;
+
+            let page = response.all();
+            queue.extend(page.iter().map(|song| song.0.clone()));
+
+            let total = response.total();
+            if !Self::has_more_pages(queue.len(), page.len(), total) {
+                break;
+            }
+
+            trace!(
+                "fetched {} of {} songs of playlist {playlist_id}",
+                queue.len(),
+                total.unwrap_or_default()
+            );
+        }
+
+        Ok(queue)
+    }
+
+    /// Returns whether more pages of a paginated list remain.
+    ///
+    /// # Arguments
+    ///
+    /// * `fetched` - Number of items fetched so far
+    /// * `page_len` - Number of items in the last page
+    /// * `total` - Total number of items, or `None` for unpaginated responses
+    ///   that hold all items at once
+    #[must_use]
+    fn has_more_pages(fetched: usize, page_len: usize, total: Option<u64>) -> bool {
+        page_len > 0 && total.is_some_and(|total| (fetched as u64) < total)
+    }
+
+    /// Checks whether a song is in the user's favorites.
+    ///
+    /// Looks the song up in all favorites, which are fetched and cached by
+    /// [`favorite_ids`](Self::favorite_ids).
+    ///
+    /// # Arguments
+    ///
+    /// * `song_id` - ID of the song to check
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// * Network request fails
+    /// * Response parsing fails
+    pub async fn is_favorite(&mut self, song_id: TrackId) -> Result<bool> {
+        Ok(self.favorite_ids().await?.contains(&song_id))
+    }
+
+    /// Fetches the IDs of the user's favorite songs.
+    ///
+    /// Fetches all pages of favorite song IDs. The result is cached until
+    /// favorites are changed through [`set_favorite`](Self::set_favorite)
+    /// or [`toggle_favorite`](Self::toggle_favorite).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// * Network request fails
+    /// * Response parsing fails
+    async fn favorite_ids(&mut self) -> Result<&[TrackId]> {
+        if self.favorite_ids.is_none() {
+            let mut song_ids = Vec::new();
+            loop {
+                let request = favorites::ListRequest {
+                    count: Self::FAVORITES_MAX,
+                    start: song_ids.len() as u64,
+                };
+                let body = serde_json::to_string(&request)?;
+                let response = self.request::<FavoriteSong>(body, None).await?;
+
+                let page = response.all();
+                song_ids.extend(page.iter().map(|favorite| favorite.id));
+
+                if !Self::has_more_pages(song_ids.len(), page.len(), response.total()) {
+                    break;
+                }
+            }
+
+            self.favorite_ids = Some(song_ids);
+        }
+
+        Ok(self.favorite_ids.as_deref().unwrap_or_default())
+    }
+
+    /// Fetches the user's favorite songs as a playable queue.
+    ///
+    /// Fetches all pages of favorite song IDs, then resolves their track
+    /// information. The result is cached until favorites are changed through
+    /// [`set_favorite`](Self::set_favorite) or
+    /// [`toggle_favorite`](Self::toggle_favorite).
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// * Network request fails
+    /// * Response parsing fails
+    pub async fn favorites(&mut self) -> Result<Queue> {
+        if let Some(favorites) = &self.favorites {
+            return Ok(favorites.clone());
+        }
+
+        let song_ids = self.favorite_ids().await?.to_vec();
+        let favorites = self.songs(&song_ids).await?;
+        self.favorites = Some(favorites.clone());
+        Ok(favorites)
+    }
+
+    /// Adds a song to or removes it from the user's favorites.
+    ///
+    /// # Arguments
+    ///
+    /// * `song_id` - ID of the song to add or remove
+    /// * `favorite` - Whether to add (`true`) or remove (`false`) the song
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// * Network request fails
+    /// * Response parsing fails
+    /// * Deezer did not accept the change
+    pub async fn set_favorite(&mut self, song_id: TrackId, favorite: bool) -> Result<()> {
+        let request = favorites::Request { song_id };
+        let body = serde_json::to_string(&request)?;
+        let accepted = if favorite {
+            self.request::<AddFavorite>(body, None)
+                .await?
+                .first()
+                .is_some_and(|result| result.0)
+        } else {
+            self.request::<RemoveFavorite>(body, None)
+                .await?
+                .first()
+                .is_some_and(|result| result.0)
+        };
+
+        if accepted {
+            self.favorite_ids = None;
+            self.favorites = None;
+            Ok(())
+        } else {
+            Err(Error::failed_precondition(format!(
+                "failed to {} favorites for song {song_id}",
+                if favorite { "add to" } else { "remove from" }
+            )))
+        }
+    }
+
+    /// Toggles whether a song is in the user's favorites.
+    ///
+    /// # Arguments
+    ///
+    /// * `song_id` - ID of the song to toggle
+    ///
+    /// # Returns
+    ///
+    /// Returns whether the song is a favorite after toggling.
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// * Network request fails
+    /// * Response parsing fails
+    /// * Deezer did not accept the change
+    pub async fn toggle_favorite(&mut self, song_id: TrackId) -> Result<bool> {
+        let favorite = !self.is_favorite(song_id).await?;
+        self.set_favorite(song_id, favorite).await?;
+        Ok(favorite)
+    }
+
+    /// Searches for content by name.
+    ///
+    /// Returns up to 40 results of the requested type, ordered by relevance.
+    /// No matches yield empty results, and ambiguous queries return all
+    /// matches for the caller to choose from.
+    ///
+    /// # Arguments
+    ///
+    /// * `query` - Text to search for
+    /// * `search_type` - Type of content to search for
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// * Network request fails
+    /// * Response parsing fails
+    pub async fn search(&mut self, query: &str, search_type: SearchType) -> Result<SearchResults> {
+        let request = search::Request {
+            query: query.to_string(),
+            filter: search::FILTER_ALL,
+            output: search_type,
+            start: 0,
+            count: Self::SEARCH_MAX,
+        };
+        let body = serde_json::to_string(&request)?;
+
+        let results = match search_type {
+            SearchType::Track => {
+                let response = self.request::<SearchTrack>(body, None).await?;
+                SearchResults::Tracks(response.all().iter().map(|track| track.0.clone()).collect())
+            }
+            SearchType::Album => {
+                SearchResults::Albums(self.request::<SearchAlbum>(body, None).await?.all().clone())
+            }
+            SearchType::Artist => SearchResults::Artists(
+                self.request::<SearchArtist>(body, None)
+                    .await?
+                    .all()
+                    .clone(),
+            ),
+            SearchType::Playlist => SearchResults::Playlists(
+                self.request::<SearchPlaylist>(body, None)
+                    .await?
+                    .all()
+                    .clone(),
+            ),
+        };
+
+        debug!("found {} {search_type} results for {query}", results.len());
+        Ok(results)
+    }
+
+    /// Logs a listen to the user's listening history.
+    ///
+    /// Listens are used by Deezer for recommendations, and are separate from
+    /// the stream reporting over the websocket.
+    ///
+    /// # Arguments
+    ///
+    /// * `track_id` - ID of the track that was listened to
+    /// * `typ` - Type of the track
+    /// * `quality` - Audio quality the track was played in
+    /// * `listening_time` - How long the track was listened to
+    ///
+    /// # Errors
+    ///
+    /// Returns an error if:
+    /// * Network request fails
+    /// * Response parsing fails
+    /// * Deezer did not accept the listen
+    pub async fn log_listen(
+        &mut self,
+        track_id: TrackId,
+        typ: TrackType,
+        quality: AudioQuality,
+        listening_time: Duration,
+    ) -> Result<()> {
+        let now = SystemTime::now()
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default();
+        let started = now.saturating_sub(listening_time);
+
+        let request = listen::Request {
+            params: listen::Params {
+                timestamp: now.as_secs(),
+                ts_listen: started.as_secs(),
+                typ: listen::LISTEN_TYPE_STREAM,
+                stat: listen::Stat::default(),
+                media: listen::Media {
+                    id: track_id,
+                    typ,
+                    format: quality.into(),
+                },
+                listening_time: listening_time.as_secs(),
+            },
+        };
+        let body = serde_json::to_string(&request)?;
+
+        if self
+            .request::<Listen>(body, None)
+            .await?
+            .first()
+            .is_some_and(|result| result.0)
+        {
+            Ok(())
+        } else {
+            Err(Error::failed_precondition(format!(
+                "failed to log listen of {typ} {track_id}"
+            )))
+        }
+    }
+
     /// Retrieves an ARL token using an OAuth access token.
     ///
     /// # Arguments
@@ -731,7 +1235,7 @@ impl Gateway {
         let response = self.http_client.execute(request).await?;
         let body = response.text().await?;
         let result: auth::User = protocol::json(&body, query.path())
-            .map_err(|_| Error::permission_denied("email or password incorrect"))?;
+            .map_err(|e| Self::auth_error(&body, &e, "email or password incorrect"))?;
 
         // Finally use the access token to get an ARL.
         self.get_arl(&result.access_token).await
@@ -822,3 +1326,82 @@ impl Gateway {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::ErrorKind;
+
+    #[derive(Debug, Deserialize)]
+    struct Item {
+        #[expect(dead_code)]
+        id: u64,
+    }
+
+    impl gateway::Method for Item {
+        const METHOD: &'static str = "test.getItem";
+    }
+
+    fn parse(body: &str) -> Result<Response<Item>> {
+        Gateway::parse_response(body)
+    }
+
+    #[test]
+    fn parse_response_accepts_results() {
+        let response = parse(r#"{"error": [], "results": [{"id": 1}]}"#).unwrap();
+        assert_eq!(response.all().len(), 1);
+    }
+
+    #[test]
+    fn parse_response_rejects_session_errors_in_parsed_response() {
+        for code in Gateway::SESSION_ERRORS {
+            let body = format!(r#"{{"error": [["{code}", "invalid"]], "results": []}}"#);
+            let e = parse(&body).unwrap_err();
+            assert_eq!(e.kind, ErrorKind::Unauthenticated, "{code}");
+        }
+    }
+
+    #[test]
+    fn parse_response_rejects_session_errors_in_unparseable_response() {
+        for code in Gateway::SESSION_ERRORS {
+            let body = format!(r#"{{"error": {{"{code}": "invalid"}}, "results": {{}}}}"#);
+            let e = parse(&body).unwrap_err();
+            assert_eq!(e.kind, ErrorKind::Unauthenticated, "{code}");
+        }
+    }
+
+    #[test]
+    fn parse_response_passes_other_errors_in_parsed_response() {
+        let body = r#"{"error": [["DATA_ERROR", "no data"]], "results": []}"#;
+        let response = parse(body).unwrap();
+        assert!(response.errors().contains_key("DATA_ERROR"));
+    }
+
+    #[test]
+    fn parse_response_keeps_parse_error_for_other_errors() {
+        let body = r#"{"error": {"DATA_ERROR": "no data"}, "results": {}}"#;
+        let e = parse(body).unwrap_err();
+        assert_ne!(e.kind, ErrorKind::Unauthenticated);
+
+        let e = parse("not json").unwrap_err();
+        assert_ne!(e.kind, ErrorKind::Unauthenticated);
+    }
+
+    #[test]
+    fn has_more_pages_until_total() {
+        assert!(Gateway::has_more_pages(1_000, 1_000, Some(2_500)));
+        assert!(Gateway::has_more_pages(2_000, 1_000, Some(2_500)));
+        assert!(!Gateway::has_more_pages(2_500, 500, Some(2_500)));
+        assert!(!Gateway::has_more_pages(3_000, 1_000, Some(2_500)));
+    }
+
+    #[test]
+    fn has_more_pages_stops_on_empty_page() {
+        assert!(!Gateway::has_more_pages(1_000, 0, Some(2_500)));
+    }
+
+    #[test]
+    fn has_more_pages_stops_when_unpaginated() {
+        assert!(!Gateway::has_more_pages(10, 10, None));
+    }
+}
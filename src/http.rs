@@ -5,6 +5,8 @@
 //! * Persistent login across client restarts
 //! * Request rate limiting to respect API quotas
 //! * Network interface binding for routing control
+//! * Optional custom hostname resolution
+//! * Optional custom certificate authorities
 //! * Configurable timeouts for connections and reads
 //! * Connection keepalive for performance
 //!
@@ -69,7 +71,12 @@ use reqwest::{
     Body, Method, Url,
 };
 
-use crate::{config::Config, error::Result};
+use crate::{
+    config::Config,
+    dns::Resolver,
+    error::{Error, Result},
+    tls,
+};
 
 /// HTTP client with session management and rate limiting.
 ///
@@ -162,6 +169,8 @@ impl Client {
     /// Returns error if:
     /// * HTTP client creation fails
     /// * Header values are invalid
+    /// * Resolver creation fails
+    /// * CA certificate file cannot be loaded
     ///
     /// # Panics
     ///
@@ -192,6 +201,20 @@ impl Client {
             http_client = http_client.cookie_provider(Arc::clone(jar));
         }
 
+        if let Some(ref nameserver) = config.resolver {
+            let resolver = Resolver::new(nameserver.clone(), config.bind_address)?;
+            http_client = http_client.dns_resolver(Arc::new(resolver));
+        }
+
+        if let Some(ref ca_cert) = config.ca_cert {
+            for certificate in tls::load_certificates(ca_cert)? {
+                let certificate = reqwest::Certificate::from_der(&certificate).map_err(|e| {
+                    Error::invalid_argument(format!("invalid certificate authority: {e}"))
+                })?;
+                http_client = http_client.add_root_certificate(certificate);
+            }
+        }
+
         // Rate limit own requests as to not DoS the Deezer infrastructure.
         let replenish_interval =
             Self::RATE_LIMIT_INTERVAL / u32::from(Self::RATE_LIMIT_CALLS_PER_INTERVAL);
@@ -0,0 +1,477 @@
+//! Rebroadcasting of playback to an Icecast server.
+//!
+//! This module handles:
+//! * Capturing decoded audio from the player output
+//! * Encoding PCM samples to MP3
+//! * Streaming the encoded audio to an Icecast mountpoint
+//! * Reconnecting when the server connection is lost
+//!
+//! # Architecture
+//!
+//! Audio flows through these stages:
+//! 1. [`Tee`] wraps the player output and copies samples in chunks
+//! 2. A background task encodes the chunks to MP3
+//! 3. The encoded audio is sent as the body of a streaming HTTP `PUT` request
+//!
+//! The audio thread never blocks on the network: when the server cannot keep
+//! up, chunks are dropped. When no audio is playing, silence is sent to keep
+//! the mountpoint alive.
+//!
+//! The stream is captured before volume control, so listeners hear the
+//! content at full scale regardless of the local volume.
+//!
+//! # Legal
+//!
+//! Rebroadcasting does not circumvent the Deezer Terms of Service. Make sure
+//! that you are allowed to share what you play, for example by restricting
+//! access to the mountpoint to your own household.
+//!
+//! # Example
+//!
+//! ```rust
+//! use pleezer::icecast;
+//!
+//! // Start streaming and wrap the output source
+//! let tx = icecast::spawn(&config, server)?;
+//! let output = icecast::tee(output, Some(tx));
+//! ```
+
+use std::{mem, time::Duration};
+
+use exponential_backoff::Backoff;
+use mp3lame_encoder::{Bitrate, Builder, FlushNoGap, InterleavedPcm, Quality};
+use reqwest::header::{HeaderValue, CONTENT_TYPE, USER_AGENT};
+use rodio::{source::SeekError, Source};
+use tokio::sync::mpsc;
+
+use crate::{
+    config::{Config, IcecastServer},
+    error::{Error, Result},
+    player::SampleFormat,
+    track::DEFAULT_SAMPLE_RATE,
+};
+
+/// Chunk of interleaved PCM samples in a single format.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Chunk {
+    /// Number of interleaved channels.
+    pub channels: u16,
+
+    /// Sample rate in Hz.
+    pub sample_rate: u32,
+
+    /// Interleaved samples.
+    pub samples: Vec<SampleFormat>,
+}
+
+/// Duration of audio collected before sending a chunk.
+const CHUNK_DURATION: Duration = Duration::from_millis(100);
+
+/// Number of chunks that may be queued for encoding.
+///
+/// When full, new chunks are dropped instead of blocking the audio thread.
+const CHUNK_BUFFER: usize = 32;
+
+/// Number of encoded buffers that may be queued for sending.
+const BODY_BUFFER: usize = 8;
+
+/// Time without audio after which silence is sent.
+const IDLE_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Bitrate of the MP3 stream.
+const BITRATE: Bitrate = Bitrate::Kbps192;
+
+/// Time before connecting to the server times out.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Number of reconnection attempts before giving up.
+const BACKOFF_ATTEMPTS: u32 = 10;
+
+/// Minimum duration to wait between reconnection attempts.
+const MIN_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Maximum duration to wait between reconnection attempts.
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+
+/// Wraps a source so that its samples are also sent for streaming.
+///
+/// # Arguments
+///
+/// * `input` - Audio source to capture
+/// * `tx` - Channel as returned by [`spawn`], or `None` to pass samples
+///   through without streaming
+#[must_use]
+pub fn tee<I>(input: I, tx: Option<mpsc::Sender<Chunk>>) -> Tee<I>
+where
+    I: Source<Item = SampleFormat>,
+{
+    let channels = input.channels();
+    let sample_rate = input.sample_rate();
+
+    Tee {
+        input,
+        tx,
+        buffer: Vec::new(),
+        channels,
+        sample_rate,
+    }
+}
+
+/// Audio source that copies its samples to an Icecast stream.
+///
+/// Samples pass through unchanged. Copies are collected into chunks and
+/// sent without blocking; chunks are dropped when the channel is full.
+pub struct Tee<I>
+where
+    I: Source<Item = SampleFormat>,
+{
+    /// Input audio source
+    input: I,
+
+    /// Channel to the streaming task, if streaming
+    tx: Option<mpsc::Sender<Chunk>>,
+
+    /// Samples collected for the next chunk
+    buffer: Vec<SampleFormat>,
+
+    /// Channel count of the collected samples
+    channels: u16,
+
+    /// Sample rate of the collected samples
+    sample_rate: u32,
+}
+
+impl<I> Tee<I>
+where
+    I: Source<Item = SampleFormat>,
+{
+    /// Sends the collected samples as a chunk.
+    ///
+    /// Drops the chunk if the streaming task cannot keep up.
+    fn flush(&mut self) {
+        let Some(tx) = self.tx.as_ref() else {
+            return;
+        };
+        if self.buffer.is_empty() {
+            return;
+        }
+
+        let chunk = Chunk {
+            channels: self.channels,
+            sample_rate: self.sample_rate,
+            samples: mem::take(&mut self.buffer),
+        };
+
+        if let Err(mpsc::error::TrySendError::Full(_)) = tx.try_send(chunk) {
+            trace!("icecast stream is lagging, dropping audio");
+        }
+    }
+
+    /// Number of samples in a chunk for the current format.
+    fn chunk_len(&self) -> usize {
+        let frames = u128::from(self.sample_rate) * CHUNK_DURATION.as_millis() / 1000;
+        usize::try_from(frames)
+            .unwrap_or(usize::MAX)
+            .saturating_mul(usize::from(self.channels))
+    }
+}
+
+impl<I> Iterator for Tee<I>
+where
+    I: Source<Item = SampleFormat>,
+{
+    type Item = SampleFormat;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        let sample = self.input.next()?;
+        if self.tx.is_none() {
+            return Some(sample);
+        }
+
+        // The format may change at track boundaries.
+        let channels = self.input.channels();
+        let sample_rate = self.input.sample_rate();
+        if channels != self.channels || sample_rate != self.sample_rate {
+            self.flush();
+            self.channels = channels;
+            self.sample_rate = sample_rate;
+        }
+
+        self.buffer.push(sample);
+        if self.buffer.len() >= self.chunk_len() {
+            self.flush();
+        }
+
+        Some(sample)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+impl<I> Source for Tee<I>
+where
+    I: Source<Item = SampleFormat>,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        self.input.current_frame_len()
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+
+    #[inline]
+    fn try_seek(&mut self, pos: Duration) -> std::result::Result<(), SeekError> {
+        self.buffer.clear();
+        self.input.try_seek(pos)
+    }
+}
+
+/// MP3 encoder that follows format changes of the input.
+struct Encoder {
+    /// LAME encoder for the current format
+    inner: mp3lame_encoder::Encoder,
+
+    /// Channel count of the encoder input
+    channels: u16,
+
+    /// Sample rate of the encoder input
+    sample_rate: u32,
+}
+
+impl Encoder {
+    /// Creates an MP3 encoder for the specified format.
+    ///
+    /// Input with more than two channels is reduced to its first two channels.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the encoder does not support the format.
+    fn new(channels: u16, sample_rate: u32) -> Result<Self> {
+        let mut builder =
+            Builder::new().ok_or_else(|| Error::internal("failed to create mp3 encoder"))?;
+        let output_channels = u8::try_from(channels.min(2)).unwrap_or(2);
+        builder
+            .set_num_channels(output_channels)
+            .map_err(|e| Error::invalid_argument(format!("mp3 channels: {e}")))?;
+        builder
+            .set_sample_rate(sample_rate)
+            .map_err(|e| Error::invalid_argument(format!("mp3 sample rate: {e}")))?;
+        builder
+            .set_brate(BITRATE)
+            .map_err(|e| Error::invalid_argument(format!("mp3 bitrate: {e}")))?;
+        builder
+            .set_quality(Quality::Good)
+            .map_err(|e| Error::invalid_argument(format!("mp3 quality: {e}")))?;
+
+        let inner = builder
+            .build()
+            .map_err(|e| Error::internal(format!("failed to build mp3 encoder: {e}")))?;
+
+        Ok(Self {
+            inner,
+            channels,
+            sample_rate,
+        })
+    }
+
+    /// Encodes a chunk of samples to MP3.
+    ///
+    /// Recreates the encoder when the format of the chunk differs from
+    /// the previous one, flushing the previous encoder first.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the encoder cannot be created or encoding fails.
+    fn encode(&mut self, chunk: &Chunk) -> Result<Vec<u8>> {
+        let mut output = Vec::new();
+
+        if chunk.channels != self.channels || chunk.sample_rate != self.sample_rate {
+            self.flush(&mut output)?;
+            *self = Self::new(chunk.channels, chunk.sample_rate)?;
+        }
+
+        let samples: Vec<SampleFormat> = match chunk.channels {
+            1 | 2 => chunk.samples.clone(),
+            channels => chunk
+                .samples
+                .chunks_exact(usize::from(channels))
+                .flat_map(|frame| [frame[0], frame[1]])
+                .collect(),
+        };
+
+        if chunk.channels == 1 {
+            self.inner
+                .encode_to_vec(mp3lame_encoder::MonoPcm(&samples), &mut output)
+                .map_err(|e| Error::internal(format!("failed to encode mp3: {e}")))?;
+        } else {
+            self.inner
+                .encode_to_vec(InterleavedPcm(&samples), &mut output)
+                .map_err(|e| Error::internal(format!("failed to encode mp3: {e}")))?;
+        }
+
+        Ok(output)
+    }
+
+    /// Flushes any buffered audio to `output`.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if flushing fails.
+    fn flush(&mut self, output: &mut Vec<u8>) -> Result<()> {
+        self.inner
+            .flush_to_vec::<FlushNoGap>(output)
+            .map_err(|e| Error::internal(format!("failed to flush mp3 encoder: {e}")))?;
+        Ok(())
+    }
+
+    /// Returns a chunk of silence in the current format.
+    fn silence(&self, duration: Duration) -> Chunk {
+        let frames = usize::try_from(u128::from(self.sample_rate) * duration.as_millis() / 1000)
+            .unwrap_or_default();
+
+        Chunk {
+            channels: self.channels,
+            sample_rate: self.sample_rate,
+            samples: vec![0.0; frames.saturating_mul(usize::from(self.channels))],
+        }
+    }
+}
+
+/// Starts streaming to an Icecast server in the background.
+///
+/// The returned channel should be passed to [`tee`]. Streaming stops when
+/// all senders are dropped, or when the server cannot be reached after
+/// several attempts.
+///
+/// # Arguments
+///
+/// * `config` - Configuration for the user agent and bind address
+/// * `server` - Icecast mountpoint and credentials
+///
+/// # Errors
+///
+/// Returns error if the HTTP client cannot be created.
+pub fn spawn(config: &Config, server: IcecastServer) -> Result<mpsc::Sender<Chunk>> {
+    let client = reqwest::Client::builder()
+        .connect_timeout(CONNECT_TIMEOUT)
+        .local_address(config.bind_address)
+        .build()?;
+
+    let user_agent = HeaderValue::from_str(&config.user_agent)?;
+    let name = HeaderValue::from_str(&config.device_name)?;
+
+    let (tx, rx) = mpsc::channel(CHUNK_BUFFER);
+    tokio::spawn(async move {
+        if let Err(e) = run(client, server, user_agent, name, rx).await {
+            error!("icecast stream stopped: {e}");
+        }
+    });
+
+    Ok(tx)
+}
+
+/// Streams encoded chunks to the server, reconnecting on failures.
+///
+/// # Errors
+///
+/// Returns error if encoding fails or reconnection attempts are exhausted.
+async fn run(
+    client: reqwest::Client,
+    server: IcecastServer,
+    user_agent: HeaderValue,
+    name: HeaderValue,
+    mut rx: mpsc::Receiver<Chunk>,
+) -> Result<()> {
+    let mut encoder = Encoder::new(2, DEFAULT_SAMPLE_RATE)?;
+    let mut attempts = Backoff::new(BACKOFF_ATTEMPTS, MIN_BACKOFF, MAX_BACKOFF).into_iter();
+
+    loop {
+        let (body_tx, body_rx) = mpsc::channel::<Vec<u8>>(BODY_BUFFER);
+        let body = futures_util::stream::unfold(body_rx, |mut body_rx| async move {
+            body_rx
+                .recv()
+                .await
+                .map(|bytes| (Ok::<_, std::io::Error>(bytes), body_rx))
+        });
+
+        let request = client
+            .put(server.url.clone())
+            .basic_auth(&server.username, Some(&server.password))
+            .header(CONTENT_TYPE, HeaderValue::from_static("audio/mpeg"))
+            .header(USER_AGENT, user_agent.clone())
+            .header("Ice-Name", name.clone())
+            .header("Ice-Public", HeaderValue::from_static("0"))
+            .body(reqwest::Body::wrap_stream(body));
+
+        let mut response = tokio::spawn(async move {
+            request
+                .send()
+                .await
+                .and_then(reqwest::Response::error_for_status)
+        });
+
+        info!("streaming to icecast server at {}", server.url);
+
+        // Icecast may accept the stream before the request body is complete.
+        let mut accepted = false;
+
+        let reason = loop {
+            tokio::select! {
+                result = &mut response, if !accepted => {
+                    match result {
+                        Ok(Ok(_)) => {
+                            // Reset the backoff only once the server accepted
+                            // the source: sending to the body channel succeeds
+                            // even when the server rejects it.
+                            accepted = true;
+                            attempts = Backoff::new(BACKOFF_ATTEMPTS, MIN_BACKOFF, MAX_BACKOFF).into_iter();
+                        }
+                        Ok(Err(e)) => break e.into(),
+                        Err(e) => break Error::internal(e.to_string()),
+                    }
+                }
+
+                chunk = tokio::time::timeout(IDLE_TIMEOUT, rx.recv()) => {
+                    let chunk = match chunk {
+                        Ok(Some(chunk)) => chunk,
+                        // All senders are gone: the player was dropped.
+                        Ok(None) => return Ok(()),
+                        // Keep the mountpoint alive while nothing is playing.
+                        Err(_) => encoder.silence(IDLE_TIMEOUT),
+                    };
+
+                    let bytes = encoder.encode(&chunk)?;
+                    if !bytes.is_empty() && body_tx.send(bytes).await.is_err() {
+                        break Error::unavailable("icecast connection lost");
+                    }
+                }
+            }
+        };
+
+        response.abort();
+        match attempts.next().flatten() {
+            Some(duration) => {
+                warn!("{reason}; reconnecting to icecast server in {duration:?}");
+                tokio::time::sleep(duration).await;
+            }
+            None => return Err(reason),
+        }
+    }
+}
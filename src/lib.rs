@@ -24,6 +24,8 @@
 //!   - [`audio_file`]: Unified interface for audio stream handling
 //!   - [`decrypt`]: Handles encrypted content
 //!   - [`decoder`]: Audio format decoding
+//!   - [`dither`]: Dithering for bit depth reduction
+//!   - `icecast`: Rebroadcasting to an Icecast server (requires the `icecast` feature)
 //!   - [`normalize`]: Audio leveling and dynamic range control
 //!   - [`player`]: Controls audio playback and queues
 //!   - [`track`]: Manages track metadata and downloads
@@ -34,13 +36,19 @@
 //!
 //! * **Configuration**
 //!   - [`config`]: Application settings
+//!   - [`dns`]: Custom hostname resolution
 //!   - [`proxy`]: Network proxy support
+//!   - [`tls`]: Custom certificate authorities
+//!
+//! * **Notifications**
+//!   - `notify`: Desktop notifications on track changes (requires the `notify` feature)
 //!
 //! * **Protocol**
 //!   - [`events`]: Event system for state changes
 //!   - [`protocol`]: Deezer Connect message types
 //!
 //! * **System Integration**
+//!   - [`export`]: Queue export to playlist formats
 //!   - [`signal`]: Signal handling (SIGTERM, SIGHUP)
 //!   - [`mod@error`]: Error types and handling
 //!   - [`util`]: General helper functions
@@ -49,12 +57,20 @@
 //! # Example
 //!
 //! ```rust,no_run
-//! use pleezer::{config::Config, player::Player, remote::Client};
+//! use pleezer::{
+//!     config::{Config, Credentials},
+//!     player::{OutputConfig, Player},
+//!     remote::Client,
+//! };
 //!
 //! async fn example() -> pleezer::error::Result<()> {
-//!     // Create player with configuration
-//!     let config = Config::new()?;
-//!     let player = Player::new(&config, "").await?;
+//!     // Create player with default configuration
+//!     let credentials = Credentials::Login {
+//!         email: "user@example.com".to_string(),
+//!         password: "secret".to_string(),
+//!     };
+//!     let config = Config::new(credentials)?;
+//!     let player = Player::new(&config, &OutputConfig::default()).await?;
 //!
 //!     // Create and start client
 //!     let mut client = Client::new(&config, player)?;
@@ -103,16 +119,25 @@ pub mod audio_file;
 pub mod config;
 pub mod decoder;
 pub mod decrypt;
+pub mod dither;
+pub mod dns;
 pub mod error;
 pub mod events;
+pub mod export;
 pub mod gateway;
 pub mod http;
+#[cfg(feature = "icecast")]
+pub mod icecast;
 pub mod normalize;
+#[cfg(feature = "notify")]
+pub mod notify;
+pub mod now_playing;
 pub mod player;
 pub mod protocol;
 pub mod proxy;
 pub mod remote;
 pub mod signal;
+pub mod tls;
 pub mod tokens;
 pub mod track;
 pub mod util;
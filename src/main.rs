@@ -19,7 +19,7 @@
 //! 2. Establishes Deezer connection
 //! 3. Maintains connection with automatic retry on failures:
 //!    * Uses exponential backoff with jitter
-//!    * Makes up to 10 retry attempts
+//!    * Makes up to 10 retry attempts by default, or retries forever
 //!    * Backs off between 100ms and 10s
 //! 4. Handles graceful shutdown
 //!
@@ -32,32 +32,56 @@
 //! * ARL expiration triggers immediate retry
 //! * Other errors are logged and may trigger retry
 //!
+//! On failure, the process exits with a code that depends on the
+//! [`ErrorKind`], so that supervisors can decide whether to restart.
+//!
 //! # Retry Behavior
 //!
 //! The retry logic uses exponential backoff with the following parameters:
-//! * Maximum 5 retry attempts
+//! * Maximum 10 retry attempts by default (configurable, 0 for unlimited)
 //! * Initial backoff of 100ms
 //! * Maximum backoff of 10 seconds
-//! * Random jitter between attempts
+//! * Random jitter between attempts (configurable, 30% by default)
+//!
+//! With unlimited retries, attempts after the exponential backoff is spent
+//! wait the maximum backoff each, so that long outages recover by themselves.
+//!
+//! An optional random delay before the first connection spreads out
+//! instances that start together.
 
-use std::{env, fs, path::Path, process, time::Duration};
+use std::{
+    env,
+    ffi::OsString,
+    fs,
+    io::{self, Cursor, Write},
+    iter,
+    path::{Path, PathBuf},
+    process,
+    time::Duration,
+};
 
 use clap::{command, Parser, ValueHint};
 use exponential_backoff::Backoff;
-use log::{debug, error, info, trace, warn, LevelFilter};
+use log::{debug, error, info, warn, LevelFilter};
 
 use pleezer::{
     arl::Arl,
-    config::{Config, Credentials},
+    audio_file::AudioFile,
+    config::{Config, Credentials, HookMode, InitialVolumeMode, StopAfter, UuidFormat},
     decrypt,
+    dither::Dither,
+    dns::Nameserver,
     error::{Error, ErrorKind, Result},
-    player::Player,
-    protocol::connect::{DeviceType, Percentage},
+    http, now_playing,
+    player::{OutputConfig, Player},
+    protocol::connect::{AudioQuality, DeviceType, MessageType, Percentage},
     remote,
     signal::{self, ShutdownSignal},
-    uuid::Uuid,
 };
 
+#[cfg(feature = "icecast")]
+use pleezer::config::IcecastServer;
+
 /// Build profile indicator for logging.
 ///
 /// Shows "debug" when built without optimizations.
@@ -76,7 +100,7 @@ const BUILD_PROFILE: &str = "release";
 /// cannot be used together.
 const ARGS_GROUP_LOGGING: &str = "logging";
 
-/// Number of retry attempts before giving up.
+/// Default number of retry attempts before giving up.
 ///
 /// After this many failed connection attempts, the application will terminate
 /// with an error instead of continuing to retry.
@@ -94,6 +118,20 @@ const MIN_BACKOFF: Duration = Duration::from_millis(100);
 /// exponential increases.
 const MAX_BACKOFF: Duration = Duration::from_secs(10);
 
+/// Default ratio of random jitter between retry attempts.
+///
+/// Matches the default of `exponential_backoff`.
+const DEFAULT_RECONNECT_JITTER: f32 = 0.3;
+
+/// Default maximum size of the secrets file in bytes.
+///
+/// Leaves room for additional secrets while still protecting against
+/// reading arbitrarily large files into memory.
+const DEFAULT_SECRETS_MAX_SIZE: u64 = 8 * 1024;
+
+/// Upper bound of the configurable secrets file size in bytes.
+const SECRETS_MAX_SIZE_LIMIT: u64 = 1024 * 1024;
+
 /// Command line arguments as parsed by `clap`.
 ///
 /// Provides configuration options for:
@@ -115,6 +153,18 @@ struct Args {
     #[arg(short, long, value_name = "FILE", value_hint = ValueHint::FilePath, default_value_t = String::from("secrets.toml"), env = "PLEEZER_SECRETS")]
     secrets: String,
 
+    /// Maximum size of the secrets file in bytes
+    ///
+    /// Protects against reading large files into memory by mistake.
+    #[arg(
+        long,
+        value_name = "BYTES",
+        default_value_t = DEFAULT_SECRETS_MAX_SIZE,
+        value_parser = clap::value_parser!(u64).range(1..=SECRETS_MAX_SIZE_LIMIT),
+        env = "PLEEZER_SECRETS_MAX_SIZE"
+    )]
+    secrets_max_size: u64,
+
     /// Set the player's name as shown to Deezer clients
     ///
     /// If not specified, uses the system hostname.
@@ -128,6 +178,14 @@ struct Args {
     #[arg(long, default_value_t = DeviceType::Web, env = "PLEEZER_DEVICE_TYPE")]
     device_type: DeviceType,
 
+    /// Format of the device ID sent to Deezer
+    ///
+    /// For compatibility with controllers that expect the format of a
+    /// specific platform.
+    /// Values: hyphenated, simple, upper-hyphenated, upper-simple
+    #[arg(long, default_value_t = UuidFormat::Hyphenated, env = "PLEEZER_DEVICE_ID_FORMAT")]
+    device_id_format: UuidFormat,
+
     /// Select the audio output device
     ///
     /// Format: [<host>][|<device>][|<sample rate>][|<sample format>]
@@ -136,12 +194,81 @@ struct Args {
     #[arg(short, long, default_value = None, env = "PLEEZER_DEVICE")]
     device: Option<String>,
 
+    /// Select the audio output device and configuration as JSON
+    ///
+    /// For example: {"host": "ALSA", "device": "hw:CARD=DAC,DEV=0",
+    /// "sample_rate": 48000, "sample_format": "i32", "channels": 2}.
+    /// All fields are optional.
+    #[arg(
+        long,
+        value_name = "JSON",
+        value_parser = parse_output_config,
+        conflicts_with = "device",
+        env = "PLEEZER_DEVICE_CONFIG"
+    )]
+    device_config: Option<OutputConfig>,
+
+    /// Wait up to this many seconds for the output device at startup
+    ///
+    /// Retries with backoff when the device is not available yet, for
+    /// example when a USB or Bluetooth device is still being set up at boot.
+    /// If omitted, the device is only opened when playback starts.
+    #[arg(long, value_name = "SECONDS", env = "PLEEZER_DEVICE_OPEN_TIMEOUT")]
+    device_open_timeout: Option<u64>,
+
+    /// Reload the current track in the new quality when the quality changes
+    ///
+    /// By default, the current track keeps playing in its quality, and the
+    /// new quality applies from the next track. With this option, the current
+    /// track is downloaded again in the new quality, for example when
+    /// reconnecting after changing the quality in the app.
+    #[arg(
+        long,
+        default_value_t = false,
+        env = "PLEEZER_RELOAD_ON_QUALITY_CHANGE"
+    )]
+    reload_on_quality_change: bool,
+
+    /// Skip tracks that play longer than this many seconds
+    ///
+    /// Safety valve for tracks with malformed metadata. Livestreams are
+    /// never skipped. If omitted, tracks play until they end.
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        value_parser = clap::value_parser!(u64).range(1..),
+        env = "PLEEZER_MAX_TRACK_DURATION"
+    )]
+    max_track_duration: Option<u64>,
+
+    /// Dither output to 16-bit or smaller sample formats
+    ///
+    /// Masks distortion when reducing 24-bit or lossy audio to the bit
+    /// depth of the output device.
+    /// Values: none, triangular
+    #[arg(long, default_value_t = Dither::None, env = "PLEEZER_DITHER")]
+    dither: Dither,
+
     /// Enable volume normalization
     ///
     /// Normalizes volume across tracks to provide consistent listening levels.
     #[arg(long, default_value_t = false, env = "PLEEZER_NORMALIZE_VOLUME")]
     normalize_volume: bool,
 
+    /// Apply a constant gain to all output (-20 to 20 dB)
+    ///
+    /// Matches the output level to the input sensitivity of an amplifier.
+    /// Combined with volume normalization when enabled, and limited to
+    /// prevent clipping at positive values.
+    #[arg(
+        long,
+        value_name = "DB",
+        default_value_t = 0.0,
+        allow_negative_numbers = true,
+        env = "PLEEZER_PREAMP"
+    )]
+    preamp: f32,
+
     /// Set initial volume level (0-100)
     ///
     /// Applied when no volume is reported by Deezer client or when reported as maximum.
@@ -153,12 +280,243 @@ struct Args {
     )]
     initial_volume: Option<u8>,
 
+    /// When the initial volume yields to the volume of the controller
+    ///
+    /// In sticky mode, the initial volume is applied on every connection
+    /// until the controller sets a volume below maximum. In once mode, it is
+    /// applied when playback first starts, and the controller volume is used
+    /// from then on.
+    /// Values: sticky, once
+    #[arg(long, default_value_t = InitialVolumeMode::Sticky, env = "PLEEZER_INITIAL_VOLUME_MODE")]
+    initial_volume_mode: InitialVolumeMode,
+
+    /// Audio quality to request before user data is loaded
+    ///
+    /// Once user data is loaded, the quality is lowered to the casting
+    /// quality of the user if it exceeds it.
+    /// Values: low, standard, high, lossless
+    #[arg(
+        long,
+        value_name = "QUALITY",
+        value_parser = parse_quality,
+        default_value = "standard",
+        env = "PLEEZER_INITIAL_QUALITY"
+    )]
+    initial_quality: AudioQuality,
+
+    /// Maximum number of tracks to download concurrently
+    ///
+    /// Limits parallel downloads when tracks are prefetched, to prevent
+    /// saturating bandwidth on queue changes.
+    #[arg(
+        long,
+        value_parser = clap::value_parser!(u8).range(1..),
+        default_value_t = 2,
+        env = "PLEEZER_MAX_CONCURRENT_DOWNLOADS"
+    )]
+    max_concurrent_downloads: u8,
+
+    /// Stream tracks instead of downloading them completely
+    ///
+    /// Keeps only a small window of each track in memory. Lowers memory
+    /// usage on constrained devices, but seeking is slower.
+    #[arg(long, default_value_t = false, env = "PLEEZER_STREAMING")]
+    streaming: bool,
+
+    /// Output inaudible noise while idle to keep the audio device awake
+    ///
+    /// Works around DACs and receivers that power down on digital silence
+    /// and clip the start of the next track.
+    #[arg(long, default_value_t = false, env = "PLEEZER_KEEPALIVE_AUDIO")]
+    keepalive_audio: bool,
+
+    /// Discard audio instead of opening an output device
+    ///
+    /// Playback is reported to the controller as usual. For testing and for
+    /// machines without audio hardware.
+    #[arg(long, default_value_t = false, env = "PLEEZER_NO_AUDIO")]
+    no_audio: bool,
+
     /// Prevent other clients from taking over the connection
     ///
     /// By default, other clients can interrupt and take control of playback.
     #[arg(long, default_value_t = false, env = "PLEEZER_NO_INTERRUPTIONS")]
     no_interruptions: bool,
 
+    /// Hide from other clients while connected with --no-interruptions
+    ///
+    /// By default, other clients still see this device while it is
+    /// occupied, but cannot connect to it.
+    #[arg(
+        long,
+        default_value_t = false,
+        requires = "no_interruptions",
+        env = "PLEEZER_HIDE_WHEN_TAKEN"
+    )]
+    hide_when_taken: bool,
+
+    /// Connect without stream limitation notices when their channel is unavailable
+    ///
+    /// Subscriptions are attempted 3 times. By default, failing to subscribe
+    /// to any channel fails the connection, which is then retried.
+    #[arg(long, default_value_t = false, env = "PLEEZER_ALLOW_DEGRADED")]
+    allow_degraded: bool,
+
+    /// Prevent other clients from taking over for this many seconds after connecting
+    ///
+    /// Stops controllers from taking over back and forth. By default, other
+    /// clients can take over at any time.
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        value_parser = clap::value_parser!(u64).range(1..),
+        conflicts_with = "no_interruptions",
+        env = "PLEEZER_TAKEOVER_COOLDOWN"
+    )]
+    takeover_cooldown: Option<u64>,
+
+    /// Re-announce availability every number of seconds when not connected
+    ///
+    /// Sends a fresh connection offer to controllers that requested discovery
+    /// before. Helps controllers that missed the initial offer on flaky
+    /// networks. If omitted, only responds to discovery requests.
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        value_parser = clap::value_parser!(u64).range(1..),
+        env = "PLEEZER_ANNOUNCE_INTERVAL"
+    )]
+    announce_interval: Option<u64>,
+
+    /// Remember discovery sessions for this many seconds across reconnections
+    ///
+    /// Prevents older Deezer apps from showing duplicate remotes after a
+    /// quick reconnection. If omitted, discovery sessions are forgotten on
+    /// every connection.
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        value_parser = clap::value_parser!(u64).range(..=Config::DISCOVERY_GRACE_MAX.as_secs()),
+        env = "PLEEZER_DISCOVERY_GRACE"
+    )]
+    discovery_grace: Option<u64>,
+
+    /// Reconnect after this many seconds to refresh stale sessions
+    ///
+    /// Waits until playback is paused or stopped, so it is not interrupted.
+    /// Must be at least 60. If omitted, connections are kept until they fail.
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        value_parser = clap::value_parser!(u64).range(60..),
+        env = "PLEEZER_MAX_SESSION_DURATION"
+    )]
+    max_session_duration: Option<u64>,
+
+    /// Extend the Flow queue at most once per this many seconds
+    ///
+    /// Coalesces extensions when skipping rapidly through Flow, instead of
+    /// fetching recommendations for every track. Set to 0 to disable.
+    #[arg(
+        long,
+        value_name = "SECONDS",
+        default_value_t = Config::FLOW_EXTEND_INTERVAL_DEFAULT.as_secs(),
+        env = "PLEEZER_FLOW_EXTEND_INTERVAL"
+    )]
+    flow_extend_interval: u64,
+
+    /// Number of Flow tracks to keep resolved ahead of the current track
+    ///
+    /// Extends the Flow queue when at most this many tracks are left, with
+    /// at most this many new tracks at a time.
+    #[arg(
+        long,
+        value_name = "TRACKS",
+        value_parser = clap::builder::RangedU64ValueParser::<usize>::new().range(1..),
+        default_value_t = Config::FLOW_LOOKAHEAD_DEFAULT,
+        env = "PLEEZER_FLOW_LOOKAHEAD"
+    )]
+    flow_lookahead: usize,
+
+    /// Maximum websocket frame size in KiB
+    ///
+    /// Should be at most a quarter of the maximum message size.
+    #[arg(
+        long,
+        value_name = "KIB",
+        value_parser = clap::value_parser!(u16).range(1..),
+        default_value_t = 32,
+        env = "PLEEZER_WEBSOCKET_FRAME_MAX"
+    )]
+    websocket_frame_max: u16,
+
+    /// Maximum websocket message size in KiB
+    ///
+    /// Larger messages, like queues of huge playlists, are ignored.
+    /// Should be at most half of the write buffer size.
+    #[arg(
+        long,
+        value_name = "KIB",
+        value_parser = clap::value_parser!(u16).range(1..),
+        default_value_t = 128,
+        env = "PLEEZER_WEBSOCKET_MESSAGE_MAX"
+    )]
+    websocket_message_max: u16,
+
+    /// Maximum websocket write buffer size in KiB
+    #[arg(
+        long,
+        value_name = "KIB",
+        value_parser = clap::value_parser!(u16).range(1..),
+        default_value_t = 256,
+        env = "PLEEZER_WEBSOCKET_BUFFER_MAX"
+    )]
+    websocket_buffer_max: u16,
+
+    /// Stop playback and disconnect after this many tracks or this long
+    ///
+    /// A number of tracks like 10, or a time like 90s, 30m or 2h, counted
+    /// from when a controller connects. Livestreams do not count as tracks.
+    #[arg(long, value_name = "LIMIT", env = "PLEEZER_STOP_AFTER")]
+    stop_after: Option<StopAfter>,
+
+    /// Exit instead of staying available when the stop limit is reached
+    #[arg(
+        long,
+        default_value_t = false,
+        requires = "stop_after",
+        env = "PLEEZER_EXIT_AFTER_STOP"
+    )]
+    exit_after_stop: bool,
+
+    /// Do not log played tracks to the Deezer listening history
+    ///
+    /// Prevents headless playback from influencing your recommendations.
+    #[arg(long, default_value_t = false, env = "PLEEZER_NO_LISTENING_HISTORY")]
+    no_listening_history: bool,
+
+    /// Do not report playback progress to the controller
+    ///
+    /// Reduces network traffic, but the controller shows frozen progress.
+    #[arg(long, default_value_t = false, env = "PLEEZER_NO_PROGRESS_REPORTS")]
+    no_progress_reports: bool,
+
+    /// Duration in milliseconds to fade out playback when shutting down (0-5000)
+    ///
+    /// Reports the pause to the controller and prevents speakers from popping.
+    #[arg(
+        long,
+        value_name = "MILLIS",
+        value_parser = clap::value_parser!(u16).range(0..=5000),
+        default_value_t = 500,
+        env = "PLEEZER_EXIT_FADE"
+    )]
+    exit_fade: u16,
+
+    /// Stop playback immediately when shutting down, without fading out
+    #[arg(long, default_value_t = false, env = "PLEEZER_NO_FADE_ON_EXIT")]
+    no_fade_on_exit: bool,
+
     /// Address to bind outgoing connections to
     ///
     /// Defaults to "0.0.0.0" (IPv4 any address) since Deezer services are IPv4-only
@@ -168,10 +526,211 @@ struct Args {
     #[arg(long, default_value = "0.0.0.0", env = "PLEEZER_BIND")]
     bind: String,
 
+    /// Nameserver to resolve hostnames with, instead of the system resolver
+    ///
+    /// Either an IP address for plain DNS (port 53 unless specified), or an
+    /// https:// URL for DNS-over-HTTPS.
+    #[arg(long, value_name = "IP|URL", env = "PLEEZER_RESOLVER")]
+    resolver: Option<Nameserver>,
+
+    /// PEM file with additional certificate authorities to trust
+    ///
+    /// For networks with TLS-intercepting proxies. Certificates are trusted
+    /// in addition to those of the operating system.
+    #[arg(long, value_name = "FILE", value_hint = ValueHint::FilePath, env = "PLEEZER_CA_CERT")]
+    ca_cert: Option<PathBuf>,
+
+    /// Extra HTTP header to send with gateway requests, as "Name: value"
+    ///
+    /// Can be given multiple times. For proxies or CDNs with header-based
+    /// access control. Headers like Cookie and User-Agent cannot be set.
+    #[arg(
+        long = "header",
+        value_name = "HEADER",
+        value_parser = parse_header,
+        env = "PLEEZER_HEADER"
+    )]
+    headers: Vec<(String, String)>,
+
+    /// Report this operating system name to Deezer instead of the detected one
+    ///
+    /// Used in the User-Agent. For reproducible testing of OS-dependent
+    /// gateway behavior.
+    #[arg(long, env = "PLEEZER_OS_NAME")]
+    os_name: Option<String>,
+
+    /// Report this operating system version to Deezer instead of the detected one
+    ///
+    /// Used in the User-Agent. For reproducible testing of OS-dependent
+    /// gateway behavior.
+    #[arg(long, env = "PLEEZER_OS_VERSION")]
+    os_version: Option<String>,
+
+    /// Use this client ID instead of a random one on every start
+    ///
+    /// A number of 9 digits. Keeping it the same across restarts helps to
+    /// correlate sessions during protocol investigation.
+    #[arg(
+        long,
+        value_parser = parse_client_id,
+        env = "PLEEZER_CLIENT_ID"
+    )]
+    client_id: Option<usize>,
+
+    /// Ratio of random jitter in reconnect backoff (0.0-1.0)
+    ///
+    /// Higher values spread reconnects of many instances further apart,
+    /// for example after a Deezer outage. 1.0 is full jitter.
+    #[arg(
+        long,
+        value_name = "RATIO",
+        value_parser = parse_ratio,
+        default_value_t = DEFAULT_RECONNECT_JITTER,
+        env = "PLEEZER_RECONNECT_JITTER"
+    )]
+    reconnect_jitter: f32,
+
+    /// Number of reconnect attempts before giving up (0 for unlimited)
+    ///
+    /// With unlimited attempts, pleezer never exits on network errors and
+    /// keeps retrying at the maximum backoff of 10 seconds.
+    #[arg(
+        long,
+        value_name = "COUNT",
+        default_value_t = BACKOFF_ATTEMPTS,
+        env = "PLEEZER_RETRY_ATTEMPTS"
+    )]
+    retry_attempts: u32,
+
+    /// Maximum random delay in milliseconds before connecting at startup
+    ///
+    /// Spreads connections of many instances that start together.
+    #[arg(long, value_name = "MILLIS", env = "PLEEZER_STARTUP_JITTER_MAX")]
+    startup_jitter_max: Option<u64>,
+
+    /// Icecast mountpoint to rebroadcast playback to
+    ///
+    /// For example: http://localhost:8000/pleezer.mp3
+    /// Credentials are read from the secrets file.
+    #[cfg(feature = "icecast")]
+    #[arg(long, value_hint = ValueHint::Url, env = "PLEEZER_ICECAST_URL")]
+    icecast_url: Option<String>,
+
+    /// Show a desktop notification when the track changes
+    ///
+    /// Shows the title, artist and album through the notification service
+    /// of the operating system.
+    #[cfg(feature = "notify")]
+    #[arg(long, default_value_t = false, env = "PLEEZER_NOTIFY")]
+    notify: bool,
+
     /// Script to execute when events occur
     #[arg(long, value_hint = ValueHint::ExecutablePath, env = "PLEEZER_HOOK")]
     hook: Option<String>,
 
+    /// Script that decides whether to accept a controller connection
+    ///
+    /// Receives CONTROLLER_ID and USER_ID in its environment. Connections
+    /// are accepted when it exits with status 0, and rejected otherwise.
+    #[arg(long, value_hint = ValueHint::ExecutablePath, env = "PLEEZER_CONNECT_POLICY")]
+    connect_policy: Option<String>,
+
+    /// Whether to wait for the hook script to finish
+    ///
+    /// In blocking mode, events are handled one after another, and scripts
+    /// exceeding the hook timeout are killed.
+    /// Values: detached, blocking
+    #[arg(long, default_value_t = HookMode::Detached, env = "PLEEZER_HOOK_MODE")]
+    hook_mode: HookMode,
+
+    /// Maximum time in milliseconds to wait for the hook script in blocking mode
+    #[arg(
+        long,
+        value_name = "MILLIS",
+        default_value_t = 5000,
+        value_parser = clap::value_parser!(u64).range(1..),
+        env = "PLEEZER_HOOK_TIMEOUT"
+    )]
+    hook_timeout: u64,
+
+    /// Write the current track and queue to this file when they change
+    ///
+    /// The file is replaced atomically, so it can be watched by
+    /// now-playing displays.
+    #[arg(long, value_hint = ValueHint::FilePath, env = "PLEEZER_NOWPLAYING_FILE")]
+    nowplaying_file: Option<String>,
+
+    /// Format of the now-playing file
+    ///
+    /// Values: m3u, json
+    #[arg(
+        long,
+        default_value_t = now_playing::Format::M3u,
+        requires = "nowplaying_file",
+        env = "PLEEZER_NOWPLAYING_FORMAT"
+    )]
+    nowplaying_format: now_playing::Format,
+
+    /// Export the complete queue to this file on SIGUSR1
+    ///
+    /// Saves what you were listening to as a playlist.
+    #[arg(long, value_hint = ValueHint::FilePath, env = "PLEEZER_EXPORT_FILE")]
+    export_file: Option<String>,
+
+    /// Format of the queue export
+    ///
+    /// Values: m3u, json
+    #[arg(
+        long,
+        default_value_t = now_playing::Format::M3u,
+        requires = "export_file",
+        env = "PLEEZER_EXPORT_FORMAT"
+    )]
+    export_format: now_playing::Format,
+
+    /// When to use colors in log output
+    ///
+    /// Values: auto (only when writing to a terminal), always, never
+    #[arg(long, value_enum, default_value_t = ColorChoice::Auto, env = "PLEEZER_COLOR")]
+    color: ColorChoice,
+
+    /// Write log output to this file instead of stderr
+    ///
+    /// The file is rotated when it exceeds the maximum size.
+    #[arg(long, value_hint = ValueHint::FilePath, env = "PLEEZER_LOG_FILE")]
+    log_file: Option<String>,
+
+    /// Maximum size of the log file in MiB before rotating it
+    #[arg(
+        long,
+        value_name = "MIB",
+        default_value_t = 10,
+        value_parser = clap::value_parser!(u64).range(1..),
+        requires = "log_file",
+        env = "PLEEZER_LOG_MAX_SIZE"
+    )]
+    log_max_size: u64,
+
+    /// Number of rotated log files to keep
+    #[arg(
+        long,
+        value_name = "COUNT",
+        default_value_t = 5,
+        value_parser = clap::value_parser!(u8).range(1..),
+        requires = "log_file",
+        env = "PLEEZER_LOG_MAX_FILES"
+    )]
+    log_max_files: u8,
+
+    /// Also write log output to stderr when logging to a file
+    #[arg(
+        long,
+        default_value_t = false,
+        requires = "log_file",
+        env = "PLEEZER_LOG_STDERR"
+    )]
+    log_stderr: bool,
+
     /// Suppress all output except warnings and errors
     #[arg(short, long, default_value_t = false, group = ARGS_GROUP_LOGGING, env = "PLEEZER_QUIET")]
     quiet: bool,
@@ -183,6 +742,22 @@ struct Args {
     #[arg(short, long, action = clap::ArgAction::Count, group = ARGS_GROUP_LOGGING, env = "PLEEZER_VERBOSE")]
     verbose: u8,
 
+    /// Check the decryption key and exit
+    ///
+    /// Verifies the decryption secret from the secrets file, or the one from
+    /// the Deezer web player if none is set, and reports whether tracks will
+    /// decrypt correctly.
+    #[arg(long, default_value_t = false, env = "PLEEZER_CHECK_DECRYPT")]
+    check_decrypt: bool,
+
+    /// Play a local audio file or HTTP URL and exit
+    ///
+    /// For debugging the audio pipeline without Deezer: the audio is decoded,
+    /// normalized and output like tracks, but bypasses the gateway and
+    /// decryption. Needs no secrets file.
+    #[arg(long, value_name = "PATH_OR_URL", value_hint = ValueHint::AnyPath, env = "PLEEZER_PLAY_LOCAL")]
+    play_local: Option<String>,
+
     /// Monitor the Deezer Connect websocket without participating
     ///
     /// A development tool that observes websocket traffic. Requires verbose
@@ -194,6 +769,166 @@ struct Args {
         env = "PLEEZER_EAVESDROP"
     )]
     eavesdrop: bool,
+
+    /// Emit events for playback of other devices while eavesdropping
+    ///
+    /// Turns progress reports between other devices on the account into
+    /// `observed` events for hook scripts and --print-events, without acting
+    /// on them.
+    #[arg(
+        long,
+        default_value_t = false,
+        requires = "eavesdrop",
+        env = "PLEEZER_EAVESDROP_EVENTS"
+    )]
+    eavesdrop_events: bool,
+
+    /// Drop incoming messages of these types before handling
+    ///
+    /// A development tool to observe controller behavior when certain
+    /// messages go unanswered. Takes a comma-separated list of wire format
+    /// names, for example: refreshQueue,status. Requires verbose logging
+    /// (-v or -vv). This breaks normal operation: for protocol research only.
+    #[arg(
+        long,
+        value_name = "TYPES",
+        value_delimiter = ',',
+        requires = "verbose",
+        env = "PLEEZER_IGNORE_MESSAGE_TYPES"
+    )]
+    ignore_message_types: Vec<MessageType>,
+
+    /// Print events and their hook script variables to stdout
+    ///
+    /// A development tool for writing hook scripts: shows which events fire
+    /// with which variables, with or without a hook script.
+    #[arg(long, default_value_t = false, env = "PLEEZER_PRINT_EVENTS")]
+    print_events: bool,
+}
+
+/// When to use colors in log output.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, clap::ValueEnum)]
+enum ColorChoice {
+    /// Use colors only when writing to a terminal
+    #[default]
+    Auto,
+
+    /// Always use colors
+    Always,
+
+    /// Never use colors
+    Never,
+}
+
+impl From<ColorChoice> for env_logger::WriteStyle {
+    fn from(choice: ColorChoice) -> Self {
+        match choice {
+            ColorChoice::Auto => Self::Auto,
+            ColorChoice::Always => Self::Always,
+            ColorChoice::Never => Self::Never,
+        }
+    }
+}
+
+/// Log file that rotates when it exceeds a maximum size.
+///
+/// On rotation, `pleezer.log` is renamed to `pleezer.log.1`, `pleezer.log.1`
+/// to `pleezer.log.2` and so on, dropping the oldest file beyond the maximum
+/// number of files.
+struct RotatingFile {
+    /// Path of the current log file
+    path: PathBuf,
+
+    /// Current log file, opened for appending
+    file: fs::File,
+
+    /// Size of the current log file in bytes
+    size: u64,
+
+    /// Size in bytes beyond which the log file is rotated
+    max_size: u64,
+
+    /// Number of rotated log files to keep
+    max_files: u8,
+
+    /// Whether to also write log output to stderr
+    stderr: bool,
+}
+
+impl RotatingFile {
+    /// Opens a log file for appending, creating it if it does not exist.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the file cannot be opened.
+    fn open(path: PathBuf, max_size: u64, max_files: u8, stderr: bool) -> io::Result<Self> {
+        let file = Self::append(&path)?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            path,
+            file,
+            size,
+            max_size,
+            max_files,
+            stderr,
+        })
+    }
+
+    /// Opens a file for appending, creating it if it does not exist.
+    fn append(path: &Path) -> io::Result<fs::File> {
+        fs::OpenOptions::new().create(true).append(true).open(path)
+    }
+
+    /// Returns the path of the rotated log file with the given index.
+    fn rotated_path(&self, index: u8) -> PathBuf {
+        let mut path = OsString::from(self.path.as_os_str());
+        path.push(format!(".{index}"));
+        PathBuf::from(path)
+    }
+
+    /// Shifts the rotated log files and starts a new log file.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if a file cannot be renamed or opened.
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        for index in (1..self.max_files).rev() {
+            let from = self.rotated_path(index);
+            if from.exists() {
+                fs::rename(from, self.rotated_path(index + 1))?;
+            }
+        }
+        fs::rename(&self.path, self.rotated_path(1))?;
+
+        self.file = Self::append(&self.path)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.size > 0 && self.size + buf.len() as u64 > self.max_size {
+            // The logger cannot log its own errors, and failing to rotate
+            // should not prevent logging to the current file.
+            if let Err(e) = self.rotate() {
+                eprintln!("failed to rotate log file {}: {e}", self.path.display());
+            }
+        }
+
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        if self.stderr {
+            let _ = io::stderr().write_all(&buf[..written]);
+        }
+
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
 }
 
 /// Initialize logging system.
@@ -204,15 +939,24 @@ struct Args {
 /// * `-vv` sets Trace level
 /// * `RUST_LOG` environment variable provides defaults
 /// * External crates are limited to Warning level
+/// * `--color` controls ANSI colors, by default only when stderr is a terminal
+/// * `--log-file` writes to a rotating log file instead of stderr, or in
+///   addition to stderr with `--log-stderr`
+///
+/// Levels apply equally to the log file and stderr.
 ///
 /// # Arguments
 ///
 /// * `config` - Command line arguments containing logging options
 ///
+/// # Errors
+///
+/// Returns error if the log file cannot be opened.
+///
 /// # Panics
 ///
 /// Panics if logger is already initialized.
-fn init_logger(config: &Args) {
+fn init_logger(config: &Args) -> Result<()> {
     let mut logger = env_logger::Builder::from_env(
         // Note: if you change the default logging level here, then you should
         // probably also change the verbosity levels below.
@@ -256,7 +1000,103 @@ fn init_logger(config: &Args) {
         logger.filter_module(external_module, external_level);
     }
 
+    logger.write_style(config.color.into());
+
+    if let Some(log_file) = &config.log_file {
+        let file = RotatingFile::open(
+            PathBuf::from(log_file),
+            config.log_max_size.saturating_mul(1024 * 1024),
+            config.log_max_files,
+            config.log_stderr,
+        )
+        .map_err(|e| {
+            io::Error::new(e.kind(), format!("failed to open log file {log_file}: {e}"))
+        })?;
+        logger.target(env_logger::Target::Pipe(Box::new(file)));
+    }
+
     logger.init();
+    Ok(())
+}
+
+/// Parses an HTTP header in "Name: value" format.
+///
+/// Names and values are validated when the configuration is validated.
+///
+/// # Errors
+///
+/// Returns error if the header has no colon or an empty name.
+fn parse_header(header: &str) -> Result<(String, String)> {
+    match header.split_once(':') {
+        Some((name, value)) if !name.trim().is_empty() => {
+            Ok((name.trim().to_string(), value.trim().to_string()))
+        }
+        _ => Err(Error::invalid_argument(format!(
+            "{header} is not in \"Name: value\" format"
+        ))),
+    }
+}
+
+/// Parses an audio output configuration from JSON.
+///
+/// # Errors
+///
+/// Returns `InvalidArgument` if the JSON is invalid or has unknown fields.
+fn parse_output_config(value: &str) -> Result<OutputConfig> {
+    serde_json::from_str(value)
+        .map_err(|e| Error::invalid_argument(format!("invalid device configuration: {e}")))
+}
+
+/// Parses an audio quality by name.
+///
+/// # Errors
+///
+/// Returns error if the name is not `low`, `standard`, `high` or `lossless`.
+fn parse_quality(value: &str) -> Result<AudioQuality> {
+    match value.to_lowercase().parse::<AudioQuality>() {
+        Ok(AudioQuality::Unknown) | Err(_) => Err(Error::invalid_argument(format!(
+            "{value} is not one of low, standard, high or lossless"
+        ))),
+        Ok(quality) => Ok(quality),
+    }
+}
+
+/// Parses a ratio between 0.0 and 1.0 inclusive.
+///
+/// # Errors
+///
+/// Returns error if the value is not a number or out of range.
+fn parse_ratio(value: &str) -> Result<f32> {
+    let ratio: f32 = value
+        .parse()
+        .map_err(|_| Error::invalid_argument(format!("{value} is not a number")))?;
+    if !(0.0..=1.0).contains(&ratio) {
+        return Err(Error::out_of_range(format!(
+            "{value} is not between 0.0 and 1.0"
+        )));
+    }
+
+    Ok(ratio)
+}
+
+/// Parses a client ID within [`Config::CLIENT_ID_RANGE`].
+///
+/// # Errors
+///
+/// Returns error if the value is not a number or out of range.
+fn parse_client_id(value: &str) -> Result<usize> {
+    let client_id: usize = value
+        .parse()
+        .map_err(|_| Error::invalid_argument(format!("{value} is not a number")))?;
+    if !Config::CLIENT_ID_RANGE.contains(&client_id) {
+        return Err(Error::out_of_range(format!(
+            "{value} is not between {} and {}",
+            Config::CLIENT_ID_RANGE.start(),
+            Config::CLIENT_ID_RANGE.end()
+        )));
+    }
+
+    Ok(client_id)
 }
 
 /// Parse the secrets file into a configuration value.
@@ -264,29 +1104,32 @@ fn init_logger(config: &Args) {
 /// # Security
 ///
 /// To prevent resource exhaustion attacks:
-/// * File size is limited to 1024 bytes
+/// * File size is limited to `max_size` bytes
 /// * Contents must be valid UTF-8
 /// * Must be valid TOML format
 ///
 /// # Arguments
 ///
 /// * `secrets` - Path to the secrets file
+/// * `max_size` - Maximum file size in bytes
 ///
 /// # Errors
 ///
-/// Returns error if:
-/// * File cannot be read
-/// * File exceeds size limit
-/// * Content isn't valid UTF-8
-/// * Content isn't valid TOML
-fn parse_secrets(secrets: impl AsRef<Path>) -> Result<toml::Value> {
+/// Returns `OutOfRange` if the file exceeds the size limit.
+///
+/// Returns `InvalidArgument` if the content isn't valid TOML.
+///
+/// Returns an I/O error if the file cannot be read or isn't valid UTF-8.
+fn parse_secrets(secrets: impl AsRef<Path>, max_size: u64) -> Result<toml::Value> {
     // Prevent out-of-memory condition: secrets file should be small.
     let attributes = fs::metadata(&secrets)?;
     let file_size = attributes.len();
-    if file_size > 1024 {
-        return Err(Error::out_of_range(
-            "{secrets} too large: {file_size} bytes",
-        ));
+    if file_size > max_size {
+        return Err(Error::out_of_range(format!(
+            "{} too large: {file_size} bytes exceeds the limit of {max_size} bytes; \
+             raise it with --secrets-max-size",
+            secrets.as_ref().to_string_lossy()
+        )));
     }
 
     let contents = fs::read_to_string(&secrets)?;
@@ -298,6 +1141,36 @@ fn parse_secrets(secrets: impl AsRef<Path>) -> Result<toml::Value> {
     })
 }
 
+/// Opens a local audio file or HTTP URL for playback.
+///
+/// URLs with an `http` or `https` scheme are downloaded into memory in full,
+/// anything else is opened as a file.
+///
+/// # Arguments
+///
+/// * `source` - Path or URL of the audio
+/// * `config` - Configuration for the HTTP client
+///
+/// # Errors
+///
+/// Returns error if the file cannot be opened or the download fails.
+async fn open_local(source: &str, config: &Config) -> Result<AudioFile> {
+    match source.parse::<url::Url>() {
+        Ok(url) if matches!(url.scheme(), "http" | "https") => {
+            let client = http::Client::without_cookies(config)?;
+            let response = client.execute(client.get(url, "")).await?;
+            let bytes = response.bytes().await?.to_vec();
+            let byte_len = bytes.len() as u64;
+            Ok(AudioFile::from_reader(Cursor::new(bytes), Some(byte_len)))
+        }
+        _ => {
+            let file = fs::File::open(source)?;
+            let byte_len = file.metadata()?.len();
+            Ok(AudioFile::from_reader(file, Some(byte_len)))
+        }
+    }
+}
+
 /// Main application loop.
 ///
 /// Handles the core application lifecycle:
@@ -310,11 +1183,14 @@ fn parse_secrets(secrets: impl AsRef<Path>) -> Result<toml::Value> {
 /// # Arguments
 ///
 /// * `args` - Parsed command line arguments
+/// * `resume` - Playback state handed across configuration reloads
 ///
 /// # Returns
 ///
 /// Returns the signal that triggered the shutdown, or an error if one occurred.
-/// SIGHUP triggers a configuration reload and restart.
+/// SIGHUP triggers a configuration reload and restart. On reload, the playback
+/// state is stored in `resume`, to be resumed by the next run when a
+/// controller connects.
 ///
 /// # Errors
 ///
@@ -327,7 +1203,7 @@ fn parse_secrets(secrets: impl AsRef<Path>) -> Result<toml::Value> {
 /// * Unrecoverable network error occurs
 ///
 /// Network errors that might be temporary will trigger retry instead.
-async fn run(args: Args) -> Result<ShutdownSignal> {
+async fn run(args: Args, resume: &mut Option<remote::Resume>) -> Result<ShutdownSignal> {
     if args.device.as_ref().is_some_and(|device| device == "?") {
         // List available devices and exit.
         let devices = Player::enumerate_devices();
@@ -348,30 +1224,47 @@ async fn run(args: Args) -> Result<ShutdownSignal> {
         info!("using proxy: {proxy}");
     }
 
+    if let Some(resolver) = &args.resolver {
+        info!("using resolver: {resolver}");
+    }
+
     let config = {
-        // Get the credentials from the secrets file.
-        info!("parsing secrets from {}", args.secrets);
-        let secrets = parse_secrets(args.secrets)?;
-
-        let credentials = match secrets.get("arl").and_then(|value| value.as_str()) {
-            Some(arl) => {
-                let result = arl.parse::<Arl>()?;
-                info!("using arl from secrets file");
-                Credentials::Arl(result)
+        // Get the credentials from the secrets file. Local playback does not
+        // log in, so it does not need a secrets file.
+        let secrets = if args.play_local.is_some() {
+            toml::Value::Table(toml::Table::new())
+        } else {
+            info!("parsing secrets from {}", args.secrets);
+            parse_secrets(args.secrets, args.secrets_max_size)?
+        };
+
+        let credentials = if args.play_local.is_some() {
+            // Local playback does not log in.
+            Credentials::Login {
+                email: String::new(),
+                password: String::new(),
             }
-            None => {
-                let email = secrets
-                    .get("email")
-                    .and_then(|email| email.as_str())
-                    .ok_or_else(|| Error::unauthenticated("email not found"))?;
-                let password = secrets
-                    .get("password")
-                    .and_then(|password| password.as_str())
-                    .ok_or_else(|| Error::unauthenticated("password not found"))?;
+        } else {
+            match secrets.get("arl").and_then(|value| value.as_str()) {
+                Some(arl) => {
+                    let result = arl.parse::<Arl>()?;
+                    info!("using arl from secrets file");
+                    Credentials::Arl(result)
+                }
+                None => {
+                    let email = secrets
+                        .get("email")
+                        .and_then(|email| email.as_str())
+                        .ok_or_else(|| Error::unauthenticated("email not found"))?;
+                    let password = secrets
+                        .get("password")
+                        .and_then(|password| password.as_str())
+                        .ok_or_else(|| Error::unauthenticated("password not found"))?;
 
-                Credentials::Login {
-                    email: email.to_string(),
-                    password: password.to_string(),
+                    Credentials::Login {
+                        email: email.to_string(),
+                        password: password.to_string(),
+                    }
                 }
             }
         };
@@ -384,96 +1277,165 @@ async fn run(args: Args) -> Result<ShutdownSignal> {
             None => None,
         };
 
-        let app_name = env!("CARGO_PKG_NAME").to_owned();
-        let app_version = env!("CARGO_PKG_VERSION").to_owned();
-        let app_lang = "en".to_owned();
-
-        let device_id = *machine_uid::get()
-            .and_then(|uid| uid.parse().map_err(Into::into))
-            .unwrap_or_else(|_| {
-                warn!("could not get machine uuid, using random device id");
-                Uuid::fast_v4()
-            });
-        trace!("device uuid: {device_id}");
-
-        // Additional `User-Agent` string checks on top of what
-        // `reqwest::HeaderValue` already checks.
-        let illegal_chars = |chr| chr == '/' || chr == ';';
-        if app_name.is_empty()
-            || app_name.contains(illegal_chars)
-            || app_version.is_empty()
-            || app_version.contains(illegal_chars)
-            || app_lang.chars().count() != 2
-            || app_lang.contains(illegal_chars)
-        {
-            return Err(Error::invalid_argument(format!(
-            "application name, version and/or language invalid (\"{app_name}\"; \"{app_version}\"; \"{app_lang}\")")
-        ));
-        }
+        #[cfg(feature = "icecast")]
+        let icecast = match args.icecast_url {
+            Some(url) => {
+                let username = secrets
+                    .get("icecast_username")
+                    .and_then(|username| username.as_str())
+                    .unwrap_or("source");
+                let password = secrets
+                    .get("icecast_password")
+                    .and_then(|password| password.as_str())
+                    .ok_or_else(|| Error::unauthenticated("icecast_password not found"))?;
 
-        let os_name = match std::env::consts::OS {
-            "macos" => "osx",
-            other => other,
+                Some(IcecastServer {
+                    url: url.parse()?,
+                    username: username.to_string(),
+                    password: password.to_string(),
+                })
+            }
+            None => None,
         };
+        #[cfg(not(feature = "icecast"))]
+        let icecast = None;
 
-        let os_version = match std::env::consts::OS {
-            "linux" => sysinfo::System::kernel_version(),
-            _ => sysinfo::System::os_version(),
-        }
-        .unwrap_or("0".to_string());
-        if os_name.is_empty()
-            || os_name.contains(illegal_chars)
-            || os_version.is_empty()
-            || os_version.contains(illegal_chars)
-        {
-            return Err(Error::invalid_argument(format!(
-                "os name and/or version invalid (\"{os_name}\"; \"{os_version}\")"
-            )));
-        }
-
-        // Set `User-Agent` to be served like Deezer on desktop.
-        let user_agent = format!(
-            "{app_name}/{app_version} (Rust; {os_name}/{os_version}; like Desktop; {app_lang})"
-        );
-        trace!("user agent: {user_agent}");
+        #[cfg(feature = "notify")]
+        let notify = args.notify;
+        #[cfg(not(feature = "notify"))]
+        let notify = false;
 
-        // Deezer on desktop uses a new `cid` on every start.
-        let client_id = fastrand::usize(100_000_000..=999_999_999);
-        trace!("client id: {client_id}");
-
-        Config {
-            app_name: app_name.clone(),
-            app_version,
-            app_lang,
-
-            device_id,
+        let defaults = Config::new(credentials)?;
+        let user_agent = if args.os_name.is_some() || args.os_version.is_some() {
+            Config::user_agent_for_os(
+                &defaults.app_name,
+                &defaults.app_version,
+                &defaults.app_lang,
+                args.os_name.as_deref().unwrap_or(Config::os_name()),
+                &args.os_version.clone().unwrap_or_else(Config::os_version),
+            )?
+        } else {
+            defaults.user_agent.clone()
+        };
+        let client_id = args.client_id.unwrap_or(defaults.client_id);
+        let config = Config {
+            user_agent,
+            client_id,
             device_type: args.device_type,
-            device_name: args
-                .name
-                .or_else(|| sysinfo::System::host_name().clone())
-                .unwrap_or_else(|| app_name.clone()),
+            device_id_format: args.device_id_format,
+            device_name: args.name.unwrap_or(defaults.device_name),
 
             interruptions: !args.no_interruptions,
+            hide_when_taken: args.hide_when_taken,
+            allow_degraded: args.allow_degraded,
+            takeover_cooldown: args.takeover_cooldown.map(Duration::from_secs),
+            announce_interval: args.announce_interval.map(Duration::from_secs),
+            discovery_grace: args
+                .discovery_grace
+                .map_or(Duration::ZERO, Duration::from_secs),
+            max_session_duration: args.max_session_duration.map(Duration::from_secs),
+            flow_extend_interval: Duration::from_secs(args.flow_extend_interval),
+            flow_lookahead: args.flow_lookahead,
+            websocket_frame_size_max: usize::from(args.websocket_frame_max) * 1024,
+            websocket_message_size_max: usize::from(args.websocket_message_max) * 1024,
+            websocket_buffer_max: usize::from(args.websocket_buffer_max) * 1024,
+            stop_after: args.stop_after,
+            exit_after_stop: args.exit_after_stop,
             normalization: args.normalize_volume,
+            preamp_db: args.preamp,
             initial_volume: args
                 .initial_volume
                 .map(|volume| Percentage::from_percent(volume as f32)),
+            initial_volume_mode: args.initial_volume_mode,
+            initial_quality: args.initial_quality,
+            max_concurrent_downloads: args.max_concurrent_downloads.into(),
+            streaming: args.streaming,
+            reload_on_quality_change: args.reload_on_quality_change,
+            max_track_duration: args.max_track_duration.map(Duration::from_secs),
+            device_open_timeout: args.device_open_timeout.map(Duration::from_secs),
+            dither: args.dither,
+            keepalive_audio: args.keepalive_audio,
+            no_audio: args.no_audio,
 
             hook: args.hook,
+            connect_policy: args.connect_policy,
+            hook_mode: args.hook_mode,
+            hook_timeout: Duration::from_millis(args.hook_timeout),
+            now_playing_file: args.nowplaying_file.map(PathBuf::from),
+            now_playing_format: args.nowplaying_format,
+            export_file: args.export_file.map(PathBuf::from),
+            export_format: args.export_format,
 
-            client_id,
-            user_agent,
-
-            credentials,
             bf_secret,
 
             eavesdrop: args.eavesdrop,
+            eavesdrop_events: args.eavesdrop_events,
+            ignore_message_types: args.ignore_message_types,
+            print_events: args.print_events,
+            listening_history: !args.no_listening_history,
+            progress_reports: !args.no_progress_reports,
+            exit_fade: (!args.no_fade_on_exit && args.exit_fade > 0)
+                .then(|| Duration::from_millis(args.exit_fade.into())),
             bind_address: args.bind.parse()?,
-        }
+            gateway_headers: args.headers,
+            resolver: args.resolver,
+            ca_cert: args.ca_cert,
+
+            icecast,
+            notify,
+
+            ..defaults
+        };
+
+        config.validate()?;
+        config
+    };
+
+    if args.check_decrypt {
+        // Check the decryption key and exit.
+        let bf_secret = if let Some(bf_secret) = config.bf_secret {
+            info!("checking bf_secret from secrets file");
+            bf_secret
+        } else {
+            info!("no bf_secret in secrets file, checking the one from the web player");
+            let client = http::Client::without_cookies(&config)?;
+            Config::try_key(&client).await?
+        };
+
+        Config::verify_key(&bf_secret)?;
+        info!("bf_secret is valid: tracks will decrypt correctly");
+        return Ok(ShutdownSignal::Interrupt);
+    }
+
+    let output_config = match &args.device_config {
+        Some(output_config) => output_config.clone(),
+        None => args.device.as_deref().unwrap_or_default().parse()?,
     };
 
-    let player = Player::new(&config, args.device.as_deref().unwrap_or_default()).await?;
+    if let Some(source) = &args.play_local {
+        // Play the audio without Deezer and exit. This needs no decryption
+        // key, so none is fetched.
+        let file = open_local(source, &config).await?;
+        let mut player = Player::without_decryption(&config, &output_config).await?;
+        info!("playing {source}");
+        player.play_file(file).await?;
+        player.stop();
+        return Ok(ShutdownSignal::Interrupt);
+    }
+
+    let player = Player::new(&config, &output_config).await?;
     let mut client = remote::Client::new(&config, player)?;
+    if let Some(resume) = resume.take() {
+        client.resume(resume);
+    }
+
+    // Spread out connections of instances that start together.
+    if let Some(max) = args.startup_jitter_max.filter(|max| *max > 0) {
+        let delay = Duration::from_millis(fastrand::u64(..=max));
+        info!("delaying connection by {delay:?}");
+        tokio::time::sleep(delay).await;
+    }
+
     let mut signals = signal::Handler::new()?;
 
     // Main application loop. This restarts the new remote client when it gets disconnected for
@@ -489,9 +1451,13 @@ async fn run(args: Args) -> Result<ShutdownSignal> {
                 match signal {
                     ShutdownSignal::Interrupt | ShutdownSignal::Terminate => {
                         info!("received {signal}, shutting down");
+                        if let Some(duration) = config.exit_fade {
+                            client.fade_out(duration).await;
+                        }
                     }
                     ShutdownSignal::Reload => {
                         info!("received {signal}, restarting client");
+                        *resume = client.resume_state();
                     }
                 }
                 client.stop().await;
@@ -499,13 +1465,31 @@ async fn run(args: Args) -> Result<ShutdownSignal> {
             }
 
             result = async {
-                for (i, backoff) in Backoff::new(BACKOFF_ATTEMPTS, MIN_BACKOFF, MAX_BACKOFF).into_iter().enumerate() {
+                let unlimited = args.retry_attempts == 0;
+                let attempts = if unlimited { BACKOFF_ATTEMPTS } else { args.retry_attempts };
+                let limit = if unlimited { "unlimited".to_string() } else { attempts.to_string() };
+
+                let mut backoffs = Backoff::new(attempts, MIN_BACKOFF, MAX_BACKOFF);
+                backoffs.set_jitter(args.reconnect_jitter);
+
+                // When unlimited, continue at the maximum backoff after the
+                // exponential backoff is spent. Otherwise, end with `None`.
+                let backoffs = backoffs
+                    .into_iter()
+                    .flatten()
+                    .chain(iter::repeat(MAX_BACKOFF).take_while(|_| unlimited))
+                    .map(Some)
+                    .chain(iter::once(None));
+
+                for (i, backoff) in backoffs.enumerate() {
                     match client.start().await {
                         Ok(result) => return Ok(result),
                         Err(e) => {
                             match e.kind {
                                 // Bail out if the user is:
-                                // - not able to login
+                                // - not able to login (failures of the
+                                //   authentication endpoint itself are
+                                //   `Unavailable` and retried below)
                                 // - not allowed to use remote control
                                 ErrorKind::PermissionDenied |
                                 // - using too many devices
@@ -519,11 +1503,18 @@ async fn run(args: Args) -> Result<ShutdownSignal> {
                                     warn!("{e}");
                                     return Ok(());
                                 }
+                                // Log in again right away when the session was
+                                // revoked, for example by logging out elsewhere.
+                                // Logins with a revoked ARL are denied above,
+                                // and repeated revocations back off below.
+                                ErrorKind::Unauthenticated if i == 0 => {
+                                    warn!("{e}; logging in again");
+                                }
                                 _ => match backoff {
-                                    // Retry `BACKOFF_ATTEMPTS` times with exponential backoff
+                                    // Retry `--retry-attempts` times with exponential backoff
                                     // on network errors.
                                     Some(duration) => {
-                                        error!("{e}; retrying in {duration:?} ({}/{BACKOFF_ATTEMPTS})", i+1);
+                                        error!("{e}; retrying in {duration:?} ({}/{limit})", i+1);
                                         tokio::time::sleep(duration).await;
                                     }
                                     // Bail out if we have exhausted all retries.
@@ -537,6 +1528,10 @@ async fn run(args: Args) -> Result<ShutdownSignal> {
                 Ok(())
             } => {
                 match result {
+                    Ok(()) if client.is_stopped() => {
+                        info!("stop limit reached, shutting down");
+                        break Ok(ShutdownSignal::Interrupt);
+                    }
                     Ok(()) => { info!("restarting client"); }
                     Err(e) => break Err(e),
                 }
@@ -558,12 +1553,21 @@ async fn run(args: Args) -> Result<ShutdownSignal> {
 ///
 /// Exits with status code:
 /// - 0 for clean shutdown
-/// - 1 if an error occurs
+/// - 1 for other errors
+/// - 2 for configuration errors
+/// - 3 for authentication and permission errors
+/// - 4 for account limitations
+/// - 5 for network errors
+///
+/// See [`ErrorKind::exit_code`] for the full mapping.
 #[tokio::main]
 async fn main() {
     // `clap` handles our command line arguments and help text.
     let args = Args::parse();
-    init_logger(&args);
+    if let Err(e) = init_logger(&args) {
+        eprintln!("{e}");
+        process::exit(e.kind.exit_code());
+    }
 
     // Dump command line arguments before we do anything more.
     // This aids in debugging of whatever comes next.
@@ -582,8 +1586,9 @@ async fn main() {
 
     info!("starting {name}/{version}; {BUILD_PROFILE}");
 
+    let mut resume = None;
     loop {
-        match run(args.clone()).await {
+        match run(args.clone(), &mut resume).await {
             Ok(signal) => {
                 if signal == ShutdownSignal::Reload {
                     continue;
@@ -593,7 +1598,7 @@ async fn main() {
             }
             Err(e) => {
                 error!("{e}");
-                process::exit(1);
+                process::exit(e.kind.exit_code());
             }
         }
     }
@@ -0,0 +1,60 @@
+//! Desktop notifications of the track now playing.
+//!
+//! Shows a desktop notification with the title, artist and album when the
+//! track changes, for ambient awareness without a hook script. Screen readers
+//! announce these notifications, which makes track changes accessible to
+//! users who cannot see the controller.
+//!
+//! Notifications are sent through the notification service of the operating
+//! system, like D-Bus on Linux. Sending blocks, so it runs on the blocking
+//! thread pool. Failures are logged and otherwise ignored: a missing
+//! notification service never interrupts playback.
+//!
+//! Cover art is not included, because notification services load images
+//! from local files only.
+//!
+//! # Example
+//!
+//! ```rust
+//! use pleezer::notify;
+//!
+//! notify::track_changed(&track_info);
+//! ```
+
+use notify_rust::Notification;
+
+use crate::{events::TrackInfo, track::TrackType};
+
+/// Shows a desktop notification for a track that started playing.
+///
+/// Returns immediately: the notification is sent in the background.
+///
+/// # Arguments
+///
+/// * `track` - Track that started playing
+pub fn track_changed(track: &TrackInfo) {
+    let summary = track.title.clone().unwrap_or_else(|| track.artist.clone());
+
+    let body = match (&track.title, &track.album_title) {
+        (Some(_), Some(album)) => format!("{} - {album}", track.artist),
+        (Some(_), None) => track.artist.clone(),
+        (None, _) => String::default(),
+    };
+
+    let appname = match track.typ {
+        TrackType::Song => "Now playing",
+        TrackType::Episode => "Now playing episode",
+        TrackType::Livestream => "Now playing live",
+    };
+
+    tokio::task::spawn_blocking(move || {
+        if let Err(e) = Notification::new()
+            .appname(appname)
+            .summary(&summary)
+            .body(&body)
+            .show()
+        {
+            warn!("error showing notification: {e}");
+        }
+    });
+}
@@ -0,0 +1,171 @@
+//! Now-playing file export.
+//!
+//! Writes the current track and the queue to a file, so that external
+//! now-playing displays can watch a file instead of running a hook script.
+//! Two formats are supported:
+//! * M3U - Extended playlist of the current track followed by the tracks
+//!   still to come
+//! * JSON - The current track, its position, and the complete queue
+//!
+//! Files are written atomically: contents go to a temporary file in the same
+//! directory, which is then renamed over the target. Watchers never see a
+//! partially written file.
+//!
+//! # Example
+//!
+//! ```rust
+//! use pleezer::now_playing::{self, Format};
+//!
+//! now_playing::write(path, Format::Json, player.queue(), player.position())?;
+//! ```
+
+use std::{
+    fmt, fs,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use crate::{
+    error::{Error, Result},
+    track::{Track, TrackType},
+};
+
+/// File format of the now-playing file.
+#[derive(Copy, Clone, Debug, Default, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Format {
+    /// Extended M3U playlist.
+    #[default]
+    M3u,
+
+    /// JSON document.
+    Json,
+}
+
+/// Formats the file format as its lowercase name.
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::M3u => write!(f, "m3u"),
+            Self::Json => write!(f, "json"),
+        }
+    }
+}
+
+/// Parses a file format from its name, case-insensitively.
+///
+/// # Errors
+///
+/// Returns error if the name is not `m3u` or `json`.
+impl FromStr for Format {
+    type Err = Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "m3u" => Ok(Self::M3u),
+            "json" => Ok(Self::Json),
+            _ => Err(Error::invalid_argument(format!(
+                "invalid now-playing format {s}: expected m3u or json"
+            ))),
+        }
+    }
+}
+
+/// Writes the current track and queue to a file atomically.
+///
+/// # Arguments
+///
+/// * `path` - File to write
+/// * `format` - File format
+/// * `queue` - Tracks in the queue, in playback order
+/// * `position` - Index of the current track in the queue
+///
+/// # Errors
+///
+/// Returns error if:
+/// * The temporary file cannot be written
+/// * The temporary file cannot be renamed over `path`
+pub fn write(path: &Path, format: Format, queue: &[Track], position: usize) -> Result<()> {
+    let contents = match format {
+        Format::M3u => m3u(queue.iter().skip(position)),
+        Format::Json => json(queue, position)?,
+    };
+
+    write_atomic(path, contents)
+}
+
+/// Writes contents to a file atomically.
+///
+/// Contents go to a temporary file in the same directory, which is then
+/// renamed over `path`.
+///
+/// # Errors
+///
+/// Returns error if:
+/// * The temporary file cannot be written
+/// * The temporary file cannot be renamed over `path`
+pub(crate) fn write_atomic(path: &Path, contents: impl AsRef<[u8]>) -> Result<()> {
+    let mut temp = PathBuf::from(path).into_os_string();
+    temp.push(".tmp");
+
+    fs::write(&temp, contents)?;
+    fs::rename(&temp, path)?;
+    Ok(())
+}
+
+/// Renders tracks as an extended M3U playlist.
+pub(crate) fn m3u<'a>(tracks: impl IntoIterator<Item = &'a Track>) -> String {
+    let mut playlist = String::from("#EXTM3U\n");
+    for track in tracks {
+        let duration = track.duration().map_or(-1, |duration| {
+            i64::try_from(duration.as_secs()).unwrap_or(i64::MAX)
+        });
+        let title = track.title().unwrap_or_default();
+        playlist.push_str(&format!(
+            "#EXTINF:{duration},{} - {title}\n{}\n",
+            track.artist(),
+            url(track)
+        ));
+    }
+
+    playlist
+}
+
+/// Renders the queue as a JSON document.
+///
+/// # Errors
+///
+/// Returns error if serialization fails.
+pub(crate) fn json(queue: &[Track], position: usize) -> Result<String> {
+    let tracks: Vec<_> = queue.iter().map(track_json).collect();
+    let document = serde_json::json!({
+        "position": position,
+        "current": queue.get(position).map(track_json),
+        "queue": tracks,
+    });
+
+    serde_json::to_string_pretty(&document).map_err(Into::into)
+}
+
+/// Converts a track into its JSON representation.
+fn track_json(track: &Track) -> serde_json::Value {
+    serde_json::json!({
+        "id": track.id().to_string(),
+        "type": track.typ().to_string(),
+        "title": track.title(),
+        "artist": track.artist(),
+        "album": track.album_title(),
+        "cover_id": track.cover_id(),
+        "duration": track.duration().map(|duration| duration.as_secs_f64()),
+        "url": url(track),
+    })
+}
+
+/// Returns the Deezer web page of a track.
+fn url(track: &Track) -> String {
+    let path = match track.typ() {
+        TrackType::Song => "track",
+        TrackType::Episode => "episode",
+        TrackType::Livestream => "livestream",
+    };
+    format!("https://www.deezer.com/{path}/{}", track.id())
+}
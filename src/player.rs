@@ -34,13 +34,41 @@
 //! * Flexible audio device selection
 //! * Multiple audio host support
 //!
+//! # Gapless Playback
+//!
+//! The next track is preloaded and appended to the output queue before the
+//! current track ends, so tracks are joined sample by sample. No silence is
+//! inserted or trimmed between them, apart from the encoder delay and
+//! padding that the decoder removes as signalled by the file. This keeps
+//! continuous mixes seamless, regardless of the album context.
+//!
+//! # Resampling
+//!
+//! Tracks are resampled to the sample rate of the output device by `rodio`,
+//! which interpolates linearly between samples. This is cheap enough for
+//! low-power devices, but its quality is not configurable: `rodio` offers no
+//! higher-quality resampler. For the best fidelity, avoid resampling
+//! altogether by opening the output device at the sample rate of the tracks,
+//! which is 44.1 kHz for songs.
+//!
+//! # Output Format
+//!
+//! The output device is opened once, with one sample rate, sample format
+//! and channel count for the whole session. Tracks in other formats are
+//! converted on the fly: a queue that mixes lossless songs with MP3 uploads,
+//! podcasts and livestreams plays without reopening the device between
+//! tracks. Reopening per track would break gapless playback, because the
+//! next track is already queued on the open device, and make some devices
+//! glitch or click. To play songs without conversion, open the device at
+//! 44.1 kHz in stereo; tracks in other formats are then still converted.
+//!
 //! # Example
 //!
 //! ```rust
-//! use pleezer::player::Player;
+//! use pleezer::player::{OutputConfig, Player};
 //!
 //! // Create player with default audio device
-//! let mut player = Player::new(&config, "").await?;
+//! let mut player = Player::new(&config, &OutputConfig::default()).await?;
 //!
 //! // Configure playback
 //! player.set_normalization(true);
@@ -57,20 +85,32 @@
 //! player.stop();
 //! ```
 
-use std::{collections::HashSet, sync::Arc, time::Duration};
+use std::{
+    collections::HashSet,
+    str::FromStr,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
 
 use cpal::traits::{DeviceTrait, HostTrait};
-use md5::{Digest, Md5};
 use rodio::Source;
-use stream_download::storage::{adaptive::AdaptiveStorageProvider, temp::TempStorageProvider};
+use serde::Deserialize;
+use stream_download::storage::{
+    adaptive::AdaptiveStorageProvider, bounded::BoundedStorageProvider,
+    memory::MemoryStorageProvider, temp::TempStorageProvider,
+};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
 use url::Url;
 
+#[cfg(feature = "icecast")]
+use crate::icecast;
 use crate::{
     config::Config,
     decoder::Decoder,
     decrypt::{self},
+    dither::{self, Dither},
     error::{Error, ErrorKind, Result},
-    events::Event,
+    events::{Event, SkipReason},
     http, normalize,
     protocol::{
         connect::{
@@ -79,7 +119,7 @@ use crate::{
         },
         gateway::{self, MediaUrl},
     },
-    track::{Track, TrackId, DEFAULT_SAMPLE_RATE},
+    track::{Track, TrackId, TrackType, DEFAULT_SAMPLE_RATE},
     util::{self, ToF32, UNITY_GAIN},
 };
 
@@ -89,6 +129,142 @@ use crate::{
 /// used for internal audio processing.
 pub type SampleFormat = f32;
 
+/// Audio output device available on the system.
+///
+/// Describes a device as reported by its audio host, for device selection.
+/// The host and name together form the first part of the device
+/// specification passed to [`Player::new`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DeviceInfo {
+    /// Name of the audio host, like `ALSA` or `CoreAudio`.
+    pub host: String,
+
+    /// Name of the device, as reported by the host.
+    pub name: String,
+
+    /// Output configurations supported at standard sample rates.
+    pub configs: Vec<SupportedConfig>,
+
+    /// Whether this is the default output device of its host.
+    pub is_default: bool,
+}
+
+/// Output configuration supported by an audio device.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct SupportedConfig {
+    /// Number of output channels.
+    pub channels: u16,
+
+    /// Sample rate in Hz.
+    pub sample_rate: u32,
+
+    /// Sample format.
+    pub sample_format: cpal::SampleFormat,
+}
+
+/// Audio output device and configuration to open.
+///
+/// Structured form of the device specification string:
+/// ```text
+/// [<host>][|<device>][|<sample rate>][|<sample format>]
+/// ```
+///
+/// All fields are optional: unset fields select the default host, device
+/// or configuration. Names are matched case-insensitively. Unlike the
+/// string form, it can also select the channel count.
+///
+/// Deserializes from JSON like:
+/// ```json
+/// {"host": "ALSA", "device": "hw:CARD=DAC,DEV=0", "sample_rate": 48000, "channels": 2}
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct OutputConfig {
+    /// Name of the audio host, like `ALSA` or `CoreAudio`.
+    pub host: Option<String>,
+
+    /// Name of the output device.
+    pub device: Option<String>,
+
+    /// Sample rate in Hz.
+    pub sample_rate: Option<u32>,
+
+    /// Sample format, like `i16` or `f32`.
+    pub sample_format: Option<String>,
+
+    /// Number of output channels.
+    pub channels: Option<u16>,
+}
+
+impl FromStr for OutputConfig {
+    type Err = Error;
+
+    /// Parses a device specification string.
+    ///
+    /// From left to right, the fields are optional, and empty fields select
+    /// the default. An empty string selects the system default device.
+    ///
+    /// # Errors
+    ///
+    /// Returns `InvalidArgument` if the sample rate is not a number.
+    fn from_str(s: &str) -> Result<Self> {
+        let mut components = s
+            .split('|')
+            .map(|component| Some(component).filter(|component| !component.is_empty()));
+
+        let host = components.next().flatten().map(ToOwned::to_owned);
+        let device = components.next().flatten().map(ToOwned::to_owned);
+        let sample_rate = components
+            .next()
+            .flatten()
+            .map(|rate| {
+                rate.parse()
+                    .map_err(|_| Error::invalid_argument(format!("invalid sample rate {rate}")))
+            })
+            .transpose()?;
+        let sample_format = components.next().flatten().map(ToOwned::to_owned);
+
+        Ok(Self {
+            host,
+            device,
+            sample_rate,
+            sample_format,
+            channels: None,
+        })
+    }
+}
+
+impl DeviceInfo {
+    /// Returns the supported sample rates in Hz, in order of first support.
+    #[must_use]
+    pub fn sample_rates(&self) -> Vec<u32> {
+        Self::unique(self.configs.iter().map(|config| config.sample_rate))
+    }
+
+    /// Returns the supported sample formats, in order of first support.
+    #[must_use]
+    pub fn sample_formats(&self) -> Vec<cpal::SampleFormat> {
+        Self::unique(self.configs.iter().map(|config| config.sample_format))
+    }
+
+    /// Returns the supported channel counts, in order of first support.
+    #[must_use]
+    pub fn channels(&self) -> Vec<u16> {
+        Self::unique(self.configs.iter().map(|config| config.channels))
+    }
+
+    /// Collects values without duplicates, keeping the first occurrence.
+    fn unique<T: PartialEq>(values: impl Iterator<Item = T>) -> Vec<T> {
+        let mut result = Vec::new();
+        for value in values {
+            if !result.contains(&value) {
+                result.push(value);
+            }
+        }
+        result
+    }
+}
+
 /// Audio playback manager.
 ///
 /// Handles:
@@ -141,6 +317,9 @@ pub struct Player {
     /// or become unavailable.
     skip_tracks: HashSet<TrackId>,
 
+    /// Track whose truncated download was last retried, and how often.
+    truncation_retries: Option<(TrackId, usize)>,
+
     /// Current position in the queue.
     ///
     /// May exceed queue length to prepare for
@@ -172,12 +351,23 @@ pub struct Player {
     /// Used to calculate normalization ratios.
     gain_target_db: i8,
 
+    /// Constant gain in dB applied to all tracks.
+    ///
+    /// Added to the normalization difference, if any.
+    preamp_db: f32,
+
     /// Raw volume setting as a percentage (0.0 to 1.0).
     ///
     /// This stores the user-set volume before logarithmic scaling is applied.
     /// The actual output volume uses logarithmic scaling for better perceived control.
     volume: Percentage,
 
+    /// Output volume after logarithmic scaling.
+    ///
+    /// Applied in the output chain before dithering, instead of by the sink,
+    /// so that dither noise is not attenuated along with the audio.
+    output_volume: dither::Volume,
+
     /// Channel for sending playback events.
     ///
     /// Events include:
@@ -186,11 +376,10 @@ pub struct Player {
     /// * Connection status
     event_tx: Option<tokio::sync::mpsc::UnboundedSender<Event>>,
 
-    /// Audio device specification string.
+    /// Audio output device and configuration.
     ///
     /// Stored during construction and used to configure the device when `start()` is called.
-    /// Format: `[<host>][|<device>][|<sample rate>][|<sample format>]`.
-    device: String,
+    device: OutputConfig,
 
     /// Audio output sink.
     ///
@@ -215,6 +404,15 @@ pub struct Player {
     /// Used to calculate playback progress.
     playing_since: Duration,
 
+    /// How long the current track has actually played, excluding seeks.
+    played: Duration,
+
+    /// Sink position when the played time was last updated.
+    played_pos: Duration,
+
+    /// Whether playback has caught up with the downloaded audio.
+    buffering: bool,
+
     /// Completion signal for current track.
     ///
     /// Receiver is notified when track finishes.
@@ -230,6 +428,66 @@ pub struct Player {
     ///
     /// Used to construct track download URLs.
     media_url: Url,
+
+    /// Download slots for track downloads.
+    ///
+    /// Bounds how many tracks are downloaded in parallel. A permit is
+    /// held until the download completes or is cancelled.
+    download_slots: Arc<Semaphore>,
+
+    /// Whether to stream tracks through a bounded in-memory buffer.
+    ///
+    /// When enabled, tracks are not fully downloaded to temporary storage,
+    /// lowering peak memory at the cost of slower seeking.
+    streaming: bool,
+
+    /// Channel to the Icecast streaming task.
+    ///
+    /// When set, the audio output is also rebroadcast to an Icecast server.
+    #[cfg(feature = "icecast")]
+    icecast_tx: Option<tokio::sync::mpsc::Sender<icecast::Chunk>>,
+
+    /// Whether to reload the current track when the audio quality changes.
+    ///
+    /// When disabled, the current track keeps playing in the quality it was
+    /// downloaded in, and the new quality applies to tracks not yet downloaded.
+    reload_on_quality_change: bool,
+
+    /// Maximum playback time of a track before it is skipped.
+    ///
+    /// Does not apply to livestreams.
+    max_track_duration: Option<Duration>,
+
+    /// Dithering method for output devices with a lower bit depth.
+    ///
+    /// Only applies to 16-bit or smaller integer sample formats.
+    dither: Dither,
+
+    /// Sample format of the output device.
+    ///
+    /// Set when the device is opened, to dither the output queue.
+    sample_format: cpal::SampleFormat,
+
+    /// Whether to keep the output device awake while idle.
+    ///
+    /// When enabled, near-silence is output while no track is playing, so
+    /// that devices that power down on digital silence stay awake.
+    keepalive: bool,
+
+    /// Audio output sink for the keepalive signal.
+    ///
+    /// Plays only while the main sink is not playing.
+    /// Only available when device is open and keepalive is enabled.
+    keepalive_sink: Option<rodio::Sink>,
+
+    /// Whether to discard audio instead of opening an output device.
+    no_audio: bool,
+
+    /// Output that discards audio at real-time pace.
+    ///
+    /// Only available when audio output is disabled, between `start()` and
+    /// `stop()`.
+    null_output: Option<NullOutput>,
 }
 
 impl Player {
@@ -257,63 +515,132 @@ impl Player {
     /// sudden audio cutoffs that can cause popping sounds.
     const FADE_DURATION: Duration = Duration::from_millis(25);
 
+    /// Interval between volume steps when fading out.
+    const FADE_OUT_STEP: Duration = Duration::from_millis(10);
+
+    /// Minimum downloaded audio ahead of the playback position.
+    ///
+    /// Playback with less audio ahead is considered to be buffering.
+    const BUFFERING_THRESHOLD: Duration = Duration::from_millis(500);
+
+    /// Peak amplitude of the keepalive signal.
+    ///
+    /// Equal to one least significant bit at 16-bit resolution (about -90 dBFS),
+    /// which is inaudible but not digital silence.
+    const KEEPALIVE_AMPLITUDE: f32 = 1.0 / 32_768.0;
+
     /// Creates a new player instance.
     ///
     /// # Arguments
     ///
     /// * `config` - Player configuration including normalization settings
-    /// * `device` - Audio device specification string:
-    ///   ```text
-    ///   [<host>][|<device>][|<sample rate>][|<sample format>]
-    ///   ```
-    ///   All parts are optional. Use empty string for system default.
-    ///   Device configuration is deferred until `start()` is called.
+    /// * `device` - Audio output device and configuration, parsed from a
+    ///   device specification string or deserialized from JSON. Use the
+    ///   default for the system default device. Device configuration is
+    ///   deferred until `start()` is called.
     ///
     /// # Errors
     ///
     /// Returns error if:
     /// * HTTP client creation fails
     /// * Decryption key is invalid
-    pub async fn new(config: &Config, device: &str) -> Result<Self> {
-        let client = http::Client::without_cookies(config)?;
+    /// * Icecast streaming cannot be set up
+    /// * Output device is not available within the device open timeout
+    pub async fn new(config: &Config, device: &OutputConfig) -> Result<Self> {
+        let player = Self::without_decryption(config, device).await?;
 
         let bf_secret = if let Some(secret) = config.bf_secret {
             secret
         } else {
             debug!("no bf_secret specified, fetching one from the web player");
-            Config::try_key(&client).await?
+            Config::try_key(&player.client).await?
         };
 
-        if format!("{:x}", Md5::digest(*bf_secret)) == Config::BF_SECRET_MD5 {
-            decrypt::set_bf_secret(bf_secret)?;
-        } else {
-            return Err(Error::permission_denied("the bf_secret is not valid"));
+        Config::verify_key(&bf_secret)?;
+        decrypt::set_bf_secret(bf_secret)?;
+
+        Ok(player)
+    }
+
+    /// Creates a new player instance without setting up decryption.
+    ///
+    /// Does not fetch or verify the decryption key, so the player can only
+    /// play unencrypted audio with [`play_file`](Self::play_file). Deezer
+    /// tracks fail to decrypt.
+    ///
+    /// # Arguments
+    ///
+    /// * `config` - Player configuration including normalization settings
+    /// * `device` - Audio output device and configuration, see [`new`](Self::new)
+    ///
+    /// # Errors
+    ///
+    /// Returns error if:
+    /// * HTTP client creation fails
+    /// * Icecast streaming cannot be set up
+    /// * Output device is not available within the device open timeout
+    pub async fn without_decryption(config: &Config, device: &OutputConfig) -> Result<Self> {
+        if config.no_audio {
+            info!("audio output disabled: discarding audio");
+        } else if let Some(timeout) = config.device_open_timeout {
+            Self::wait_for_device(device, timeout).await?;
         }
 
+        let client = http::Client::without_cookies(config)?;
+
         #[expect(clippy::cast_possible_truncation)]
         let gain_target_db = gateway::user_data::Gain::default().target as i8;
 
+        if config.preamp_db > 0.0 {
+            warn!(
+                "pre-amp of {} dB may clip loud tracks; peaks will be limited",
+                config.preamp_db
+            );
+        }
+
         Ok(Self {
             queue: Vec::new(),
             skip_tracks: HashSet::new(),
+            truncation_retries: None,
             position: 0,
-            audio_quality: AudioQuality::default(),
+            audio_quality: config.initial_quality,
             client,
             license_token: String::new(),
             media_url: MediaUrl::default().into(),
             repeat_mode: RepeatMode::default(),
             normalization: config.normalization,
             gain_target_db,
+            preamp_db: config.preamp_db,
             volume: Self::DEFAULT_VOLUME,
+            output_volume: dither::Volume::default(),
             event_tx: None,
             playing_since: Duration::ZERO,
+            played: Duration::ZERO,
+            played_pos: Duration::ZERO,
+            buffering: false,
             deferred_seek: None,
             current_rx: None,
             preload_rx: None,
-            device: device.to_owned(),
+            device: device.clone(),
             sink: None,
             stream: None,
             sources: None,
+            download_slots: Arc::new(Semaphore::new(config.max_concurrent_downloads)),
+            streaming: config.streaming,
+            #[cfg(feature = "icecast")]
+            icecast_tx: config
+                .icecast
+                .clone()
+                .map(|server| icecast::spawn(config, server))
+                .transpose()?,
+            reload_on_quality_change: config.reload_on_quality_change,
+            max_track_duration: config.max_track_duration,
+            dither: config.dither,
+            sample_format: cpal::SampleFormat::F32,
+            keepalive: config.keepalive_audio,
+            keepalive_sink: None,
+            no_audio: config.no_audio,
+            null_output: None,
         })
     }
 
@@ -321,34 +648,39 @@ impl Player {
     ///
     /// # Arguments
     ///
-    /// * `device` - Device specification string in format:
-    ///   ```text
-    ///   [<host>][|<device>][|<sample rate>][|<sample format>]
-    ///   ```
-    ///   All parts are optional. Use empty string for system default.
+    /// * `device` - Audio output device and configuration. Unset fields
+    ///   select the default host, device or configuration.
     ///
     /// # Returns
     ///
     /// Returns the selected device and its configuration.
     ///
+    /// # Sample Format Negotiation
+    ///
+    /// If the device does not support the requested sample format at the
+    /// requested sample rate, the most precise sample format that it does
+    /// support is used instead. Samples are then converted on output, see
+    /// [`dither`](crate::dither) for bit depth reduction.
+    ///
+    /// Without a sample rate, the sample rate of the default configuration
+    /// of the device is used for the requested sample format or channels.
+    ///
     /// # Errors
     ///
     /// Returns error if:
     /// * Host is not found
     /// * Device is not found
-    /// * Sample rate is invalid
-    /// * Sample format is not supported
+    /// * Sample rate or channel count is not supported
     /// * Device cannot be acquired (e.g., in use by another application)
-    fn get_device(device: &str) -> Result<(rodio::Device, rodio::SupportedStreamConfig)> {
-        // The device string has the following format:
-        // "[<host>][|<device>][|<sample rate>][|<sample format>]" (case-insensitive)
-        // From left to right, the fields are optional, but each field
-        // depends on the preceding fields being specified.
-        let mut components = device.split('|');
-
-        // The host is the first field.
-        let host = match components.next() {
-            Some("") | None => cpal::default_host(),
+    ///
+    /// # Exclusive Access
+    ///
+    /// The audio backend does not expose exclusive modes, like those of
+    /// WASAPI or CoreAudio. For bit-perfect output, select an ALSA hardware
+    /// (`hw:`) device or an ASIO device, which bypass the software mixer.
+    fn get_device(device: &OutputConfig) -> Result<(rodio::Device, rodio::SupportedStreamConfig)> {
+        let host = match device.host.as_deref() {
+            None => cpal::default_host(),
             Some(name) => {
                 let host_ids = cpal::available_hosts();
                 host_ids
@@ -365,9 +697,11 @@ impl Player {
             }
         };
 
-        // The device is the second field.
-        let device = match components.next() {
-            Some("") | None => host.default_output_device().ok_or_else(|| {
+        let rate = device.sample_rate;
+        let format = device.sample_format.as_deref();
+        let channels = device.channels;
+        let device = match device.device.as_deref() {
+            None => host.default_output_device().ok_or_else(|| {
                 Error::not_found(format!(
                     "default audio output device not found on {}",
                     host.id().name()
@@ -386,43 +720,63 @@ impl Player {
             }
         };
 
-        let config = match components.next() {
-            Some("") | None => device.default_output_config().map_err(|e| {
-                Error::unavailable(format!("default output configuration unavailable: {e}"))
-            })?,
-            Some(rate) => {
-                let rate = rate
-                    .parse()
-                    .map_err(|_| Error::invalid_argument(format!("invalid sample rate {rate}")))?;
-                let rate = cpal::SampleRate(rate);
-
-                let format = match components.next() {
-                    Some("") | None => None,
-                    other => other,
+        let default_config = device.default_output_config().map_err(|e| {
+            Error::unavailable(format!("default output configuration unavailable: {e}"))
+        });
+
+        let config = match (rate, format, channels) {
+            (None, None, None) => default_config?,
+            _ => {
+                let rate = match rate {
+                    Some(rate) => cpal::SampleRate(rate),
+                    None => default_config?.sample_rate(),
                 };
 
-                device
+                let configs: Vec<_> = device
                     .supported_output_configs()?
-                    .find_map(|config| {
-                        if format.is_none_or(|format| {
-                            config
-                                .sample_format()
-                                .to_string()
-                                .eq_ignore_ascii_case(format)
-                        }) {
-                            config.try_with_sample_rate(rate)
-                        } else {
-                            None
-                        }
-                    })
-                    .ok_or_else(|| {
-                        Error::unavailable(format!(
-                            "audio output device {} does not support sample rate {} with {} sample format",
-                            device.name().as_deref().unwrap_or("UNKNOWN"),
-                            rate.0,
-                            format.unwrap_or("default")
-                        ))
-                    })?
+                    .filter(|config| channels.is_none_or(|channels| config.channels() == channels))
+                    .collect();
+                let config = configs.iter().find_map(|config| {
+                    if format.is_none_or(|format| {
+                        config
+                            .sample_format()
+                            .to_string()
+                            .eq_ignore_ascii_case(format)
+                    }) {
+                        config.clone().try_with_sample_rate(rate)
+                    } else {
+                        None
+                    }
+                });
+
+                // Fall back to the most precise sample format at this sample rate.
+                let config = config.or_else(|| {
+                    let fallback = configs
+                        .iter()
+                        .filter_map(|config| config.clone().try_with_sample_rate(rate))
+                        .max_by_key(|config| {
+                            let sample_format = config.sample_format();
+                            (sample_format.sample_size(), sample_format.is_float())
+                        })?;
+                    warn!(
+                        "audio output device {} does not support {} sample format, converting to {}",
+                        device.name().as_deref().unwrap_or("UNKNOWN"),
+                        format.unwrap_or("default"),
+                        fallback.sample_format()
+                    );
+                    Some(fallback)
+                });
+
+                config.ok_or_else(|| {
+                    let channels = channels
+                        .map(|channels| format!(" with {channels} channels"))
+                        .unwrap_or_default();
+                    Error::unavailable(format!(
+                        "audio output device {} does not support sample rate {}{channels}",
+                        device.name().as_deref().unwrap_or("UNKNOWN"),
+                        rate.0,
+                    ))
+                })?
             }
         };
 
@@ -448,6 +802,10 @@ impl Player {
     /// Called internally when needed (e.g., by `play()`) to initialize the audio device.
     /// The device remains open until `stop` is called or the player is dropped.
     ///
+    /// When audio output is disabled, no device is opened. Audio is discarded
+    /// at real-time pace instead, so that progress and events behave like
+    /// real playback.
+    ///
     /// Note: Manual calls to this method are not required as device initialization
     /// is handled automatically.
     ///
@@ -464,28 +822,124 @@ impl Player {
             return Ok(());
         }
 
-        debug!("opening output device");
+        let (sink, handle) = if self.no_audio {
+            debug!("opening null output");
 
-        let (device, device_config) = Self::get_device(&self.device)?;
-        let (stream, handle) = rodio::OutputStream::try_from_device_config(&device, device_config)?;
-        let sink = rodio::Sink::try_new(&handle)?;
+            self.sample_format = cpal::SampleFormat::F32;
+            let (sink, output) = rodio::Sink::new_idle();
+            self.null_output = Some(NullOutput::spawn(output));
+            (sink, None)
+        } else {
+            debug!("opening output device");
+
+            let (device, device_config) = Self::get_device(&self.device)?;
+            self.sample_format = device_config.sample_format();
+            let (stream, handle) =
+                rodio::OutputStream::try_from_device_config(&device, device_config)?;
+            self.stream = Some(stream);
+            (rodio::Sink::try_new(&handle)?, Some(handle))
+        };
 
         // Set the volume to the last known value. Do not use `self.set_volume` because
         // it will short-circuit when trying to set the volume to what `self.volume` already is.
         let log_volume = Self::log_volume(self.volume.as_ratio());
-        sink.set_volume(log_volume);
+        self.output_volume.set(log_volume);
 
         // The output source will output silence when the queue is empty.
         // That will cause the sink to report as "playing", so we need to pause it.
+        self.sink = Some(sink);
+        if self.append_queue()? {
+            info!("dithering output to {}", self.sample_format);
+        }
+        self.sink_mut()?.pause();
+
+        // Keep the device awake with near-silence while nothing is playing.
+        if let Some(handle) = handle.filter(|_| self.keepalive) {
+            let keepalive_sink = rodio::Sink::try_new(&handle)?;
+            keepalive_sink.append(KeepAlive::new(Self::KEEPALIVE_AMPLITUDE));
+            self.keepalive_sink = Some(keepalive_sink);
+        }
+
+        Ok(())
+    }
+
+    /// Creates a new source queue and appends it to the sink.
+    ///
+    /// The queue output passes through the same chain every time: the
+    /// Icecast rebroadcast, if any, and then the dithering filter. Called
+    /// when the device is opened and whenever the queue is replaced.
+    ///
+    /// # Returns
+    ///
+    /// Returns whether the output is dithered.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if audio device is not open.
+    fn append_queue(&mut self) -> Result<bool> {
+        let sink = self
+            .sink
+            .as_ref()
+            .ok_or_else(|| Error::unavailable("audio sink not available"))?;
+
         let (sources, output) = rodio::queue::queue(true);
+        #[cfg(feature = "icecast")]
+        let output = icecast::tee(output, self.icecast_tx.clone());
+        let output = dither::dither(
+            output,
+            self.dither,
+            self.sample_format,
+            self.output_volume.clone(),
+        );
+        let dithered = output.is_active();
         sink.append(output);
-        sink.pause();
 
-        self.sink = Some(sink);
         self.sources = Some(sources);
-        self.stream = Some(stream);
+        Ok(dithered)
+    }
 
-        Ok(())
+    /// Initial delay between attempts to find the output device.
+    const DEVICE_RETRY_MIN: Duration = Duration::from_millis(250);
+
+    /// Maximum delay between attempts to find the output device.
+    const DEVICE_RETRY_MAX: Duration = Duration::from_secs(2);
+
+    /// Waits for an output device to become available.
+    ///
+    /// Retries with exponential backoff, logging each failed attempt.
+    ///
+    /// # Arguments
+    ///
+    /// * `device` - Audio output device and configuration, see [`get_device`](Self::get_device)
+    /// * `timeout` - Maximum total time to wait
+    ///
+    /// # Errors
+    ///
+    /// Returns the last device error if the device is still not available
+    /// when the timeout expires.
+    async fn wait_for_device(device: &OutputConfig, timeout: Duration) -> Result<()> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        let mut delay = Self::DEVICE_RETRY_MIN;
+
+        loop {
+            match Self::get_device(device) {
+                Ok(_) => return Ok(()),
+                Err(e) => {
+                    let remaining = deadline.saturating_duration_since(tokio::time::Instant::now());
+                    if remaining.is_zero() {
+                        return Err(e);
+                    }
+
+                    let delay_now = delay.min(remaining);
+                    warn!(
+                        "output device not available: {e}; retrying in {}ms",
+                        delay_now.as_millis()
+                    );
+                    tokio::time::sleep(delay_now).await;
+                    delay = (delay * 2).min(Self::DEVICE_RETRY_MAX);
+                }
+            }
+        }
     }
 
     /// Closes the audio output device and stops playback.
@@ -502,8 +956,13 @@ impl Player {
             sink.stop();
         }
 
+        if let Some(keepalive_sink) = self.keepalive_sink.take() {
+            keepalive_sink.stop();
+        }
+
         self.sources = None;
         self.stream = None;
+        self.null_output = None;
         self.sink = None;
     }
 
@@ -528,30 +987,24 @@ impl Player {
 
     /// Lists available audio output devices.
     ///
-    /// Returns a sorted list of device specifications in the format:
-    /// ```text
-    /// <host>|<device>|<sample rate>|<sample format>
-    /// ```
+    /// Enumerates the output devices of all available hosts, with the
+    /// configurations they support at the standard sample rates:
+    /// * 44.1 kHz (CD audio, streaming services)
+    /// * 48 kHz (professional audio, video production)
     ///
-    /// Only enumerates configurations meeting these criteria:
-    /// * Standard sample rates:
-    ///   - 44.1 kHz (CD audio, streaming services)
-    ///   - 48 kHz (professional audio, video production)
-    ///   - I16 (16-bit integer)
-    ///   - I32 (32-bit integer)
-    ///   - F32 (32-bit float)
-    /// * Stereo output (2 channels)
-    ///
-    /// Default device is marked with "(default)" suffix.
+    /// All channel counts and sample formats are included. Devices that
+    /// cannot report their name or configurations are skipped.
     ///
-    /// Note: Other device configurations can still be used by explicitly
-    /// specifying them in the device string passed to `new()`.
+    /// Note: Devices are listed and matched by name. The audio backend does
+    /// not expose persistent device identifiers, so on ALSA, card names like
+    /// `hw:CARD=Headphones,DEV=0` are more stable across reboots than card
+    /// numbers like `hw:0,0`.
     ///
     /// # Returns
     ///
-    /// A vector of device specification strings, as sorted by the host.
+    /// A vector of devices, as sorted by the host.
     #[must_use]
-    pub fn enumerate_devices() -> Vec<String> {
+    pub fn output_devices() -> Vec<DeviceInfo> {
         let hosts = cpal::available_hosts();
         let mut result = Vec::new();
 
@@ -560,40 +1013,94 @@ impl Player {
             .into_iter()
             .filter_map(|id| cpal::host_from_id(id).ok())
         {
-            if let Ok(devices) = host.output_devices() {
-                for device in devices {
-                    if let Ok(device_name) = device.name() {
-                        if let Ok(configs) = device.supported_output_configs() {
-                            for config in configs {
-                                if config.channels() == 2
-                                    && Self::SAMPLE_FORMATS.contains(&config.sample_format())
-                                {
-                                    for sample_rate in &Self::SAMPLE_RATES {
-                                        if let Some(config) = config
-                                            .try_with_sample_rate(cpal::SampleRate(*sample_rate))
-                                        {
-                                            let line = format!(
-                                                "{}|{}|{}|{}",
-                                                host.id().name(),
-                                                device_name,
-                                                config.sample_rate().0,
-                                                config.sample_format(),
-                                            );
-
-                                            result.push(line);
-                                        }
-                                    }
-                                }
-                            }
+            let default_name = host
+                .default_output_device()
+                .and_then(|device| device.name().ok());
+
+            let Ok(devices) = host.output_devices() else {
+                continue;
+            };
+
+            for device in devices {
+                let Ok(name) = device.name() else {
+                    continue;
+                };
+                let Ok(supported) = device.supported_output_configs() else {
+                    continue;
+                };
+
+                let mut configs = Vec::new();
+                for config in supported {
+                    for sample_rate in &Self::SAMPLE_RATES {
+                        if let Some(config) =
+                            config.try_with_sample_rate(cpal::SampleRate(*sample_rate))
+                        {
+                            configs.push(SupportedConfig {
+                                channels: config.channels(),
+                                sample_rate: config.sample_rate().0,
+                                sample_format: config.sample_format(),
+                            });
                         }
                     }
                 }
+
+                result.push(DeviceInfo {
+                    host: host.id().name().to_owned(),
+                    is_default: default_name.as_ref() == Some(&name),
+                    name,
+                    configs,
+                });
             }
         }
 
         result
     }
 
+    /// Lists available audio output devices as device specifications.
+    ///
+    /// Returns a sorted list of device specifications in the format:
+    /// ```text
+    /// <host>|<device>|<sample rate>|<sample format>
+    /// ```
+    ///
+    /// Derived from [`output_devices`](Self::output_devices), only listing
+    /// configurations meeting these criteria:
+    /// * Standard sample rates:
+    ///   - 44.1 kHz (CD audio, streaming services)
+    ///   - 48 kHz (professional audio, video production)
+    /// * Standard sample formats:
+    ///   - I16 (16-bit integer)
+    ///   - I32 (32-bit integer)
+    ///   - F32 (32-bit float)
+    /// * Stereo output (2 channels)
+    ///
+    /// Note: Other device configurations can still be used by explicitly
+    /// specifying them in the device string passed to `new()`.
+    ///
+    /// # Returns
+    ///
+    /// A vector of device specification strings, as sorted by the host.
+    #[must_use]
+    pub fn enumerate_devices() -> Vec<String> {
+        Self::output_devices()
+            .iter()
+            .flat_map(|device| {
+                device
+                    .configs
+                    .iter()
+                    .filter(|config| {
+                        config.channels == 2 && Self::SAMPLE_FORMATS.contains(&config.sample_format)
+                    })
+                    .map(move |config| {
+                        format!(
+                            "{}|{}|{}|{}",
+                            device.host, device.name, config.sample_rate, config.sample_format,
+                        )
+                    })
+            })
+            .collect()
+    }
+
     /// Advances to the next track in the queue.
     ///
     /// Handles:
@@ -603,27 +1110,34 @@ impl Player {
     ///
     /// Behavior depends on repeat mode:
     /// * `None`: Stops at end of queue
-    /// * `One`: Stays on current track
+    /// * `One`: Stays on current track, repeating it indefinitely
     /// * `All`: Loops back to start of queue
+    ///
+    /// Any other repeat mode is treated as `None`.
+    ///
+    /// When no preloaded track took over, the new current track is downloaded
+    /// again. This replays the same track in `One` mode, and tracks that were
+    /// played before when looping in `All` mode.
     fn go_next(&mut self) {
         let old_position = self.position;
-        let repeat_mode = self.repeat_mode();
-        if repeat_mode != RepeatMode::One {
-            let next = self.position.saturating_add(1);
-            if next < self.queue.len() {
-                // Move to the next track.
-                self.position = next;
-            } else {
-                // Reached the end of the queue: rewind to the beginning.
-                if repeat_mode != RepeatMode::All {
-                    self.pause();
-                };
-                self.position = 0;
+        self.played = Duration::ZERO;
+        let (next, keep_playing) =
+            Self::next_position(self.position, self.queue.len(), self.repeat_mode());
+        if !keep_playing {
+            self.pause();
+        }
+        self.position = next;
+
+        // A finished download cannot be played again: reset it so that the
+        // playback loop loads the track anew.
+        if self.current_rx.is_none() {
+            if let Some(current) = self.track_mut() {
+                current.reset_download();
             }
         }
 
         if self.position() != old_position {
-            self.notify(Event::TrackChanged);
+            self.notify_track_changed();
         }
 
         // Even if we were already playing, we need to report another playback stream.
@@ -632,6 +1146,35 @@ impl Player {
         }
     }
 
+    /// Returns the gain in dB to apply to a track.
+    ///
+    /// This is the pre-amp plus, when the track loudness is known, the
+    /// difference between the normalization target and the track loudness.
+    fn gain_difference(preamp_db: f32, gain_target_db: i8, track_lufs: Option<f32>) -> f32 {
+        preamp_db + track_lufs.map_or(0.0, |track_lufs| f32::from(gain_target_db) - track_lufs)
+    }
+
+    /// Returns the queue position after `position` in a queue of `len`
+    /// tracks, and whether playback continues there.
+    ///
+    /// Playback stops when the end of the queue is reached, unless repeating
+    /// all tracks.
+    fn next_position(position: usize, len: usize, repeat_mode: RepeatMode) -> (usize, bool) {
+        match repeat_mode {
+            RepeatMode::One => (position, true),
+            repeat_mode => {
+                let next = position.saturating_add(1);
+                if next < len {
+                    // Move to the next track.
+                    (next, true)
+                } else {
+                    // Reached the end of the queue: rewind to the beginning.
+                    (0, repeat_mode == RepeatMode::All)
+                }
+            }
+        }
+    }
+
     /// The normalization attack time (5ms).
     /// This is the time it takes for the limiter to respond to level increases.
     /// Value matches Spotify's implementation for consistent behavior.
@@ -653,6 +1196,18 @@ impl Player {
     /// Time before network operations timeout.
     const NETWORK_TIMEOUT: Duration = Duration::from_secs(2);
 
+    /// Number of times to retry downloads that returned an empty response.
+    const EMPTY_DOWNLOAD_RETRIES: usize = 1;
+
+    /// Number of times to reload tracks whose download was truncated.
+    pub(crate) const TRUNCATED_DOWNLOAD_RETRIES: usize = 1;
+
+    /// Size of the streaming buffer as a multiple of the prefetch size.
+    ///
+    /// Leaves room for the decoder to read behind the download position
+    /// while the next block is being fetched.
+    const STREAMING_BUFFER_FACTOR: usize = 2;
+
     /// The `ReplayGain` 2.0 reference level in LUFS.
     /// Used when calculating normalization from `ReplayGain` metadata.
     const REPLAY_GAIN_LUFS: i8 = -18;
@@ -660,7 +1215,9 @@ impl Player {
     /// Loads and prepares a track for playback.
     ///
     /// Downloads and configures audio processing:
-    /// 1. Downloads content through unified `AudioFile` interface
+    /// 1. Downloads content through unified `AudioFile` interface:
+    ///    * Default: to temporary storage with prefetch buffer
+    ///    * Streaming: through a bounded in-memory buffer
     /// 2. Configures format-specific decoder:
     ///    * MP3: Optimized seeking for CBR content
     ///    * FLAC: Raw frame handling
@@ -672,6 +1229,9 @@ impl Player {
     ///    * Channel count from codec or content type
     /// 4. Applies volume normalization if enabled
     ///
+    /// Waits for a download slot when `max_concurrent_downloads` tracks are
+    /// already downloading.
+    ///
     /// # Arguments
     ///
     /// * `position` - Queue position of track to load
@@ -698,27 +1258,64 @@ impl Player {
             .ok_or_else(|| Error::unavailable("audio sources not available"))?;
 
         if track.handle().is_none() {
-            let download = tokio::time::timeout(Self::NETWORK_TIMEOUT, async {
-                // Start downloading the track.
-                let medium = track
-                    .get_medium(
-                        &self.client,
-                        &self.media_url,
-                        self.audio_quality,
-                        self.license_token.clone(),
-                    )
-                    .await?;
-
-                let prefetch_size = usize::try_from(track.prefetch_size()).unwrap_or(usize::MAX);
-                let storage = AdaptiveStorageProvider::new(
-                    TempStorageProvider::default(),
-                    prefetch_size
-                        .try_into()
-                        .map_err(|e| Error::internal(format!("prefetch size error: {e}")))?,
-                );
-                track.start_download(&self.client, &medium, storage).await
-            })
-            .await??;
+            let mut retries = 0;
+            let download = loop {
+                // Wait for a download slot. This is outside the network timeout,
+                // because other downloads may take much longer to complete.
+                let permit = Self::download_slot(&self.download_slots).await?;
+
+                let result = tokio::time::timeout(Self::NETWORK_TIMEOUT, async {
+                    // Start downloading the track.
+                    let medium = track
+                        .get_medium(
+                            &self.client,
+                            &self.media_url,
+                            self.audio_quality,
+                            self.license_token.clone(),
+                        )
+                        .await?;
+
+                    let prefetch_size =
+                        usize::try_from(track.prefetch_size()).unwrap_or(usize::MAX);
+                    if self.streaming {
+                        // Keep only a window of the track in memory. Seeking outside of
+                        // the window re-requests the track from the new offset.
+                        let buffer_size = prefetch_size
+                            .saturating_mul(Self::STREAMING_BUFFER_FACTOR)
+                            .try_into()
+                            .map_err(|e| Error::internal(format!("buffer size error: {e}")))?;
+                        let storage =
+                            BoundedStorageProvider::new(MemoryStorageProvider, buffer_size);
+                        track
+                            .start_download(&self.client, &medium, storage, permit)
+                            .await
+                    } else {
+                        let storage = AdaptiveStorageProvider::new(
+                            TempStorageProvider::default(),
+                            prefetch_size.try_into().map_err(|e| {
+                                Error::internal(format!("prefetch size error: {e}"))
+                            })?,
+                        );
+                        track
+                            .start_download(&self.client, &medium, storage, permit)
+                            .await
+                    }
+                })
+                .await?;
+
+                match result {
+                    // Empty responses are usually transient CDN errors.
+                    Err(e)
+                        if e.kind == ErrorKind::DataLoss
+                            && retries < Self::EMPTY_DOWNLOAD_RETRIES =>
+                    {
+                        warn!("{e}; retrying");
+                        track.reset_download();
+                        retries += 1;
+                    }
+                    result => break result?,
+                }
+            };
 
             // Create a new decoder for the track.
             let mut decoder = Decoder::new(track, download)?;
@@ -727,58 +1324,53 @@ impl Player {
             if let Some(bits_per_sample) = decoder.bits_per_sample() {
                 track.bits_per_sample = Some(bits_per_sample);
             }
+            track.decoded_duration = decoder.total_duration();
+            if track.playback_duration() != track.duration() {
+                debug!(
+                    "{} {track} decodes to {:.1}s instead of {}s",
+                    track.typ(),
+                    track.decoded_duration.unwrap_or_default().as_secs_f32(),
+                    track.duration().unwrap_or_default().as_secs()
+                );
+            }
 
-            // Seek to the deferred position if set.
-            if let Some(progress) = self.deferred_seek.take() {
-                // Set the track position only if `progress` is beyond the track start. We start
-                // at the beginning anyway, and this prevents decoder errors.
-                if !progress.is_zero() {
-                    if let Err(e) = decoder.try_seek(progress) {
-                        error!("failed to seek to deferred position: {}", e);
-                    }
+            // Seek to the deferred position if set, or else to the start of the clip.
+            let clip_start = track.clip().map_or(Duration::ZERO, |clip| clip.start);
+            let start = self.deferred_seek.take().unwrap_or(clip_start);
+            // Set the track position only if `start` is beyond the track start. We start
+            // at the beginning anyway, and this prevents decoder errors.
+            if !start.is_zero() {
+                if let Err(e) = decoder.try_seek(start) {
+                    error!("failed to seek to start position: {}", e);
                 }
             }
 
-            // Apply volume normalization if enabled.
-            let mut difference = 0.0;
+            // Apply volume normalization if enabled, on top of the pre-amp.
+            let mut track_lufs = None;
             if self.normalization {
-                match track.gain() {
-                    Some(gain) => difference = f32::from(self.gain_target_db) - gain,
-                    None => {
-                        if let Some(replay_gain) = decoder.replay_gain() {
-                            debug!("track replay gain: {replay_gain:.1} dB");
-                            let track_lufs = f32::from(Self::REPLAY_GAIN_LUFS) - replay_gain;
-                            difference = f32::from(self.gain_target_db) - track_lufs;
-                        } else {
-                            warn!(
-                                "{} {track} has no gain information, skipping normalization",
-                                track.typ()
-                            );
-                        }
-                    }
+                track_lufs = track.gain().or_else(|| {
+                    decoder.replay_gain().map(|replay_gain| {
+                        debug!("track replay gain: {replay_gain:.1} dB");
+                        f32::from(Self::REPLAY_GAIN_LUFS) - replay_gain
+                    })
+                });
+                if track_lufs.is_none() {
+                    warn!(
+                        "{} {track} has no gain information, skipping normalization",
+                        track.typ()
+                    );
                 }
-            };
+            }
+            let difference = Self::gain_difference(self.preamp_db, self.gain_target_db, track_lufs);
 
-            let rx = if difference == 0.0 {
-                // No normalization needed, just append the decoder.
-                sources.append_with_signal(decoder)
-            } else {
-                let ratio = util::db_to_ratio(difference);
+            if difference != 0.0 {
                 debug!(
                     "normalizing {} {track} by {difference:.1} dB ({})",
                     track.typ(),
-                    Percentage::from_ratio(ratio)
+                    Percentage::from_ratio(util::db_to_ratio(difference))
                 );
-                let normalized = normalize::normalize(
-                    decoder,
-                    ratio,
-                    Self::NORMALIZE_THRESHOLD_DB,
-                    Self::NORMALIZE_KNEE_WIDTH_DB,
-                    Self::NORMALIZE_ATTACK_TIME,
-                    Self::NORMALIZE_RELEASE_TIME,
-                );
-                sources.append_with_signal(normalized)
-            };
+            }
+            let rx = Self::append_normalized(sources, decoder, difference);
 
             let sample_rate = track.sample_rate.map_or("unknown".to_string(), |rate| {
                 (rate.to_f32_lossy() / 1000.).to_string()
@@ -795,10 +1387,93 @@ impl Player {
                 track.channels.unwrap_or_else(|| track.typ().default_channels())
             );
 
-            return Ok(Some(rx));
+            return Ok(Some(rx));
+        }
+
+        Ok(None)
+    }
+
+    /// Appends a decoder to the audio sources, normalized by a gain.
+    ///
+    /// # Arguments
+    ///
+    /// * `sources` - Audio sources to append to
+    /// * `decoder` - Decoder of the audio to append
+    /// * `difference` - Gain in dB to apply; zero appends the decoder as is
+    ///
+    /// Returns a receiver that is signalled when the audio finished playing.
+    fn append_normalized(
+        sources: &rodio::queue::SourcesQueueInput<SampleFormat>,
+        decoder: Decoder,
+        difference: f32,
+    ) -> std::sync::mpsc::Receiver<()> {
+        if difference == 0.0 {
+            // No normalization needed, just append the decoder.
+            sources.append_with_signal(decoder)
+        } else {
+            let normalized = normalize::normalize(
+                decoder,
+                util::db_to_ratio(difference),
+                Self::NORMALIZE_THRESHOLD_DB,
+                Self::NORMALIZE_KNEE_WIDTH_DB,
+                Self::NORMALIZE_ATTACK_TIME,
+                Self::NORMALIZE_RELEASE_TIME,
+            );
+            sources.append_with_signal(normalized)
+        }
+    }
+
+    /// Plays an audio file that does not come from Deezer, until it ends.
+    ///
+    /// For debugging the audio pipeline without the Deezer gateway: the
+    /// file passes through the same decoding, normalization and output as
+    /// tracks, but without decryption. As files have no gain metadata,
+    /// normalization uses their `ReplayGain` tags, if any. The queue is
+    /// not touched and no events are emitted.
+    ///
+    /// # Arguments
+    ///
+    /// * `file` - Unencrypted audio file in any format supported by the decoder
+    ///
+    /// # Errors
+    ///
+    /// Returns error if:
+    /// * Audio device fails to open
+    /// * Audio decoding fails
+    pub async fn play_file(&mut self, file: AudioFile) -> Result<()> {
+        self.start()?;
+
+        let mut decoder = Decoder::with_codec(file, None, TrackType::Song.default_channels())?;
+        debug!(
+            "decoding file; sample rate: {} Hz; channels: {}; duration: {:?}",
+            decoder.sample_rate(),
+            decoder.channels(),
+            decoder.total_duration(),
+        );
+
+        let mut difference = self.preamp_db;
+        if self.normalization {
+            match decoder.replay_gain() {
+                Some(replay_gain) => {
+                    let file_lufs = f32::from(Self::REPLAY_GAIN_LUFS) - replay_gain;
+                    difference += f32::from(self.gain_target_db) - file_lufs;
+                    debug!("normalizing file by {difference:.1} dB");
+                }
+                None => warn!("file has no replay gain information, skipping normalization"),
+            }
         }
 
-        Ok(None)
+        let sources = self
+            .sources
+            .as_ref()
+            .ok_or_else(|| Error::unavailable("audio sources not available"))?;
+        let rx = Self::append_normalized(sources, decoder, difference);
+        self.sink_mut()?.play();
+
+        tokio::task::spawn_blocking(move || rx.recv())
+            .await
+            .map_err(|e| Error::internal(format!("playback task failed: {e}")))?
+            .map_err(|e| Error::internal(format!("playback signal failed: {e}")))
     }
 
     /// Returns the current playback position from the sink.
@@ -830,16 +1505,24 @@ impl Player {
     /// * Audio system fails
     pub async fn run(&mut self) -> Result<()> {
         loop {
+            self.update_played();
+            self.check_max_track_duration();
+            self.check_clip_end();
+
             match self.current_rx.as_mut() {
                 Some(current_rx) => {
                     // Check if the current track has finished playing.
                     if current_rx.try_recv().is_ok() {
-                        // Save the point in time when the track finished playing.
-                        self.playing_since = self.get_pos();
+                        if let Err(e) = self.track().map_or(Ok(()), Track::check_download) {
+                            self.retry_truncated(e);
+                        } else {
+                            // Save the point in time when the track finished playing.
+                            self.playing_since = self.get_pos();
 
-                        // Move the preloaded track, if any, to the current track.
-                        self.current_rx = self.preload_rx.take();
-                        self.go_next();
+                            // Move the preloaded track, if any, to the current track.
+                            self.current_rx = self.preload_rx.take();
+                            self.go_next();
+                        }
                     }
 
                     // Preload the next track if all of the following conditions are met:
@@ -860,7 +1543,10 @@ impl Player {
                                     }
                                     Err(e) => {
                                         error!("failed to preload next {next_track_typ}: {e}");
-                                        self.mark_unavailable(next_track_id);
+                                        self.mark_unavailable(
+                                            next_track_id,
+                                            SkipReason::Unavailable,
+                                        );
                                     }
                                 }
                             }
@@ -879,7 +1565,7 @@ impl Player {
                                 Ok(rx) => {
                                     if let Some(rx) = rx {
                                         self.current_rx = Some(rx);
-                                        self.notify(Event::TrackChanged);
+                                        self.notify_track_changed();
                                         if self.is_playing() {
                                             self.notify(Event::Play);
                                         }
@@ -887,7 +1573,7 @@ impl Player {
                                 }
                                 Err(e) => {
                                     error!("failed to load {track_typ}: {e}");
-                                    self.mark_unavailable(track_id);
+                                    self.mark_unavailable(track_id, SkipReason::Unavailable);
                                 }
                             }
                         }
@@ -895,18 +1581,204 @@ impl Player {
                 }
             }
 
+            self.update_keepalive();
+            self.update_buffering();
+
             // Yield to the runtime to allow other tasks to run.
             tokio::time::sleep(Duration::from_millis(10)).await;
         }
     }
 
+    /// Adds the time played since the last update to the played time.
+    ///
+    /// The sink position does not advance while paused or buffering. Seeks
+    /// move the position without playing, so they move the reference
+    /// position instead, and rewinds are not counted.
+    fn update_played(&mut self) {
+        let pos = self.get_pos();
+        self.played = self
+            .played
+            .saturating_add(pos.saturating_sub(self.played_pos));
+        self.played_pos = pos;
+    }
+
+    /// Tracks whether playback has caught up with the downloaded audio.
+    ///
+    /// Playback is buffering when the current track is playing but not
+    /// completely downloaded, and less than
+    /// [`BUFFERING_THRESHOLD`](Self::BUFFERING_THRESHOLD) of audio is
+    /// downloaded ahead of the playback position. Livestreams are never
+    /// buffering, as they have no fixed download size.
+    ///
+    /// Emits [`Event::Buffering`] and [`Event::Buffered`] on changes.
+    fn update_buffering(&mut self) {
+        let played = self.get_pos().saturating_sub(self.playing_since);
+        let buffering = self.is_playing()
+            && self.track().is_some_and(|track| {
+                !track.is_livestream()
+                    && !track.is_complete()
+                    && track.buffered().is_none_or(|buffered| {
+                        buffered < played.saturating_add(Self::BUFFERING_THRESHOLD)
+                    })
+            });
+
+        if buffering != self.buffering {
+            self.buffering = buffering;
+            if buffering {
+                debug!("playback is buffering");
+                self.notify(Event::Buffering);
+            } else {
+                debug!("playback is no longer buffering");
+                self.notify(Event::Buffered);
+            }
+        }
+    }
+
+    /// Plays the keepalive signal only while no track is playing.
+    ///
+    /// Does nothing if keepalive is disabled or the device is not open.
+    fn update_keepalive(&self) {
+        if let Some(keepalive_sink) = self.keepalive_sink.as_ref() {
+            let idle = !self.is_playing();
+            if idle && keepalive_sink.is_paused() {
+                trace!("starting keepalive signal");
+                keepalive_sink.play();
+            } else if !idle && !keepalive_sink.is_paused() {
+                trace!("stopping keepalive signal");
+                keepalive_sink.pause();
+            }
+        }
+    }
+
     /// Marks a track as unavailable for playback.
     ///
     /// Tracks marked unavailable will be skipped during playback.
-    /// Logs a warning the first time a track is marked unavailable.
-    fn mark_unavailable(&mut self, track_id: TrackId) {
+    /// Logs a warning and emits [`Event::Skipped`] the first time a track is
+    /// marked unavailable.
+    fn mark_unavailable(&mut self, track_id: TrackId, reason: SkipReason) {
         if self.skip_tracks.insert(track_id) {
             warn!("marking track {track_id} as unavailable");
+            self.notify(Event::Skipped(track_id, reason));
+        }
+    }
+
+    /// Reloads the current track from where its truncated download ended.
+    ///
+    /// Retries up to [`TRUNCATED_DOWNLOAD_RETRIES`](Self::TRUNCATED_DOWNLOAD_RETRIES)
+    /// times per track. After that, the track is marked unavailable and
+    /// playback moves on to the next track.
+    fn retry_truncated(&mut self, e: Error) {
+        let Some(track) = self.track() else {
+            return;
+        };
+        let track_id = track.id();
+
+        if let Some(retries) = Self::truncation_retry(self.truncation_retries, track_id) {
+            let position = self.position_in_track();
+            warn!(
+                "{e} for {} {track}: reloading from {}s",
+                track.typ(),
+                position.as_secs()
+            );
+            self.truncation_retries = Some((track_id, retries));
+            self.clear();
+            self.deferred_seek = Some(position);
+        } else {
+            error!("{e} for {} {track}: skipping", track.typ());
+            self.mark_unavailable(track_id, SkipReason::Truncated);
+
+            self.playing_since = self.get_pos();
+            self.current_rx = self.preload_rx.take();
+            self.go_next();
+        }
+    }
+
+    /// Waits for a free download slot.
+    ///
+    /// The slot is freed when the returned permit is dropped.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the download slots were closed.
+    async fn download_slot(slots: &Arc<Semaphore>) -> Result<OwnedSemaphorePermit> {
+        Arc::clone(slots)
+            .acquire_owned()
+            .await
+            .map_err(|e| Error::internal(format!("download slots closed: {e}")))
+    }
+
+    /// Returns the retry count for another reload of a truncated track.
+    ///
+    /// Returns `None` if the track was already retried
+    /// [`TRUNCATED_DOWNLOAD_RETRIES`](Self::TRUNCATED_DOWNLOAD_RETRIES) times.
+    pub(crate) fn truncation_retry(
+        retried: Option<(TrackId, usize)>,
+        track_id: TrackId,
+    ) -> Option<usize> {
+        let retries = retried
+            .filter(|(retried_id, _)| *retried_id == track_id)
+            .map_or(0, |(_, retries)| retries);
+        (retries < Self::TRUNCATED_DOWNLOAD_RETRIES).then_some(retries + 1)
+    }
+
+    /// Returns the playback position within the whole current track.
+    ///
+    /// Unlike [`progress`](Self::progress), which is relative to the start of
+    /// the clip for clipped tracks, this includes the clip offset, as needed
+    /// to seek in the track.
+    fn position_in_track(&self) -> Duration {
+        let clip_start = self
+            .track()
+            .and_then(Track::clip)
+            .map_or(Duration::ZERO, |clip| clip.start);
+        self.get_pos()
+            .saturating_sub(self.playing_since)
+            .saturating_add(clip_start)
+    }
+
+    /// Skips the current track if it played longer than the maximum duration.
+    ///
+    /// The track is marked unavailable, so that the playback loop moves on to
+    /// the next track. Livestreams are continuous and never skipped.
+    fn check_max_track_duration(&mut self) {
+        let Some(max_duration) = self.max_track_duration else {
+            return;
+        };
+        if self.current_rx.is_none() {
+            return;
+        }
+
+        let Some(track) = self.track().filter(|track| !track.is_livestream()) else {
+            return;
+        };
+        let elapsed = self.get_pos().saturating_sub(self.playing_since);
+        if elapsed > max_duration {
+            let (track_id, track_typ) = (track.id(), track.typ());
+            warn!(
+                "{track_typ} {track_id} played longer than {}s: skipping",
+                max_duration.as_secs()
+            );
+            self.mark_unavailable(track_id, SkipReason::TooLong);
+            self.clear();
+        }
+    }
+
+    /// Advances to the next track if the current track played its clip.
+    ///
+    /// Clips are not cut short in the decoder, so that seeks within the clip
+    /// keep working. Instead, this ends them like a seek to the end does.
+    fn check_clip_end(&mut self) {
+        if self.current_rx.is_none() {
+            return;
+        }
+
+        let (Some(track), Some(elapsed)) = (self.track(), self.progress()) else {
+            return;
+        };
+        if track.is_clip_end(elapsed) {
+            debug!("{} {track} reached end of clip", track.typ());
+            self.clear();
+            self.go_next();
         }
     }
 
@@ -922,6 +1794,15 @@ impl Player {
         }
     }
 
+    /// Sends a track change notification with a snapshot of the current track.
+    ///
+    /// Does nothing if there is no current track.
+    fn notify_track_changed(&self) {
+        if let Some(track) = self.track() {
+            self.notify(Event::TrackChanged(Box::new(track.into())));
+        }
+    }
+
     /// Registers an event notification channel.
     ///
     /// Events sent include:
@@ -948,6 +1829,10 @@ impl Player {
     /// Emits a Play event if playback actually starts.
     /// Does nothing if already playing.
     ///
+    /// Resuming a paused livestream reconnects the stream, so playback
+    /// continues from the live edge instead of the stale buffered audio.
+    /// The Play event is then emitted once the stream is loaded again.
+    ///
     /// # Errors
     ///
     /// Returns error if:
@@ -958,6 +1843,14 @@ impl Player {
         self.start()?;
 
         if !self.is_playing() {
+            if self.current_rx.is_some() && self.track().is_some_and(Track::is_livestream) {
+                debug!("resuming livestream: reconnecting to live edge");
+                self.clear();
+                self.sink_mut()?.play();
+                self.update_keepalive();
+                return Ok(());
+            }
+
             debug!("starting playback");
             let pos = {
                 let sink_mut = self.sink_mut()?;
@@ -973,6 +1866,8 @@ impl Player {
             // Playback reporting happens every time a track starts playing or is unpaused.
             self.notify(Event::Play);
         }
+
+        self.update_keepalive();
         Ok(())
     }
 
@@ -991,6 +1886,42 @@ impl Player {
             let _ = self.sink_mut().map(|sink| sink.pause());
             self.notify(Event::Pause);
         }
+
+        self.update_keepalive();
+    }
+
+    /// Fades out and pauses playback.
+    ///
+    /// Lowers the output volume to silence over the given duration before
+    /// pausing, to prevent speakers from popping on shutdown. The volume is
+    /// restored afterwards, so playback resumes at the previous level.
+    /// Emits a Pause event if playback was actually playing.
+    /// Does nothing if already paused.
+    ///
+    /// # Arguments
+    ///
+    /// * `duration` - Duration of the fade
+    pub async fn fade_out(&mut self, duration: Duration) {
+        if !self.is_playing() {
+            return;
+        }
+
+        debug!("fading out playback over {}ms", duration.as_millis());
+        let original_volume = self.sink.as_ref().map(|_| self.output_volume.get());
+        if let Some(original_volume) = original_volume {
+            let steps = (duration.as_millis() / Self::FADE_OUT_STEP.as_millis()).max(1);
+            for i in 1..=steps {
+                let remaining = 1.0 - i.to_f32_lossy() / steps.to_f32_lossy();
+                self.output_volume.set(original_volume * remaining);
+                tokio::time::sleep(Self::FADE_OUT_STEP).await;
+            }
+        }
+
+        self.pause();
+
+        if let Some(original_volume) = original_volume {
+            self.output_volume.set(original_volume);
+        }
     }
 
     /// Returns whether playback is active.
@@ -1008,6 +1939,16 @@ impl Player {
         self.current_rx.is_some() && self.sink.as_ref().is_some_and(|sink| !sink.is_paused())
     }
 
+    /// Returns whether playback is stalled waiting for data.
+    ///
+    /// True while the current track is playing but has caught up with its
+    /// download, for example on slow networks. Updated by [`run`](Self::run).
+    #[must_use]
+    #[inline]
+    pub fn is_buffering(&self) -> bool {
+        self.buffering
+    }
+
     /// Sets the playback state.
     ///
     /// Convenience method that:
@@ -1037,6 +1978,13 @@ impl Player {
         self.queue.get(self.position)
     }
 
+    /// Returns the tracks in the queue, in playback order.
+    #[must_use]
+    #[inline]
+    pub fn queue(&self) -> &[Track] {
+        &self.queue
+    }
+
     /// Returns a mutable reference to the currently playing track, if any.
     #[must_use]
     #[inline]
@@ -1053,10 +2001,99 @@ impl Player {
     pub fn set_queue(&mut self, tracks: Vec<Track>) {
         self.clear();
         self.position = 0;
+        self.played = Duration::ZERO;
         self.queue = tracks;
         self.skip_tracks = HashSet::new();
     }
 
+    /// Replaces the playback queue, preserving the current track if possible.
+    ///
+    /// When the track that is currently loaded is also in the new queue, it
+    /// keeps playing without interruption at its new position, along with
+    /// its download and progress. Otherwise behaves like
+    /// [`set_queue`](Self::set_queue).
+    ///
+    /// If the current track occurs more than once in the new queue, the
+    /// occurrence closest to the current position is preserved.
+    ///
+    /// # Arguments
+    ///
+    /// * `tracks` - New queue in playback order
+    ///
+    /// # Returns
+    ///
+    /// `true` if the current track was preserved, `false` if the queue was
+    /// reset.
+    pub fn update_queue(&mut self, mut tracks: Vec<Track>) -> bool {
+        let current_track_id = self
+            .track()
+            .map(Track::id)
+            .filter(|_| self.current_rx.is_some());
+        let new_position = current_track_id.and_then(|current_track_id| {
+            tracks
+                .iter()
+                .enumerate()
+                .filter(|(_, track)| track.id() == current_track_id)
+                .min_by_key(|(position, _)| position.abs_diff(self.position))
+                .map(|(position, _)| position)
+        });
+
+        let Some(new_position) = new_position else {
+            self.set_queue(tracks);
+            return false;
+        };
+
+        // Keep the current track with its download state, and drop the
+        // preloaded track as the next track may have changed.
+        std::mem::swap(&mut tracks[new_position], &mut self.queue[self.position]);
+        self.queue = tracks;
+        self.position = new_position;
+        self.preload_rx = None;
+        self.sources.as_mut().map(|sources| sources.clear());
+
+        // Forget unavailable tracks that are no longer queued.
+        let queued: HashSet<_> = self.queue.iter().map(Track::id).collect();
+        self.skip_tracks
+            .retain(|track_id| queued.contains(track_id));
+
+        true
+    }
+
+    /// Returns the songs from the current position onwards whose track
+    /// tokens expire within `threshold`.
+    ///
+    /// Episodes and livestreams are skipped, as their tokens cannot be
+    /// refreshed in batch. So are songs that could not be fetched.
+    #[must_use]
+    pub fn expiring_songs(&self, threshold: Duration) -> Vec<TrackId> {
+        let deadline = SystemTime::now() + threshold;
+        self.queue
+            .iter()
+            .skip(self.position)
+            .filter(|track| {
+                track.typ() == TrackType::Song
+                    && track.available()
+                    && track.expiry().is_some_and(|expiry| expiry <= deadline)
+            })
+            .map(Track::id)
+            .collect()
+    }
+
+    /// Updates track tokens of queued tracks from refreshed list data.
+    ///
+    /// Tracks keep their position and download state.
+    pub fn refresh_tokens(&mut self, items: &[gateway::ListData]) {
+        for item in items.iter().filter(|item| !item.is_unavailable_song()) {
+            for track in self
+                .queue
+                .iter_mut()
+                .filter(|track| track.id() == item.id())
+            {
+                track.refresh_token(item);
+            }
+        }
+    }
+
     /// Returns a reference to the next track in the queue, if any.
     #[must_use]
     #[inline]
@@ -1145,6 +2182,7 @@ impl Player {
         // Clear the sink, which will drop any handles to the current and next tracks.
         self.clear();
         self.position = position;
+        self.played = Duration::ZERO;
     }
 
     /// Clears the playback state.
@@ -1176,12 +2214,12 @@ impl Player {
             sink.stop();
 
             // Restore the original volume, if any.
-            let _ = original_volume.inspect(|volume| sink.set_volume(*volume));
+            let _ = original_volume.inspect(|volume| self.output_volume.set(*volume));
 
             // With Rodio having dropped the previous output queue, we need to create a new one.
-            let (sources, output) = rodio::queue::queue(true);
-            sink.append(output);
-            self.sources = Some(sources);
+            if let Err(e) = self.append_queue() {
+                error!("failed to create output queue: {e}");
+            }
         }
 
         // Resetting the sink drops any downloads of the current and next tracks.
@@ -1194,6 +2232,7 @@ impl Player {
         }
 
         self.playing_since = Duration::ZERO;
+        self.played_pos = Duration::ZERO;
         self.current_rx = None;
         self.preload_rx = None;
     }
@@ -1210,6 +2249,9 @@ impl Player {
     /// When setting to `RepeatMode::One`:
     /// * Clears preloaded track
     /// * Disables track preloading
+    ///
+    /// The current track keeps playing. When switching from `One` to another
+    /// mode, the next track is preloaded again.
     pub fn set_repeat_mode(&mut self, repeat_mode: RepeatMode) {
         info!("setting repeat mode to {repeat_mode}");
         self.repeat_mode = repeat_mode;
@@ -1217,7 +2259,13 @@ impl Player {
         if repeat_mode == RepeatMode::One {
             // This only clears the preloaded track.
             self.sources.as_mut().map(|sources| sources.clear());
-            self.preload_rx = None;
+            if self.preload_rx.take().is_some() {
+                // Allow the next track to be preloaded again when switching
+                // back to another repeat mode.
+                if let Some(next) = self.next_track_mut() {
+                    next.reset_download();
+                }
+            }
         }
     }
 
@@ -1293,6 +2341,7 @@ impl Player {
     /// * Unnecessary volume ramping
     ///
     /// No effect if new volume equals current volume (using epsilon comparison).
+    /// Otherwise emits a `VolumeChanged` event.
     ///
     /// # Returns
     ///
@@ -1317,6 +2366,7 @@ impl Player {
 
         // Store the unscaled volume setting for playback reporting.
         self.volume = target;
+        self.notify(Event::VolumeChanged(target.as_per_mille()));
 
         // Clamp just in case the volume is set outside the valid range.
         let volume = target.as_ratio().clamp(0.0, UNITY_GAIN);
@@ -1360,8 +2410,10 @@ impl Player {
     /// Uses thread sleep for timing rather than async to ensure precise volume
     /// transitions. The short sleep duration (25ms total) makes this acceptable.
     fn ramp_volume(&mut self, target: f32) -> Result<f32> {
-        let sink_mut = self.sink_mut()?;
-        let original_volume = sink_mut.volume();
+        if self.sink.is_none() {
+            return Err(Error::unavailable("audio sink not available"));
+        }
+        let original_volume = self.output_volume.get();
 
         let millis = Self::FADE_DURATION.as_millis();
         let fade_step = (target - original_volume) / millis.to_f32_lossy();
@@ -1373,7 +2425,7 @@ impl Player {
                 original_volume + fade_step * i.to_f32_lossy()
             };
 
-            sink_mut.set_volume(faded_volume);
+            self.output_volume.set(faded_volume);
 
             // This blocks the current thread for 1 ms, but is better than making the
             // function async and waiting for the future to complete.
@@ -1383,43 +2435,68 @@ impl Player {
         Ok(original_volume)
     }
 
-    /// Returns current playback progress.
+    /// Returns the playback position within the current track.
+    ///
+    /// For clipped tracks, the position is relative to the start of the clip.
+    /// For livestreams, returns the time since the stream started playing.
+    /// Returns None if no track is loaded.
+    ///
+    /// The progress reported to the controller is this position relative to
+    /// [`duration`](Self::duration), see
+    /// [`progress_percentage`](Self::progress_percentage).
+    #[must_use]
+    pub fn progress(&self) -> Option<Duration> {
+        // The position of the sink is the total duration played, from which
+        // the time the current track started playing is subtracted.
+        self.track()
+            .map(|_| self.get_pos().saturating_sub(self.playing_since))
+    }
+
+    /// Returns current playback progress as reported to the controller.
     ///
     /// Returns None if no track is playing or track duration is unknown.
     /// Progress is calculated as:
     /// * Regular tracks: Current position relative to total duration
     /// * Livestreams: Always reports 100% since they are continuous
     #[must_use]
-    pub fn progress(&self) -> Option<Percentage> {
+    pub fn progress_percentage(&self) -> Option<Percentage> {
         self.track().and_then(|track| {
             // Livestreams are continuous and have no fixed duration.
             // We report 100% progress to indicate that they are always at the end.
             if track.is_livestream() {
                 Some(Percentage::ONE_HUNDRED)
             } else {
-                // The progress is the difference between the current position of the sink, which is the total duration played, and the time the current track started playing.
-                let duration = track.duration()?;
-                let progress = self.get_pos().saturating_sub(self.playing_since);
+                // The duration is never zero, so this does not divide by zero.
+                let duration = self.duration()?;
+                let progress = self.progress()?;
                 Some(Percentage::from_ratio(progress.div_duration_f32(duration)))
             }
         })
     }
 
+    /// Returns how long the current track has actually played.
+    ///
+    /// Unlike [`progress`](Self::progress), this excludes seeks: skipping
+    /// ahead does not add to it, and rewinding does not subtract from it.
+    /// Reloads of the same track, like on quality changes, keep counting.
+    #[must_use]
+    #[inline]
+    pub fn played(&self) -> Duration {
+        self.played
+    }
+
     /// Returns duration of current track.
     ///
-    /// For normal tracks, returns total duration.
-    /// For livestreams, returns current stream duration since start.
-    /// Returns None if no track or duration cannot be determined.
+    /// For normal tracks, returns total duration, corrected to the decoded
+    /// duration if the metadata is off. For clipped tracks, returns the clip
+    /// duration.
+    /// Returns None for livestreams, if no track is loaded or if the duration
+    /// cannot be determined.
+    #[must_use]
     pub fn duration(&self) -> Option<Duration> {
-        self.track().and_then(|track| {
-            if track.is_livestream() {
-                self.sink
-                    .as_ref()
-                    .map(|sink| sink.get_pos().saturating_sub(self.playing_since))
-            } else {
-                track.duration()
-            }
-        })
+        self.track()
+            .filter(|track| !track.is_livestream())
+            .and_then(Track::playback_duration)
     }
 
     /// Sets playback position within current track.
@@ -1446,13 +2523,16 @@ impl Player {
     /// * Seek operation fails (except for buffering/implementation limitations)
     pub fn set_progress(&mut self, progress: Percentage) -> Result<()> {
         if let Some(track) = self.track() {
-            let duration = track.duration().ok_or_else(|| {
+            let duration = track.playback_duration().ok_or_else(|| {
                 Error::unavailable(format!("duration unknown for {} {track}", track.typ()))
             })?;
 
+            // Progress of clipped tracks is relative to the clip.
+            let offset = track.clip().map_or(Duration::ZERO, |clip| clip.start);
+
             let ratio = progress.as_ratio();
             if ratio < 1.0 {
-                let mut position = duration.mul_f32(ratio);
+                let mut position = offset + duration.mul_f32(ratio);
                 let minutes = position.as_secs() / 60;
                 let seconds = position.as_secs() % 60;
                 info!(
@@ -1463,7 +2543,7 @@ impl Player {
                 // If the requested position is beyond what is buffered, seek to the buffered
                 // position instead. This prevents blocking the player and disconnections.
                 if let Some(buffered) = track.buffered() {
-                    if duration > buffered {
+                    if offset + duration > buffered {
                         if position > buffered {
                             position = buffered;
                         }
@@ -1500,8 +2580,10 @@ impl Player {
                             .and_then(|sink| sink.try_seek(position).map_err(Into::into))
                     }) {
                     Ok(()) => {
-                        // Reset the playing time to zero, as the sink will now reset it also.
-                        self.playing_since = Duration::ZERO;
+                        // Reset the playing time to the clip start, as the sink will now
+                        // report the position within the track.
+                        self.playing_since = offset;
+                        self.played_pos = self.get_pos();
                         self.deferred_seek = None;
                     }
                     Err(e) => {
@@ -1565,11 +2647,43 @@ impl Player {
 
     /// Sets preferred audio quality for playback.
     ///
+    /// When the quality changes while a track is playing in another quality,
+    /// for example after reconnecting, the current track keeps playing in
+    /// its downloaded quality, and the new quality applies to tracks not yet
+    /// downloaded. When reloading on quality changes is enabled, the current
+    /// track is reloaded in the new quality at its current position instead.
+    ///
     /// Note: Actual quality may be lower if track is not
     /// available in requested quality.
-    #[inline]
     pub fn set_audio_quality(&mut self, quality: AudioQuality) {
+        let previous = self.audio_quality;
         self.audio_quality = quality;
+        if quality == previous || self.current_rx.is_none() {
+            return;
+        }
+
+        let Some(track) = self.track() else {
+            return;
+        };
+        if track.is_livestream() || track.quality() == quality {
+            return;
+        }
+
+        if self.reload_on_quality_change {
+            info!(
+                "audio quality changed from {previous} to {quality}: reloading {} {track}",
+                track.typ()
+            );
+            let position = self.position_in_track();
+            self.clear();
+            self.deferred_seek = Some(position);
+        } else {
+            info!(
+                "audio quality changed from {previous} to {quality}: keeping {} {track} in {}",
+                track.typ(),
+                track.quality()
+            );
+        }
     }
 
     /// Returns whether volume normalization is enabled.
@@ -1613,7 +2727,7 @@ impl Player {
     ///
     /// # Example
     /// ```
-    /// let mut player = Player::new(&config, "").await?;
+    /// let mut player = Player::new(&config, &OutputConfig::default()).await?;
     /// assert!(!player.is_started());
     ///
     /// player.start()?;
@@ -1635,3 +2749,181 @@ impl Drop for Player {
         self.stop();
     }
 }
+
+/// Output that discards audio at real-time pace.
+///
+/// Used instead of an output device when audio output is disabled. A thread
+/// consumes samples as fast as a device would play them, so that playback
+/// progress advances like it does on a device. The thread stops when this is
+/// dropped.
+struct NullOutput {
+    /// Dropped to stop the consuming thread.
+    _stop_tx: std::sync::mpsc::Sender<()>,
+}
+
+impl NullOutput {
+    /// Number of blocks of samples to consume per second.
+    const BLOCKS_PER_SECOND: u32 = 100;
+
+    /// Starts consuming samples from a sink output.
+    ///
+    /// # Arguments
+    ///
+    /// * `output` - Output of the sink to consume
+    fn spawn(output: rodio::queue::SourcesQueueOutput<SampleFormat>) -> Self {
+        let (stop_tx, stop_rx) = std::sync::mpsc::channel();
+        std::thread::spawn(move || Self::run(output, &stop_rx));
+        Self { _stop_tx: stop_tx }
+    }
+
+    /// Consumes samples in blocks, sleeping to keep real-time pace.
+    ///
+    /// Blocks do not cross frame boundaries, so each block is paced at the
+    /// sample rate and channel count it was played at.
+    fn run(
+        mut output: rodio::queue::SourcesQueueOutput<SampleFormat>,
+        stop_rx: &std::sync::mpsc::Receiver<()>,
+    ) {
+        let started = std::time::Instant::now();
+        let mut consumed = Duration::ZERO;
+
+        while stop_rx.try_recv() == Err(std::sync::mpsc::TryRecvError::Empty) {
+            let samples_per_second = (output.sample_rate() * u32::from(output.channels())).max(1);
+            let max_block = (samples_per_second / Self::BLOCKS_PER_SECOND).max(1);
+            let block = output
+                .current_frame_len()
+                .and_then(|len| u32::try_from(len).ok())
+                .filter(|len| *len > 0)
+                .map_or(max_block, |len| len.min(max_block));
+
+            for _ in 0..block {
+                if output.next().is_none() {
+                    return;
+                }
+            }
+
+            consumed += Duration::from_secs_f64(f64::from(block) / f64::from(samples_per_second));
+            if let Some(ahead) = consumed.checked_sub(started.elapsed()) {
+                std::thread::sleep(ahead);
+            }
+        }
+    }
+}
+
+/// Inaudible dithered noise to keep audio devices awake.
+///
+/// Some DACs and AV receivers power down or mute after a period of digital
+/// silence, clipping the start of the next track. This source outputs
+/// triangular noise at the given peak amplitude, which is not digital
+/// silence but far below the audible threshold.
+struct KeepAlive {
+    /// Peak amplitude of the noise
+    amplitude: f32,
+}
+
+impl KeepAlive {
+    /// Creates a new keepalive source.
+    ///
+    /// # Arguments
+    ///
+    /// * `amplitude` - Peak amplitude of the noise (0.0 to 1.0)
+    fn new(amplitude: f32) -> Self {
+        Self { amplitude }
+    }
+}
+
+impl Iterator for KeepAlive {
+    type Item = SampleFormat;
+
+    #[inline]
+    fn next(&mut self) -> Option<Self::Item> {
+        // Difference of two uniform distributions yields triangular noise.
+        Some((fastrand::f32() - fastrand::f32()) * self.amplitude)
+    }
+}
+
+impl Source for KeepAlive {
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        2
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        DEFAULT_SAMPLE_RATE
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn track_id(id: i64) -> TrackId {
+        TrackId::new(id).unwrap()
+    }
+
+    #[test]
+    fn truncation_retry_allows_first_reload() {
+        assert_eq!(Player::truncation_retry(None, track_id(1)), Some(1));
+    }
+
+    #[test]
+    fn truncation_retry_stops_after_retries() {
+        let retried = Some((track_id(1), Player::TRUNCATED_DOWNLOAD_RETRIES));
+        assert_eq!(Player::truncation_retry(retried, track_id(1)), None);
+    }
+
+    #[test]
+    fn truncation_retry_counts_per_track() {
+        let retried = Some((track_id(1), Player::TRUNCATED_DOWNLOAD_RETRIES));
+        assert_eq!(Player::truncation_retry(retried, track_id(2)), Some(1));
+    }
+
+    #[test]
+    fn gain_difference_applies_preamp_without_normalization() {
+        assert!((Player::gain_difference(0.0, -15, None)).abs() < f32::EPSILON);
+        assert!((Player::gain_difference(3.0, -15, None) - 3.0).abs() < f32::EPSILON);
+        assert!((Player::gain_difference(-6.0, -15, None) + 6.0).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn gain_difference_adds_preamp_to_normalization() {
+        assert!((Player::gain_difference(0.0, -15, Some(-9.0)) + 6.0).abs() < f32::EPSILON);
+        assert!((Player::gain_difference(3.0, -15, Some(-9.0)) + 3.0).abs() < f32::EPSILON);
+        assert!((Player::gain_difference(3.0, -15, Some(-20.0)) - 8.0).abs() < f32::EPSILON);
+    }
+
+    #[test]
+    fn next_position_moves_to_next_track() {
+        for repeat_mode in [RepeatMode::None, RepeatMode::All, RepeatMode::Unrecognized] {
+            assert_eq!(Player::next_position(0, 3, repeat_mode), (1, true));
+        }
+    }
+
+    #[test]
+    fn next_position_repeats_one() {
+        assert_eq!(Player::next_position(1, 3, RepeatMode::One), (1, true));
+        assert_eq!(Player::next_position(2, 3, RepeatMode::One), (2, true));
+    }
+
+    #[test]
+    fn next_position_at_end_of_queue() {
+        assert_eq!(Player::next_position(2, 3, RepeatMode::All), (0, true));
+        assert_eq!(Player::next_position(2, 3, RepeatMode::None), (0, false));
+        assert_eq!(
+            Player::next_position(2, 3, RepeatMode::Unrecognized),
+            (0, false)
+        );
+        assert_eq!(Player::next_position(0, 1, RepeatMode::All), (0, true));
+    }
+}
@@ -67,7 +67,7 @@ use flate2::{
 };
 use protobuf::Message;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
-use serde_repr::{Deserialize_repr, Serialize_repr};
+use serde_repr::Serialize_repr;
 use serde_with::{
     formats::Flexible, json::JsonString, serde_as, DeserializeFromStr, DisplayFromStr,
     DurationSeconds, NoneAsEmptyString, SerializeDisplay,
@@ -402,6 +402,31 @@ pub enum DeviceId {
     Other(String),
 }
 
+impl DeviceId {
+    /// Returns the UUID of the device ID, if any.
+    ///
+    /// Also returns the UUID of `Other` device IDs that hold a UUID in
+    /// another format than the standard one.
+    #[must_use]
+    pub fn uuid(&self) -> Option<Uuid> {
+        match self {
+            Self::Uuid(uuid) => Some(*uuid),
+            Self::Other(s) => Uuid::try_parse(s).ok(),
+        }
+    }
+
+    /// Returns whether both device IDs identify the same device.
+    ///
+    /// UUIDs are compared by value, regardless of their format on the wire.
+    #[must_use]
+    pub fn is_same(&self, other: &Self) -> bool {
+        match (self.uuid(), other.uuid()) {
+            (Some(uuid), Some(other)) => uuid == other,
+            _ => self == other,
+        }
+    }
+}
+
 /// Creates a new random UUID device identifier.
 ///
 /// This is the recommended default for new devices joining
@@ -895,6 +920,9 @@ impl Body {
 /// use serde_json;
 /// assert_eq!(serde_json::to_string(&Status::OK)?, "0");
 /// assert_eq!(serde_json::to_string(&Status::Error)?, "1");
+///
+/// // Unexpected values deserialize to Error
+/// assert_eq!(serde_json::from_str::<Status>("2")?, Status::Error);
 /// ```
 ///
 /// Using in command responses:
@@ -905,19 +933,7 @@ impl Body {
 ///     status: Status::OK,
 /// };
 /// ```
-#[derive(
-    Copy,
-    Clone,
-    Debug,
-    Default,
-    Hash,
-    Serialize_repr,
-    Deserialize_repr,
-    PartialOrd,
-    Ord,
-    PartialEq,
-    Eq,
-)]
+#[derive(Copy, Clone, Debug, Default, Hash, Serialize_repr, PartialOrd, Ord, PartialEq, Eq)]
 #[repr(u64)]
 pub enum Status {
     /// Command executed successfully.
@@ -952,6 +968,39 @@ impl fmt::Display for Status {
     }
 }
 
+/// Converts a raw protocol integer into a status.
+///
+/// Only `0` means success. Any other value maps to [`Status::Error`],
+/// following the protocol's "fail-by-default" approach.
+///
+/// # Examples
+///
+/// ```rust
+/// use pleezer::protocol::connect::Status;
+///
+/// assert_eq!(Status::from(0), Status::OK);
+/// assert_eq!(Status::from(1), Status::Error);
+/// assert_eq!(Status::from(42), Status::Error);
+/// ```
+impl From<i64> for Status {
+    fn from(value: i64) -> Self {
+        match value {
+            0 => Status::OK,
+            _ => Status::Error,
+        }
+    }
+}
+
+/// Deserializes a status from its protocol integer.
+///
+/// Unlike a plain `repr` deserialization, this does not fail on unexpected
+/// values but maps them to [`Status::Error`].
+impl<'de> Deserialize<'de> for Status {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        i64::deserialize(deserializer).map(Self::from)
+    }
+}
+
 /// Playback repeat mode in the Deezer Connect protocol.
 ///
 /// Controls how playback continues after reaching the end of the current track
@@ -995,20 +1044,11 @@ impl fmt::Display for Status {
 /// assert_eq!(serde_json::to_string(&RepeatMode::All)?, "1");
 /// assert_eq!(serde_json::to_string(&RepeatMode::One)?, "2");
 /// assert_eq!(serde_json::to_string(&RepeatMode::Unrecognized)?, "-1");
+///
+/// // Unrecognized values deserialize to Unrecognized
+/// assert_eq!(serde_json::from_str::<RepeatMode>("3")?, RepeatMode::Unrecognized);
 /// ```
-#[derive(
-    Copy,
-    Clone,
-    Debug,
-    Default,
-    Hash,
-    Serialize_repr,
-    Deserialize_repr,
-    PartialOrd,
-    Ord,
-    PartialEq,
-    Eq,
-)]
+#[derive(Copy, Clone, Debug, Default, Hash, Serialize_repr, PartialOrd, Ord, PartialEq, Eq)]
 // `i64` because this is serialized into and deserialized from JSON.
 #[repr(i64)]
 pub enum RepeatMode {
@@ -1048,6 +1088,40 @@ impl fmt::Display for RepeatMode {
     }
 }
 
+/// Converts a raw protocol integer into a repeat mode.
+///
+/// Unrecognized values map to [`RepeatMode::Unrecognized`], so that progress
+/// reports and skip commands with newer repeat modes are still accepted.
+///
+/// # Examples
+///
+/// ```rust
+/// assert_eq!(RepeatMode::from(0), RepeatMode::None);
+/// assert_eq!(RepeatMode::from(1), RepeatMode::All);
+/// assert_eq!(RepeatMode::from(2), RepeatMode::One);
+/// assert_eq!(RepeatMode::from(42), RepeatMode::Unrecognized);
+/// ```
+impl From<i64> for RepeatMode {
+    fn from(value: i64) -> Self {
+        match value {
+            0 => RepeatMode::None,
+            1 => RepeatMode::All,
+            2 => RepeatMode::One,
+            _ => RepeatMode::Unrecognized,
+        }
+    }
+}
+
+/// Deserializes a repeat mode from its protocol integer.
+///
+/// Unlike a plain `repr` deserialization, this does not fail on unrecognized
+/// values but maps them to [`RepeatMode::Unrecognized`].
+impl<'de> Deserialize<'de> for RepeatMode {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        i64::deserialize(deserializer).map(Self::from)
+    }
+}
+
 #[expect(clippy::doc_markdown)]
 /// # Quality Levels
 ///
@@ -1123,20 +1197,11 @@ impl fmt::Display for RepeatMode {
 /// assert_eq!(serde_json::to_string(&AudioQuality::High)?, "2");
 /// assert_eq!(serde_json::to_string(&AudioQuality::Lossless)?, "3");
 /// assert_eq!(serde_json::to_string(&AudioQuality::Unknown)?, "-1");
+///
+/// // Unrecognized values deserialize to Unknown
+/// assert_eq!(serde_json::from_str::<AudioQuality>("4")?, AudioQuality::Unknown);
 /// ```
-#[derive(
-    Copy,
-    Clone,
-    Debug,
-    Default,
-    Hash,
-    Serialize_repr,
-    Deserialize_repr,
-    PartialEq,
-    Eq,
-    PartialOrd,
-    Ord,
-)]
+#[derive(Copy, Clone, Debug, Default, Hash, Serialize_repr, PartialEq, Eq, PartialOrd, Ord)]
 // `i64` because this is serialized into and deserialized from JSON.
 #[repr(i64)]
 pub enum AudioQuality {
@@ -1234,6 +1299,40 @@ impl AudioQuality {
     }
 }
 
+/// Converts a raw protocol integer into an audio quality level.
+///
+/// Unrecognized values map to [`AudioQuality::Unknown`], so that progress
+/// reports from controllers with newer quality levels are still accepted.
+///
+/// # Examples
+///
+/// ```rust
+/// assert_eq!(AudioQuality::from(0), AudioQuality::Basic);
+/// assert_eq!(AudioQuality::from(3), AudioQuality::Lossless);
+/// assert_eq!(AudioQuality::from(42), AudioQuality::Unknown);
+/// ```
+impl From<i64> for AudioQuality {
+    fn from(value: i64) -> Self {
+        match value {
+            0 => AudioQuality::Basic,
+            1 => AudioQuality::Standard,
+            2 => AudioQuality::High,
+            3 => AudioQuality::Lossless,
+            _ => AudioQuality::Unknown,
+        }
+    }
+}
+
+/// Deserializes an audio quality level from its protocol integer.
+///
+/// Unlike a plain `repr` deserialization, this does not fail on unrecognized
+/// values but maps them to [`AudioQuality::Unknown`].
+impl<'de> Deserialize<'de> for AudioQuality {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        i64::deserialize(deserializer).map(Self::from)
+    }
+}
+
 /// Formats the audio quality for human-readable output.
 ///
 /// # Examples
@@ -1348,7 +1447,17 @@ impl FromStr for AudioQuality {
 /// assert_eq!(serde_json::to_string(&half)?, "0.5");
 /// ```
 ///
-/// /// # Floating-Point Comparisons
+/// # Precision
+///
+/// Values are deserialized into and serialized from `f32` directly, using the
+/// shortest representation that round-trips. This way a volume set by a
+/// controller is reported back exactly as it was received, without drift:
+/// a volume of `0.46` is reported as `0.46`, and equals 46%.
+///
+/// Do not widen the ratio to `f64` before serializing: `f64::from(0.46_f32)`
+/// is `0.46000000834465027`, which would be reported as such.
+///
+/// # Floating-Point Comparisons
 ///
 /// The `PartialEq` implementation uses a relative epsilon comparison method for
 /// floating-point values to handle numerical imprecision.
@@ -1448,6 +1557,24 @@ impl Percentage {
     pub fn as_percent(&self) -> f32 {
         self.0 * 100.0
     }
+
+    /// Returns the value in per mille (0 to 1000), rounded to the nearest
+    /// integer.
+    ///
+    /// Unlike the floating point value, the result can be compared exactly
+    /// and hashed. Values outside of 0% to 100% are clamped.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// let p = Percentage::from_ratio(0.7536);
+    /// assert_eq!(p.as_per_mille(), 754);
+    /// ```
+    #[must_use]
+    #[expect(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    pub fn as_per_mille(&self) -> u16 {
+        (self.0.clamp(0.0, 1.0) * 1000.0).round() as u16
+    }
 }
 
 /// Compares two percentages using a relative epsilon comparison method.
@@ -1859,7 +1986,9 @@ struct WireBody {
 
     /// Reserved field for future use.
     ///
-    /// Currently always empty. Maintained for protocol compatibility.
+    /// Always sent empty. Deezer has not been observed to populate it on
+    /// inbound messages either; if it does, the contents are logged at trace
+    /// level to help research message ordering.
     clock: HashMap<String, serde_json::Value>,
 }
 
@@ -2891,6 +3020,13 @@ impl TryFrom<WireBody> for Body {
         let message_id = wire_body.message_id;
         let message_type = wire_body.message_type;
 
+        if !wire_body.clock.is_empty() {
+            trace!(
+                "{message_type} {message_id} has clock: {:?}",
+                wire_body.clock
+            );
+        }
+
         let body = match message_type {
             MessageType::Acknowledgement => {
                 if let Payload::Acknowledgement { acknowledgement_id } = wire_body.payload {
@@ -3110,3 +3246,161 @@ impl fmt::Display for MessageType {
         write!(f, "{self:?}")
     }
 }
+
+/// Parses a message type from its wire format name.
+///
+/// # Examples
+///
+/// ```rust
+/// assert_eq!("ack".parse::<MessageType>()?, MessageType::Acknowledgement);
+/// assert_eq!("refreshQueue".parse::<MessageType>()?, MessageType::RefreshQueue);
+/// assert!("RefreshQueue".parse::<MessageType>().is_err());
+/// ```
+///
+/// # Errors
+///
+/// Returns an error if the name is not a known message type.
+impl FromStr for MessageType {
+    type Err = Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        use serde::de::{value::Error as ValueError, IntoDeserializer};
+
+        Self::deserialize(s.into_deserializer())
+            .map_err(|e: ValueError| Error::invalid_argument(format!("{s}: {e}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn status_deserializes_ok() {
+        assert_eq!(serde_json::from_str::<Status>("0").unwrap(), Status::OK);
+    }
+
+    #[test]
+    fn status_deserializes_error() {
+        assert_eq!(serde_json::from_str::<Status>("1").unwrap(), Status::Error);
+    }
+
+    #[test]
+    fn status_deserializes_unexpected_value_to_error() {
+        assert_eq!(serde_json::from_str::<Status>("2").unwrap(), Status::Error);
+        assert_eq!(serde_json::from_str::<Status>("-1").unwrap(), Status::Error);
+    }
+
+    #[test]
+    fn status_rejects_non_integer() {
+        assert!(serde_json::from_str::<Status>("\"0\"").is_err());
+    }
+
+    #[test]
+    fn audio_quality_deserializes_known_values() {
+        for quality in [
+            AudioQuality::Basic,
+            AudioQuality::Standard,
+            AudioQuality::High,
+            AudioQuality::Lossless,
+            AudioQuality::Unknown,
+        ] {
+            let json = serde_json::to_string(&quality).unwrap();
+            assert_eq!(
+                serde_json::from_str::<AudioQuality>(&json).unwrap(),
+                quality
+            );
+        }
+    }
+
+    #[test]
+    fn audio_quality_deserializes_unrecognized_value_to_unknown() {
+        for json in ["4", "-2", "42"] {
+            assert_eq!(
+                serde_json::from_str::<AudioQuality>(json).unwrap(),
+                AudioQuality::Unknown,
+                "{json}"
+            );
+        }
+    }
+
+    #[test]
+    fn audio_quality_rejects_non_integer() {
+        assert!(serde_json::from_str::<AudioQuality>("\"2\"").is_err());
+    }
+
+    #[test]
+    fn repeat_mode_deserializes_known_values() {
+        for mode in [RepeatMode::None, RepeatMode::All, RepeatMode::One] {
+            let json = serde_json::to_string(&mode).unwrap();
+            assert_eq!(serde_json::from_str::<RepeatMode>(&json).unwrap(), mode);
+        }
+    }
+
+    #[test]
+    fn repeat_mode_deserializes_unrecognized_value() {
+        for json in ["3", "-1", "42"] {
+            assert_eq!(
+                serde_json::from_str::<RepeatMode>(json).unwrap(),
+                RepeatMode::Unrecognized,
+                "{json}"
+            );
+        }
+    }
+
+    #[test]
+    fn repeat_mode_rejects_non_integer() {
+        assert!(serde_json::from_str::<RepeatMode>("\"1\"").is_err());
+    }
+
+    #[test]
+    fn percentage_round_trips_without_drift() {
+        let volume: Percentage = serde_json::from_str("0.46").unwrap();
+        assert_eq!(serde_json::to_string(&volume).unwrap(), "0.46");
+        assert_eq!(volume.to_string(), "46.0%");
+
+        let percent = Percentage::from_percent(46.0);
+        assert_eq!(percent.as_ratio().to_bits(), volume.as_ratio().to_bits());
+        assert_eq!(serde_json::to_string(&percent).unwrap(), "0.46");
+    }
+
+    #[test]
+    fn percentage_as_per_mille() {
+        for (ratio, per_mille) in [
+            (0.0, 0),
+            (0.46, 460),
+            (0.7536, 754),
+            (1.0, 1000),
+            (1.5, 1000),
+            (-0.5, 0),
+        ] {
+            assert_eq!(
+                Percentage::from_ratio(ratio).as_per_mille(),
+                per_mille,
+                "{ratio}"
+            );
+        }
+    }
+
+    #[test]
+    fn playback_progress_serializes_volume_without_drift() {
+        let payload = Payload::PlaybackProgress {
+            queue_id: "queue".to_string(),
+            element_id: QueueItem {
+                queue_id: "queue".to_string(),
+                track_id: TrackId::new(1).unwrap(),
+                position: 0,
+            },
+            duration: None,
+            buffered: None,
+            progress: None,
+            volume: serde_json::from_str("0.46").unwrap(),
+            quality: AudioQuality::Standard,
+            is_playing: true,
+            is_shuffle: false,
+            repeat_mode: RepeatMode::None,
+        };
+        let json = serde_json::to_string(&payload).unwrap();
+        assert!(json.contains(r#""volume":0.46,"#), "{json}");
+    }
+}
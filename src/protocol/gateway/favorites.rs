@@ -0,0 +1,121 @@
+//! Favorite songs endpoints.
+//!
+//! This module handles the user's favorite ("loved") songs:
+//! * Listing the IDs of all favorite songs
+//! * Adding a song to the favorites
+//! * Removing a song from the favorites
+//!
+//! Only songs can be favorited through these endpoints. Episodes and
+//! livestreams are not supported.
+//!
+//! # Wire Format
+//!
+//! Listing request:
+//! ```json
+//! {
+//!     "nb": 10000,
+//!     "start": 0
+//! }
+//! ```
+//!
+//! Listing response:
+//! ```json
+//! {
+//!     "data": [
+//!         { "SNG_ID": "123456" }
+//!     ],
+//!     "count": 1,
+//!     "total": 1,
+//!     "filtered_count": 0
+//! }
+//! ```
+//!
+//! Add or remove request:
+//! ```json
+//! {
+//!     "SNG_ID": "123456"
+//! }
+//! ```
+//!
+//! Add or remove response:
+//! ```json
+//! true
+//! ```
+//!
+//! # Example
+//!
+//! ```rust
+//! use deezer::gateway::{favorites, Response};
+//!
+//! let request = favorites::Request {
+//!     song_id: 123456.try_into()?,
+//! };
+//!
+//! let response: Response<favorites::AddFavorite> = /* gateway response */;
+//! ```
+
+use serde::{Deserialize, Serialize};
+use serde_with::{serde_as, DisplayFromStr, PickFirst};
+
+use super::Method;
+use crate::track::TrackId;
+
+/// Gateway method name for listing favorite songs.
+impl Method for FavoriteSong {
+    const METHOD: &'static str = "song.getFavoriteIds";
+}
+
+/// Gateway method name for adding a song to the favorites.
+impl Method for AddFavorite {
+    const METHOD: &'static str = "favorite_song.add";
+}
+
+/// Gateway method name for removing a song from the favorites.
+impl Method for RemoveFavorite {
+    const METHOD: &'static str = "favorite_song.remove";
+}
+
+/// Song in the user's favorites.
+#[serde_as]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Deserialize, Debug, Hash)]
+pub struct FavoriteSong {
+    /// ID of the favorite song.
+    #[serde(rename = "SNG_ID")]
+    #[serde_as(as = "PickFirst<(DisplayFromStr, _)>")]
+    pub id: TrackId,
+}
+
+/// Result of adding a song to the favorites.
+///
+/// Contains `true` when the song was added.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Deserialize, Debug, Hash)]
+#[serde(transparent)]
+pub struct AddFavorite(pub bool);
+
+/// Result of removing a song from the favorites.
+///
+/// Contains `true` when the song was removed.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Deserialize, Debug, Hash)]
+#[serde(transparent)]
+pub struct RemoveFavorite(pub bool);
+
+/// Request parameters for listing favorite songs.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Serialize, Debug, Hash)]
+pub struct ListRequest {
+    /// Maximum number of songs to return.
+    #[serde(rename = "nb")]
+    pub count: u64,
+
+    /// Offset of the first song to return.
+    pub start: u64,
+}
+
+/// Request parameters for adding or removing a favorite song.
+#[serde_as]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Serialize, Debug, Hash)]
+pub struct Request {
+    /// ID of the song to add or remove.
+    #[serde(rename = "SNG_ID")]
+    #[serde_as(as = "DisplayFromStr")]
+    pub song_id: TrackId,
+}
@@ -407,6 +407,35 @@ where
 }
 
 impl ListData {
+    /// Creates a placeholder for a song that could not be fetched.
+    ///
+    /// Keeps the song's position in a queue, so that positions stay in step
+    /// with the controller. The placeholder has no track token, so the song
+    /// is unavailable for playback and skipped.
+    #[must_use]
+    pub fn unavailable_song(id: TrackId) -> Self {
+        ListData::Song {
+            id,
+            artist: String::new(),
+            album_title: String::new(),
+            album_cover: String::new(),
+            duration: Duration::ZERO,
+            title: String::new(),
+            gain: None,
+            track_token: String::new(),
+            expiry: SystemTime::UNIX_EPOCH,
+            fallback: None,
+        }
+    }
+
+    /// Returns whether this is a placeholder for a song that could not be
+    /// fetched.
+    #[must_use]
+    #[inline]
+    pub fn is_unavailable_song(&self) -> bool {
+        matches!(self, ListData::Song { track_token, .. } if track_token.is_empty())
+    }
+
     /// Returns the type of this track.
     ///
     /// Returns a string identifier for the content type:
@@ -490,11 +519,15 @@ impl ListData {
     /// * Track duration for songs
     /// * Episode duration for podcasts
     /// * None for livestreams
+    /// * None when the duration is missing or invalid, which deserializes
+    ///   as zero
     #[must_use]
     #[inline]
     pub fn duration(&self) -> Option<Duration> {
         match self {
-            ListData::Song { duration, .. } | ListData::Episode { duration, .. } => Some(*duration),
+            ListData::Song { duration, .. } | ListData::Episode { duration, .. } => {
+                Some(*duration).filter(|duration| !duration.is_zero())
+            }
             ListData::Livestream { .. } => None,
         }
     }
@@ -588,6 +621,34 @@ impl LivestreamUrls {
             .map(|(bitrate, codec_url)| (bitrate, codec_url.clone()))
             .collect()
     }
+
+    /// Returns (bitrate, `CodecUrl`) pairs in order of preference for a target bitrate.
+    ///
+    /// Bitrates at or below the target come first, from the closest to the
+    /// lowest. When none are, or as a last resort, bitrates above the target
+    /// follow from the closest to the highest, so that a stream still plays.
+    ///
+    /// # Arguments
+    ///
+    /// * `target` - Target bitrate in kbps, or `None` to prefer the highest
+    ///
+    /// # Examples
+    ///
+    /// With streams of 64, 128 and 320 kbps:
+    /// * Target 128 kbps: 128, 64, 320
+    /// * Target 96 kbps: 64, 128, 320
+    /// * Target 32 kbps: 64, 128, 320
+    /// * No target: 320, 128, 64
+    #[must_use]
+    pub fn by_preference(&self, target: Option<usize>) -> Vec<(usize, CodecUrl)> {
+        let (mut preferred, higher): (Vec<_>, Vec<_>) = self
+            .sort_by_bitrate()
+            .into_iter()
+            .partition(|(bitrate, _)| target.is_none_or(|target| *bitrate <= target));
+        preferred.reverse();
+        preferred.extend(higher);
+        preferred
+    }
 }
 
 /// URLs for different audio codecs of a livestream.
@@ -603,3 +664,52 @@ pub struct CodecUrl {
     /// URL for MP3 stream if available
     pub mp3: Option<Url>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn urls(bitrates: &[&str]) -> LivestreamUrls {
+        LivestreamUrls {
+            data: bitrates
+                .iter()
+                .map(|bitrate| ((*bitrate).to_string(), CodecUrl::default()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn by_preference_orders_bitrates() {
+        let urls = urls(&["320", "64", "128", "hls"]);
+        for (target, expected) in [
+            (Some(128), [128, 64, 320]),
+            (Some(96), [64, 128, 320]),
+            (Some(32), [64, 128, 320]),
+            (Some(1_000), [320, 128, 64]),
+            (None, [320, 128, 64]),
+        ] {
+            let bitrates: Vec<_> = urls
+                .by_preference(target)
+                .into_iter()
+                .map(|(bitrate, _)| bitrate)
+                .collect();
+            assert_eq!(bitrates, expected, "{target:?}");
+        }
+    }
+
+    #[test]
+    fn by_preference_without_streams() {
+        assert!(urls(&[]).by_preference(Some(128)).is_empty());
+    }
+
+    #[test]
+    fn duration_treats_zero_as_unknown() {
+        let mut song = ListData::unavailable_song(TrackId::new(1).unwrap());
+        assert_eq!(song.duration(), None);
+
+        if let ListData::Song { duration, .. } = &mut song {
+            *duration = Duration::from_secs(180);
+        }
+        assert_eq!(song.duration(), Some(Duration::from_secs(180)));
+    }
+}
@@ -0,0 +1,157 @@
+//! Listening history endpoint.
+//!
+//! This module handles logging completed plays to the user's listening
+//! history. Deezer uses this history for recommendations like Flow, in
+//! addition to the stream reporting over the websocket.
+//!
+//! The request format was reverse engineered from the official web player.
+//! Only the fields needed to register a listen are included.
+//!
+//! # Wire Format
+//!
+//! Request:
+//! ```json
+//! {
+//!     "params": {
+//!         "timestamp": 1700000000,
+//!         "ts_listen": 1700000000,
+//!         "type": 1,
+//!         "stat": {
+//!             "seek": 0,
+//!             "pause": 0,
+//!             "sync": 1
+//!         },
+//!         "media": {
+//!             "id": "123456",
+//!             "type": "song",
+//!             "format": "MP3_128"
+//!         },
+//!         "lt": 187
+//!     }
+//! }
+//! ```
+//!
+//! Response:
+//! ```json
+//! true
+//! ```
+//!
+//! # Example
+//!
+//! ```rust
+//! use deezer::gateway::{listen, Response};
+//!
+//! let request = listen::Request {
+//!     params: listen::Params {
+//!         timestamp: 1700000000,
+//!         ts_listen: 1700000000,
+//!         typ: listen::LISTEN_TYPE_STREAM,
+//!         stat: listen::Stat::default(),
+//!         media: listen::Media {
+//!             id: 123456.try_into()?,
+//!             typ: TrackType::Song,
+//!             format: Format::MP3_128,
+//!         },
+//!         listening_time: 187,
+//!     },
+//! };
+//!
+//! let response: Response<listen::Listen> = /* gateway response */;
+//! ```
+
+use serde::{Deserialize, Serialize};
+use serde_with::{serde_as, DisplayFromStr};
+
+use super::Method;
+use crate::{
+    protocol::media::Format,
+    track::{TrackId, TrackType},
+};
+
+/// Gateway method name for logging a listen.
+impl Method for Listen {
+    const METHOD: &'static str = "log.listen";
+}
+
+/// Listen type for regular streaming playback.
+pub const LISTEN_TYPE_STREAM: u64 = 1;
+
+/// Result of logging a listen.
+///
+/// Contains `true` when the listen was registered.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Deserialize, Debug, Hash)]
+#[serde(transparent)]
+pub struct Listen(pub bool);
+
+/// Request wrapper for logging a listen.
+#[derive(Copy, Clone, Eq, PartialEq, Serialize, Debug, Hash)]
+pub struct Request {
+    /// Listen parameters.
+    pub params: Params,
+}
+
+/// Parameters of a listen.
+#[derive(Copy, Clone, Eq, PartialEq, Serialize, Debug, Hash)]
+pub struct Params {
+    /// UNIX timestamp in seconds of when the listen was logged.
+    pub timestamp: u64,
+
+    /// UNIX timestamp in seconds of when playback started.
+    pub ts_listen: u64,
+
+    /// Type of listen, see [`LISTEN_TYPE_STREAM`].
+    #[serde(rename = "type")]
+    pub typ: u64,
+
+    /// Playback statistics.
+    pub stat: Stat,
+
+    /// Media that was listened to.
+    pub media: Media,
+
+    /// Listening time in seconds.
+    #[serde(rename = "lt")]
+    pub listening_time: u64,
+}
+
+/// Playback statistics of a listen.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Serialize, Debug, Hash)]
+pub struct Stat {
+    /// Number of seeks during playback.
+    pub seek: u64,
+
+    /// Number of pauses during playback.
+    pub pause: u64,
+
+    /// Whether the listen is logged in real time (`1`) or afterwards (`0`).
+    pub sync: u64,
+}
+
+/// Defaults to a real time listen without seeks or pauses.
+impl Default for Stat {
+    fn default() -> Self {
+        Self {
+            seek: 0,
+            pause: 0,
+            sync: 1,
+        }
+    }
+}
+
+/// Media that was listened to.
+#[serde_as]
+#[derive(Copy, Clone, Eq, PartialEq, Serialize, Debug, Hash)]
+pub struct Media {
+    /// ID of the track.
+    #[serde_as(as = "DisplayFromStr")]
+    pub id: TrackId,
+
+    /// Type of the track.
+    #[serde(rename = "type")]
+    #[serde_as(as = "DisplayFromStr")]
+    pub typ: TrackType,
+
+    /// Format the track was played in.
+    #[serde_as(as = "DisplayFromStr")]
+    pub format: Format,
+}
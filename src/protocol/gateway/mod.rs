@@ -3,9 +3,13 @@
 //! This module provides type-safe interfaces to Deezer's gateway API endpoints,
 //! handling:
 //! * Authentication tokens ([`arl`])
+//! * Favorite songs ([`favorites`])
+//! * Listening history ([`listen`])
 //! * User data and settings ([`user_data`])
 //! * Content listings ([`list_data`])
+//! * Playlist songs ([`playlist`])
 //! * Radio stations ([`user_radio`])
+//! * Content search ([`search`])
 //!
 //! Supports multiple content types:
 //! * Songs - Regular music tracks
@@ -39,7 +43,11 @@
 //! ```
 
 pub mod arl;
+pub mod favorites;
 pub mod list_data;
+pub mod listen;
+pub mod playlist;
+pub mod search;
 pub mod user_data;
 pub mod user_radio;
 
@@ -48,6 +56,7 @@ pub use list_data::{
     episodes, livestream, songs, EpisodeData, ListData, LivestreamData, LivestreamUrl,
     LivestreamUrls, Queue, SongData,
 };
+pub use search::{SearchResults, SearchType};
 pub use user_data::{MediaUrl, UserData};
 pub use user_radio::UserRadio;
 
@@ -176,6 +185,29 @@ impl<T> Response<T> {
             Self::Unpaginated { results, .. } => results,
         }
     }
+
+    /// Returns the API errors of the response, keyed by error code.
+    ///
+    /// Empty when the request succeeded.
+    #[must_use]
+    #[inline]
+    pub fn errors(&self) -> &HashMap<String, serde_json::Value> {
+        match self {
+            Self::Paginated { error, .. } | Self::Unpaginated { error, .. } => error,
+        }
+    }
+
+    /// Returns the total number of items available, if paginated.
+    ///
+    /// Unpaginated responses hold all items and return `None`.
+    #[must_use]
+    #[inline]
+    pub fn total(&self) -> Option<u64> {
+        match self {
+            Self::Paginated { results, .. } => Some(results.total),
+            Self::Unpaginated { .. } => None,
+        }
+    }
 }
 
 /// Converts episode responses into list data responses.
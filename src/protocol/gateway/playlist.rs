@@ -0,0 +1,113 @@
+//! Playlist songs endpoint.
+//!
+//! This module handles fetching the songs of a playlist, for example to
+//! build a queue without a controller. Large playlists are returned in
+//! pages: request pages with increasing `start` offsets until `total`
+//! songs have been received.
+//!
+//! # Wire Format
+//!
+//! Request:
+//! ```json
+//! {
+//!     "playlist_id": "1234567890",
+//!     "start": 0,
+//!     "nb": 1000
+//! }
+//! ```
+//!
+//! Response:
+//! ```json
+//! {
+//!     "data": [
+//!         {
+//!             "SNG_ID": "123456",
+//!             "SNG_TITLE": "Song Title",
+//!             "ART_NAME": "Artist Name"
+//!         }
+//!     ],
+//!     "count": 1,
+//!     "total": 1,
+//!     "filtered_count": 0
+//! }
+//! ```
+//!
+//! Songs are in the same format as [`ListData`].
+//!
+//! # Example
+//!
+//! ```rust
+//! use deezer::gateway::{playlist, Response};
+//!
+//! let request = playlist::Request {
+//!     playlist_id: 1234567890,
+//!     start: 0,
+//!     count: 1000,
+//! };
+//!
+//! let response: Response<playlist::PlaylistSong> = /* gateway response */;
+//! ```
+
+use std::ops::Deref;
+
+use serde::{Deserialize, Serialize};
+use serde_with::{serde_as, DisplayFromStr};
+
+use super::{ListData, Method};
+
+/// Gateway method name for listing the songs of a playlist.
+impl Method for PlaylistSong {
+    const METHOD: &'static str = "playlist.getSongs";
+}
+
+/// Song in a playlist.
+///
+/// Contains the same track information as [`ListData`], so songs can be
+/// queued and played directly.
+#[derive(Clone, PartialEq, Deserialize, Debug)]
+#[serde(transparent)]
+pub struct PlaylistSong(pub ListData);
+
+/// Provides access to the underlying song data.
+impl Deref for PlaylistSong {
+    type Target = ListData;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/// Request parameters for a page of playlist songs.
+#[serde_as]
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Serialize, Debug, Hash)]
+pub struct Request {
+    /// ID of the playlist.
+    #[serde_as(as = "DisplayFromStr")]
+    pub playlist_id: u64,
+
+    /// Offset of the first song to return.
+    pub start: u64,
+
+    /// Maximum number of songs to return.
+    #[serde(rename = "nb")]
+    pub count: u64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn request_serializes_to_wire_format() {
+        let request = Request {
+            playlist_id: 1_234_567_890,
+            start: 1_000,
+            count: 1_000,
+        };
+        assert_eq!(
+            serde_json::to_string(&request).unwrap(),
+            r#"{"playlist_id":"1234567890","start":1000,"nb":1000}"#
+        );
+    }
+}
@@ -0,0 +1,293 @@
+//! Content search endpoint.
+//!
+//! This module resolves content by name, for example to find the ID of a
+//! track, album, artist or playlist from a user-provided string. Each search
+//! returns a single type of content, selected by [`SearchType`].
+//!
+//! Search results are returned in the order of relevance determined by
+//! Deezer. Ambiguous queries may return many matches and queries without any
+//! match return an empty list; it is up to the caller to pick a result.
+//!
+//! # Wire Format
+//!
+//! Request:
+//! ```json
+//! {
+//!     "query": "daft punk",
+//!     "filter": "ALL",
+//!     "output": "ALBUM",
+//!     "start": 0,
+//!     "nb": 40
+//! }
+//! ```
+//!
+//! Response:
+//! ```json
+//! {
+//!     "data": [
+//!         {
+//!             "ALB_ID": "302127",
+//!             "ALB_TITLE": "Discovery",
+//!             "ART_NAME": "Daft Punk",
+//!             "ALB_PICTURE": "2e018122cb56986277102d2041a592c8"
+//!         }
+//!     ],
+//!     "count": 1,
+//!     "total": 1,
+//!     "filtered_count": 0
+//! }
+//! ```
+//!
+//! # Example
+//!
+//! ```rust
+//! use deezer::gateway::{search, Response};
+//!
+//! let request = search::Request {
+//!     query: "daft punk".to_string(),
+//!     filter: search::FILTER_ALL,
+//!     output: search::SearchType::Album,
+//!     start: 0,
+//!     count: 40,
+//! };
+//!
+//! let response: Response<search::SearchAlbum> = /* gateway response */;
+//! ```
+
+use std::{fmt, ops::Deref, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+use serde_with::{serde_as, DisplayFromStr, PickFirst};
+
+use super::{ListData, Method, Queue};
+use crate::error::Error;
+
+/// Gateway method name for searching tracks.
+impl Method for SearchTrack {
+    const METHOD: &'static str = "search.music";
+}
+
+/// Gateway method name for searching albums.
+impl Method for SearchAlbum {
+    const METHOD: &'static str = "search.music";
+}
+
+/// Gateway method name for searching artists.
+impl Method for SearchArtist {
+    const METHOD: &'static str = "search.music";
+}
+
+/// Gateway method name for searching playlists.
+impl Method for SearchPlaylist {
+    const METHOD: &'static str = "search.music";
+}
+
+/// Search filter that matches on all fields.
+pub const FILTER_ALL: &str = "ALL";
+
+/// Type of content to search for.
+#[derive(
+    Copy, Clone, Debug, Default, Hash, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize,
+)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum SearchType {
+    /// Songs
+    #[default]
+    Track,
+
+    /// Albums
+    Album,
+
+    /// Artists
+    Artist,
+
+    /// User and editorial playlists
+    Playlist,
+}
+
+/// Formats the search type as its lowercase name.
+impl fmt::Display for SearchType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Track => write!(f, "track"),
+            Self::Album => write!(f, "album"),
+            Self::Artist => write!(f, "artist"),
+            Self::Playlist => write!(f, "playlist"),
+        }
+    }
+}
+
+/// Parses a search type from its name, case-insensitively.
+///
+/// # Errors
+///
+/// Returns error if the name is not `track`, `album`, `artist` or
+/// `playlist`.
+impl FromStr for SearchType {
+    type Err = Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "track" => Ok(Self::Track),
+            "album" => Ok(Self::Album),
+            "artist" => Ok(Self::Artist),
+            "playlist" => Ok(Self::Playlist),
+            _ => Err(Error::invalid_argument(format!(
+                "invalid search type {s}: expected track, album, artist or playlist"
+            ))),
+        }
+    }
+}
+
+/// Track matching a search.
+///
+/// Contains the same track information as [`ListData`], so matches can be
+/// queued and played directly.
+#[derive(Clone, PartialEq, Deserialize, Debug)]
+#[serde(transparent)]
+pub struct SearchTrack(pub ListData);
+
+/// Provides access to the underlying track data.
+impl Deref for SearchTrack {
+    type Target = ListData;
+
+    #[inline]
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+/// Album matching a search.
+#[serde_as]
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Deserialize, Debug, Hash)]
+pub struct SearchAlbum {
+    /// Album identifier.
+    #[serde(rename = "ALB_ID")]
+    #[serde_as(as = "PickFirst<(DisplayFromStr, _)>")]
+    pub id: u64,
+
+    /// Album title.
+    #[serde(default)]
+    #[serde(rename = "ALB_TITLE")]
+    pub title: String,
+
+    /// Name of the main artist.
+    #[serde(default)]
+    #[serde(rename = "ART_NAME")]
+    pub artist: String,
+
+    /// Album cover identifier.
+    ///
+    /// Defaults to an empty string when no cover is available.
+    #[serde(default)]
+    #[serde(rename = "ALB_PICTURE")]
+    pub cover: String,
+}
+
+/// Artist matching a search.
+#[serde_as]
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Deserialize, Debug, Hash)]
+pub struct SearchArtist {
+    /// Artist identifier.
+    #[serde(rename = "ART_ID")]
+    #[serde_as(as = "PickFirst<(DisplayFromStr, _)>")]
+    pub id: u64,
+
+    /// Artist name.
+    #[serde(default)]
+    #[serde(rename = "ART_NAME")]
+    pub name: String,
+
+    /// Artist picture identifier.
+    ///
+    /// Defaults to an empty string when no picture is available.
+    #[serde(default)]
+    #[serde(rename = "ART_PICTURE")]
+    pub picture: String,
+}
+
+/// Playlist matching a search.
+#[serde_as]
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Deserialize, Debug, Hash)]
+pub struct SearchPlaylist {
+    /// Playlist identifier.
+    #[serde(rename = "PLAYLIST_ID")]
+    #[serde_as(as = "PickFirst<(DisplayFromStr, _)>")]
+    pub id: u64,
+
+    /// Playlist title.
+    #[serde(default)]
+    #[serde(rename = "TITLE")]
+    pub title: String,
+
+    /// Name of the user that created the playlist.
+    #[serde(default)]
+    #[serde(rename = "PARENT_USERNAME")]
+    pub owner: String,
+
+    /// Number of songs in the playlist.
+    #[serde(default)]
+    #[serde(rename = "NB_SONG")]
+    #[serde_as(as = "PickFirst<(DisplayFromStr, _)>")]
+    pub song_count: u64,
+}
+
+/// Typed results of a search.
+///
+/// The variant corresponds to the [`SearchType`] that was searched for.
+/// Results are ordered by relevance and may be empty.
+#[derive(Clone, PartialEq, Debug)]
+pub enum SearchResults {
+    /// Matching tracks
+    Tracks(Queue),
+
+    /// Matching albums
+    Albums(Vec<SearchAlbum>),
+
+    /// Matching artists
+    Artists(Vec<SearchArtist>),
+
+    /// Matching playlists
+    Playlists(Vec<SearchPlaylist>),
+}
+
+impl SearchResults {
+    /// Returns the number of results.
+    #[must_use]
+    #[inline]
+    pub fn len(&self) -> usize {
+        match self {
+            Self::Tracks(tracks) => tracks.len(),
+            Self::Albums(albums) => albums.len(),
+            Self::Artists(artists) => artists.len(),
+            Self::Playlists(playlists) => playlists.len(),
+        }
+    }
+
+    /// Returns whether nothing matched the search.
+    #[must_use]
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Request parameters for a search.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Serialize, Debug, Hash)]
+pub struct Request {
+    /// Text to search for.
+    pub query: String,
+
+    /// Fields to match the query on, usually [`FILTER_ALL`].
+    pub filter: &'static str,
+
+    /// Type of content to return.
+    pub output: SearchType,
+
+    /// Offset of the first result to return.
+    pub start: u64,
+
+    /// Maximum number of results to return.
+    #[serde(rename = "nb")]
+    pub count: u64,
+}
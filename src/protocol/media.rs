@@ -313,6 +313,36 @@ impl From<Format> for AudioQuality {
     }
 }
 
+/// Converts an audio quality level to its corresponding media format.
+///
+/// Maps quality levels to format types:
+/// * Basic -> `MP3_64`
+/// * Standard -> `MP3_128`
+/// * High -> `MP3_320`
+/// * Lossless -> FLAC
+/// * Unknown -> `MP3_MISC`
+///
+/// # Examples
+///
+/// ```rust
+/// use deezer::protocol::{media::Format, connect::AudioQuality};
+///
+/// assert_eq!(Format::from(AudioQuality::High), Format::MP3_320);
+/// assert_eq!(Format::from(AudioQuality::Unknown), Format::MP3_MISC);
+/// ```
+impl From<AudioQuality> for Format {
+    #[inline]
+    fn from(quality: AudioQuality) -> Self {
+        match quality {
+            AudioQuality::Basic => Format::MP3_64,
+            AudioQuality::Standard => Format::MP3_128,
+            AudioQuality::High => Format::MP3_320,
+            AudioQuality::Lossless => Format::FLAC,
+            AudioQuality::Unknown => Format::MP3_MISC,
+        }
+    }
+}
+
 /// Media access response.
 ///
 /// Contains either:
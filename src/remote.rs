@@ -28,6 +28,18 @@
 //!
 //! No additional variables
 //!
+//! ## `buffering`
+//! Emitted when playback stalls waiting for data
+//!
+//! Variables:
+//! - `TRACK_ID`: The ID of the track being played
+//!
+//! ## `buffered`
+//! Emitted when playback no longer stalls waiting for data
+//!
+//! Variables:
+//! - `TRACK_ID`: The ID of the track being played
+//!
 //! ## `track_changed`
 //! Emitted when the track changes
 //!
@@ -49,18 +61,60 @@
 //! Additional variables for songs:
 //! - `ALBUM_TITLE`: Album name
 //!
+//! ## `skipped`
+//! Emitted when a track is skipped without playing in full
+//!
+//! Variables:
+//! - `TRACK_ID`: The ID of the track
+//! - `REASON`: Why the track was skipped ("unavailable", "too_long",
+//!   "truncated")
+//!
+//! ## `volume_changed`
+//! Emitted when the volume changes, whether set by the controller or by the
+//! initial volume
+//!
+//! Variables:
+//! - `VOLUME`: The new volume in percent, from 0 to 100
+//!
 //! ## `connected`
 //! Emitted when a controller connects
 //!
 //! Variables:
 //! - `USER_ID`: The Deezer user ID
 //! - `USER_NAME`: The Deezer username
+//! - `CONTROLLER_ID`: The device ID of the controller
+//! - `CONTROLLER_NAME`: The device name of the controller, if known
+//! - `CONTROLLER_TYPE`: The device type of the controller, if known
 //!
 //! ## `disconnected`
 //! Emitted when the controller disconnects
 //!
 //! No additional variables
 //!
+//! ## `favorite`
+//! Emitted when a song is added to the favorites
+//!
+//! Variables:
+//! - `TRACK_ID`: The ID of the song
+//!
+//! ## `unfavorite`
+//! Emitted when a song is removed from the favorites
+//!
+//! Variables:
+//! - `TRACK_ID`: The ID of the song
+//!
+//! ## `observed`
+//! Emitted when eavesdropping with observed events enabled, for every
+//! playback progress report of another device
+//!
+//! Variables:
+//! - `DEVICE_ID`: The ID of the reporting device
+//! - `TRACK_ID`: The ID of the track being played
+//! - `PLAYING`: Whether playback is active ("true" or "false")
+//! - `VOLUME`: The volume in percent, from 0 to 100
+//! - `PROGRESS`: The position in the track in percent, if known
+//! - `DURATION`: The track duration in seconds, if known
+//!
 //! # Protocol Details
 //!
 //! ## Connection Flow
@@ -90,7 +144,8 @@
 //! * Available - Ready for discovery
 //! * Connecting - Accepting controller
 //! * Connected - Active control session
-//! * Taken - Connection locked (if interruptions disabled)
+//! * Taken - Connection locked (if interruptions disabled), optionally hidden
+//!   from discovery
 //!
 //! ## Message Types
 //!
@@ -113,10 +168,11 @@
 //! ```
 
 use std::{
-    collections::{HashMap, HashSet},
+    collections::{HashMap, HashSet, VecDeque},
+    net::IpAddr,
     ops::ControlFlow,
+    path::PathBuf,
     pin::Pin,
-    process::Command,
     time::Duration,
 };
 
@@ -124,31 +180,37 @@ use futures_util::{stream::SplitSink, SinkExt, StreamExt};
 use log::Level;
 use semver;
 use time::OffsetDateTime;
+use tokio::process::Command;
 use tokio_tungstenite::{
     tungstenite::{
-        client::ClientRequestBuilder,
-        protocol::{frame::Frame, WebSocketConfig},
-        Message as WebsocketMessage,
+        client::ClientRequestBuilder, protocol::WebSocketConfig, Message as WebsocketMessage,
     },
-    MaybeTlsStream, WebSocketStream,
+    Connector, MaybeTlsStream, WebSocketStream,
 };
 use uuid::Uuid;
 
+#[cfg(feature = "notify")]
+use crate::notify;
 use crate::{
-    config::{Config, Credentials},
-    error::{Error, Result},
-    events::Event,
+    config::{Config, Credentials, HookMode, InitialVolumeMode, StopAfter, UuidFormat},
+    dns::{self, Resolver},
+    error::{Error, ErrorKind, Result},
+    events::{Event, ObservedPlayback},
+    export,
     gateway::Gateway,
+    now_playing,
     player::Player,
     protocol::connect::{
         queue::{self, MixType},
-        stream, Body, Channel, Contents, DeviceId, DeviceType, Headers, Ident, Message, Percentage,
-        QueueItem, RepeatMode, Status, UserId,
+        stream, Body, Channel, Contents, DeviceId, DeviceType, Headers, Ident, Message,
+        MessageType, Percentage, QueueItem, RepeatMode, Status, UserId,
     },
     proxy,
+    signal::ExportHandler,
+    tls,
     tokens::UserToken,
-    track::{Track, TrackId, DEFAULT_BITS_PER_SAMPLE, DEFAULT_SAMPLE_RATE},
-    util::ToF32,
+    track::{Track, TrackId, TrackType, DEFAULT_BITS_PER_SAMPLE, DEFAULT_SAMPLE_RATE},
+    util::{ToF32, UNITY_GAIN},
 };
 
 /// A client on the Deezer Connect protocol.
@@ -184,8 +246,8 @@ pub struct Client {
     websocket_tx:
         Option<SplitSink<WebSocketStream<MaybeTlsStream<tokio::net::TcpStream>>, WebsocketMessage>>,
 
-    /// Active channel subscriptions
-    subscriptions: HashSet<Ident>,
+    /// Channel subscriptions
+    subscriptions: Subscriptions,
 
     /// Current connection state
     connection_state: ConnectionState,
@@ -199,12 +261,21 @@ pub struct Client {
     /// Current discovery state
     discovery_state: DiscoveryState,
 
-    /// Cache of discovery session IDs to prevent duplicate offers within a single connection
+    /// Cache of discovery sessions to prevent duplicate offers within a single connection
     ///
-    /// Maps controller device IDs to their current discovery session ID. Cleared when client
-    /// starts/restarts to prevent memory exhaustion across reconnections. This caches by
+    /// Maps controller device IDs to their current discovery session. Cleared when client
+    /// starts/restarts to prevent memory exhaustion across reconnections, except for sessions
+    /// offered within the discovery grace period. This caches by
     /// device rather than session since the same controllers typically reconnect multiple times.
-    discovery_sessions: HashMap<DeviceId, String>,
+    /// Bounded to [`Self::DISCOVERY_SESSIONS_MAX`] controllers, evicting the least recently
+    /// offered.
+    discovery_sessions: HashMap<DeviceId, DiscoverySession>,
+
+    /// IDs of the most recently received messages
+    ///
+    /// Used to drop messages that are delivered more than once. Bounded to
+    /// [`Self::RECENT_MESSAGES_MAX`] entries, oldest first.
+    recent_messages: VecDeque<String>,
 
     /// Channel for receiving player and control events
     event_rx: tokio::sync::mpsc::UnboundedReceiver<Event>,
@@ -219,15 +290,124 @@ pub struct Client {
     /// Whether to allow connection interruptions
     interruptions: bool,
 
+    /// Time after connecting during which takeovers are refused, if any
+    takeover_cooldown: Option<Duration>,
+
+    /// When the current controller connected, if ever
+    connected_at: Option<tokio::time::Instant>,
+
+    /// Whether to ignore discovery requests while taken
+    hide_when_taken: bool,
+
+    /// Whether to connect without the stream channel if subscribing fails
+    allow_degraded: bool,
+
+    /// Interval at which to re-announce availability, if any
+    announce_interval: Option<Duration>,
+
+    /// How long to remember discovery sessions across reconnections
+    discovery_grace: Duration,
+
+    /// Maximum time to keep a connection before reconnecting
+    max_session_duration: Option<Duration>,
+
+    /// Timer for re-announcing availability
+    announce_timer: Pin<Box<tokio::time::Sleep>>,
+
+    /// Minimum time between extensions of the Flow queue
+    flow_extend_interval: Duration,
+
+    /// Number of Flow tracks to keep resolved ahead
+    flow_lookahead: usize,
+
+    /// Maximum websocket frame size (payload) in bytes
+    websocket_frame_size_max: usize,
+
+    /// Maximum websocket message size (payload plus headers) in bytes
+    websocket_message_size_max: usize,
+
+    /// Maximum websocket write buffer size in bytes
+    websocket_buffer_max: usize,
+
+    /// When the Flow queue was last extended, if ever
+    flow_extended_at: Option<tokio::time::Instant>,
+
+    /// Whether a throttled Flow extension is waiting for its timer
+    flow_extend_pending: bool,
+
+    /// Flow recommendations fetched beyond the lookahead, for later extensions
+    flow_surplus: Vec<Track>,
+
+    /// Timer for a throttled Flow extension
+    flow_extend_timer: Pin<Box<tokio::time::Sleep>>,
+
+    /// Limit after which to stop playback and disconnect, if any
+    stop_after: Option<StopAfter>,
+
+    /// Whether to exit once the stop limit is reached
+    exit_after_stop: bool,
+
+    /// Duration to fade out when the stop limit is reached
+    stop_fade: Option<Duration>,
+
+    /// Number of tracks started since the controller connected
+    tracks_started: u32,
+
+    /// Timer for a stop limit by time
+    stop_timer: Pin<Box<tokio::time::Sleep>>,
+
+    /// Whether the stop timer is running
+    stop_timer_armed: bool,
+
+    /// Whether the stop limit is reached and playback should stop
+    stop_requested: bool,
+
+    /// Whether playback stopped at the limit and the client should exit
+    stopped: bool,
+
     /// Optional hook script for events
     hook: Option<String>,
 
+    /// Whether to show desktop notifications on track changes
+    #[cfg(feature = "notify")]
+    notify: bool,
+
+    /// Optional script deciding whether to accept connections
+    connect_policy: Option<String>,
+
+    /// Whether to wait for the hook script to finish
+    hook_mode: HookMode,
+
+    /// When the initial volume yields to the volume of the controller
+    initial_volume_mode: InitialVolumeMode,
+
+    /// Maximum time to wait for the hook script in blocking mode
+    hook_timeout: Duration,
+
+    /// File to write the current track and queue to, if any
+    now_playing_file: Option<PathBuf>,
+
+    /// Format of the now-playing file
+    now_playing_format: now_playing::Format,
+
+    /// Optional file to export the queue to on request
+    export_file: Option<PathBuf>,
+
+    /// Format of the queue export
+    export_format: now_playing::Format,
+
+    /// Handler of queue export requests, if exports are enabled
+    export_requests: Option<ExportHandler>,
+
     /// Audio playback manager
     player: Player,
 
     /// Timer for playback progress reports
     reporting_timer: Pin<Box<tokio::time::Sleep>>,
 
+    /// Number of playback progress reports that failed in a row
+    reporting_failures: u32,
+
     /// Current playback queue
     ///
     /// Maintains both track list and shuffle state.
@@ -240,6 +420,74 @@ pub struct Client {
 
     /// Whether to monitor all websocket traffic
     eavesdrop: bool,
+
+    /// Whether to emit events for playback observed while eavesdropping
+    eavesdrop_events: bool,
+
+    /// Whether to log listens to the user's listening history
+    listening_history: bool,
+
+    /// Whether to report playback progress to the controller
+    progress_reports: bool,
+
+    /// Whether the listen of the current track was logged
+    listen_logged: bool,
+
+    /// Message types to drop before handling, for protocol research
+    ignore_message_types: HashSet<MessageType>,
+
+    /// Whether to print events and their hook variables to stdout
+    print_events: bool,
+
+    /// Local address to connect the websocket from
+    bind_address: IpAddr,
+
+    /// Custom resolver for the websocket host, if any
+    resolver: Option<Resolver>,
+
+    /// TLS connector trusting custom certificate authorities, if any
+    tls_connector: Option<Connector>,
+
+    /// Playback state to resume when a controller connects
+    resume: Option<Resume>,
+}
+
+/// Playback state handed across a configuration reload.
+///
+/// Captured from a client before it stops, and resumed by its successor
+/// when a controller connects, so that playback continues near where it
+/// was instead of starting over.
+///
+/// Tracks are resolved again when resuming, so that their tokens are fresh.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Resume {
+    /// Queue as last published by the controller
+    queue: queue::List,
+
+    /// Position in the queue, in playback order
+    position: usize,
+
+    /// Progress within the current track
+    progress: Option<Percentage>,
+
+    /// Whether playback was active
+    playing: bool,
+}
+
+/// Discovery session of a controller.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+struct DiscoverySession {
+    /// Discovery session ID
+    id: String,
+
+    /// When the last connection offer was sent
+    offered_at: tokio::time::Instant,
+
+    /// Device name of the controller, if it offered connections itself
+    device_name: Option<String>,
+
+    /// Device type of the controller, if it offered connections itself
+    device_type: Option<DeviceType>,
 }
 
 /// Device discovery state.
@@ -300,6 +548,9 @@ enum ShuffleAction {
 /// The state transitions from Active to Inactive when the client takes control by setting
 /// a volume below maximum. When a connection ends, it transitions back to Active to ensure
 /// the initial volume is reapplied on reconnection.
+///
+/// With [`InitialVolumeMode::Once`], the state transitions to Inactive as soon as the
+/// initial volume is applied on starting playback, and stays Inactive.
 #[derive(Copy, Clone, Debug, PartialEq)]
 enum InitialVolume {
     /// Initial volume is active and will be applied on connection/reconnection
@@ -310,6 +561,70 @@ enum InitialVolume {
     Disabled,
 }
 
+/// Channel subscriptions of the websocket connection.
+///
+/// Separates the channels that should be subscribed to from those that
+/// subscriptions were sent for on the current websocket:
+/// * Pending - Wanted, but not subscribed on the current websocket, because
+///   sending failed or the websocket was replaced
+/// * Active - Subscribed on the current websocket
+///
+/// This makes subscribing and unsubscribing idempotent, and allows all
+/// wanted channels to be subscribed to again on a new websocket.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+struct Subscriptions {
+    /// Channels that should be subscribed to
+    wanted: HashSet<Ident>,
+
+    /// Channels subscribed to on the current websocket
+    active: HashSet<Ident>,
+}
+
+impl Subscriptions {
+    /// Marks a channel as wanted.
+    ///
+    /// Returns whether a subscription still needs to be sent.
+    fn want(&mut self, ident: Ident) -> bool {
+        self.wanted.insert(ident);
+        !self.active.contains(&ident)
+    }
+
+    /// Marks a channel as no longer wanted.
+    ///
+    /// Returns whether an unsubscription still needs to be sent.
+    fn unwant(&mut self, ident: Ident) -> bool {
+        self.wanted.remove(&ident);
+        self.active.contains(&ident)
+    }
+
+    /// Records that a subscription was sent on the current websocket.
+    fn subscribed(&mut self, ident: Ident) {
+        self.active.insert(ident);
+    }
+
+    /// Records that an unsubscription was sent on the current websocket.
+    fn unsubscribed(&mut self, ident: Ident) {
+        self.active.remove(&ident);
+    }
+
+    /// Forgets the subscriptions of a websocket that was replaced.
+    ///
+    /// Wanted channels become pending, to be subscribed to again.
+    fn reset(&mut self) {
+        self.active.clear();
+    }
+
+    /// Returns the channels that are wanted but not subscribed to.
+    fn pending(&self) -> Vec<Ident> {
+        self.wanted.difference(&self.active).copied().collect()
+    }
+
+    /// Returns the channels subscribed to on the current websocket.
+    fn active(&self) -> Vec<Ident> {
+        self.active.iter().copied().collect()
+    }
+}
+
 /// Calculates a future time instant by adding seconds to now.
 ///
 /// Used for scheduling timers and watchdogs. Handles overflow
@@ -345,27 +660,51 @@ impl Client {
     /// Buffer before token refresh to prevent expiration during requests.
     const TOKEN_EXPIRATION_THRESHOLD: Duration = Duration::from_secs(60);
 
+    /// Lookahead for refreshing track tokens of queued songs.
+    ///
+    /// Songs whose tokens expire within this time are refreshed in batch
+    /// when the track changes.
+    const TRACK_TOKEN_REFRESH_THRESHOLD: Duration = Duration::from_secs(15 * 60);
+
+    /// Minimum time between connection offers to the same controller.
+    ///
+    /// Throttles controllers that churn discovery session IDs.
+    const DISCOVERY_OFFER_INTERVAL: Duration = Duration::from_secs(5);
+
+    /// Maximum number of controllers to cache discovery sessions for.
+    const DISCOVERY_SESSIONS_MAX: usize = 32;
+
+    /// Number of recently received message IDs to remember for duplicate detection.
+    const RECENT_MESSAGES_MAX: usize = 64;
+
+    /// Playback time after which a track is logged to the listening history.
+    const LISTEN_THRESHOLD: Duration = Duration::from_secs(30);
+
     /// How often to report playback progress to controller.
     const REPORTING_INTERVAL: Duration = Duration::from_secs(3);
 
+    /// Number of playback progress reports to fail in a row before the
+    /// connection is considered dead.
+    const REPORTING_FAILURES_MAX: u32 = 5;
+
+    /// Number of attempts to subscribe to a channel when connecting.
+    const SUBSCRIBE_ATTEMPTS: u32 = 3;
+
+    /// Time to wait before retrying to subscribe to a channel, doubling on
+    /// every retry.
+    const SUBSCRIBE_RETRY_DELAY: Duration = Duration::from_millis(250);
+
+    /// Maximum time to wait for the connection policy script.
+    ///
+    /// Controllers expect a quick response to their connection attempt.
+    const CONNECT_POLICY_TIMEOUT: Duration = Duration::from_secs(2);
+
     /// Maximum time to wait for controller heartbeat.
     const WATCHDOG_RX_TIMEOUT: Duration = Duration::from_secs(10);
 
     /// Maximum time between sending heartbeats.
     const WATCHDOG_TX_TIMEOUT: Duration = Duration::from_secs(5);
 
-    /// Maximum allowed websocket frame size (payload) in bytes.
-    /// Set to 32KB (message size / 4) to balance between chunking and overhead.
-    const FRAME_SIZE_MAX: usize = Self::MESSAGE_SIZE_MAX / 4;
-
-    /// Maximum allowed websocket message size (payload plus headers) in bytes.
-    /// Set to 128KB (message buffer / 2) to provide backpressure and prevent OOM.
-    const MESSAGE_SIZE_MAX: usize = Self::MESSAGE_BUFFER_MAX / 2;
-
-    /// Maximum size of the websocket write buffer in bytes.
-    /// Set to 256KB to provide adequate buffering while preventing memory exhaustion.
-    const MESSAGE_BUFFER_MAX: usize = 2 * 128 * 1024;
-
     /// Default session TTL (4 hours)
     const SESSION_DEFAULT_TTL: Duration = Duration::from_secs(4 * 3600);
 
@@ -393,6 +732,7 @@ impl Client {
     /// Returns error if:
     /// * Application version in config is not valid `SemVer`
     /// * Gateway client creation fails
+    /// * Resolver creation fails
     pub fn new(config: &Config, player: Player) -> Result<Self> {
         // Construct version in the form of `Mmmppp` where:
         // - `M` is the major version
@@ -419,6 +759,9 @@ impl Client {
         let reporting_timer = tokio::time::sleep(Duration::ZERO);
         let watchdog_rx = tokio::time::sleep(Duration::ZERO);
         let watchdog_tx = tokio::time::sleep(Duration::ZERO);
+        let announce_timer = tokio::time::sleep(Duration::ZERO);
+        let flow_extend_timer = tokio::time::sleep(Duration::ZERO);
+        let stop_timer = tokio::time::sleep(Duration::ZERO);
 
         let (time_to_live_tx, time_to_live_rx) = tokio::sync::mpsc::channel(1);
         let (event_tx, event_rx) = tokio::sync::mpsc::unbounded_channel::<Event>();
@@ -431,8 +774,29 @@ impl Client {
             None => InitialVolume::Disabled,
         };
 
+        let resolver = config
+            .resolver
+            .clone()
+            .map(|nameserver| Resolver::new(nameserver, config.bind_address))
+            .transpose()?;
+
+        let export_requests = config
+            .export_file
+            .as_ref()
+            .map(|_| ExportHandler::new())
+            .transpose()?;
+
+        let tls_connector = config
+            .ca_cert
+            .as_ref()
+            .map(|ca_cert| tls::websocket_connector(&tls::load_certificates(ca_cert)?))
+            .transpose()?;
+
         Ok(Self {
-            device_id: config.device_id.into(),
+            device_id: match config.device_id_format {
+                UuidFormat::Hyphenated => config.device_id.into(),
+                format => DeviceId::Other(format.format(&config.device_id)),
+            },
             device_name: config.device_name.clone(),
             device_type: config.device_type,
 
@@ -446,7 +810,7 @@ impl Client {
             version,
             websocket_tx: None,
 
-            subscriptions: HashSet::new(),
+            subscriptions: Subscriptions::default(),
 
             connection_state: ConnectionState::Disconnected,
             watchdog_rx: Box::pin(watchdog_rx),
@@ -457,18 +821,66 @@ impl Client {
 
             player,
             reporting_timer: Box::pin(reporting_timer),
+            reporting_failures: 0,
 
             discovery_state: DiscoveryState::Available,
             discovery_sessions: HashMap::new(),
+            recent_messages: VecDeque::with_capacity(Self::RECENT_MESSAGES_MAX),
 
             initial_volume,
             interruptions: config.interruptions,
+            takeover_cooldown: config.takeover_cooldown,
+            connected_at: None,
+            hide_when_taken: config.hide_when_taken,
+            allow_degraded: config.allow_degraded,
+            announce_interval: config.announce_interval,
+            discovery_grace: config.discovery_grace,
+            max_session_duration: config.max_session_duration,
+            announce_timer: Box::pin(announce_timer),
+            flow_extend_interval: config.flow_extend_interval,
+            flow_lookahead: config.flow_lookahead,
+            websocket_frame_size_max: config.websocket_frame_size_max,
+            websocket_message_size_max: config.websocket_message_size_max,
+            websocket_buffer_max: config.websocket_buffer_max,
+            flow_extended_at: None,
+            flow_extend_pending: false,
+            flow_surplus: Vec::new(),
+            flow_extend_timer: Box::pin(flow_extend_timer),
+            stop_after: config.stop_after,
+            exit_after_stop: config.exit_after_stop,
+            stop_fade: config.exit_fade,
+            tracks_started: 0,
+            stop_timer: Box::pin(stop_timer),
+            stop_timer_armed: false,
+            stop_requested: false,
+            stopped: false,
             hook: config.hook.clone(),
+            #[cfg(feature = "notify")]
+            notify: config.notify,
+            connect_policy: config.connect_policy.clone(),
+            hook_mode: config.hook_mode,
+            initial_volume_mode: config.initial_volume_mode,
+            hook_timeout: config.hook_timeout,
+            now_playing_file: config.now_playing_file.clone(),
+            now_playing_format: config.now_playing_format,
+            export_file: config.export_file.clone(),
+            export_format: config.export_format,
+            export_requests,
 
             queue: None,
             deferred_position: None,
 
             eavesdrop: config.eavesdrop,
+            eavesdrop_events: config.eavesdrop_events,
+            listening_history: config.listening_history,
+            progress_reports: config.progress_reports,
+            listen_logged: false,
+            ignore_message_types: config.ignore_message_types.iter().copied().collect(),
+            print_events: config.print_events,
+            bind_address: config.bind_address,
+            resolver,
+            tls_connector,
+            resume: None,
         })
     }
 
@@ -530,6 +942,12 @@ impl Client {
     fn set_player_settings(&mut self) {
         let audio_quality = self.gateway.audio_quality();
         info!("user casting quality: {audio_quality}");
+        if self.player.audio_quality() > audio_quality {
+            info!(
+                "lowering initial quality {} to user casting quality",
+                self.player.audio_quality()
+            );
+        }
         self.player.set_audio_quality(audio_quality);
 
         let gain_target_db = self.gateway.target_gain();
@@ -659,8 +1077,13 @@ impl Client {
     /// * Token renewal fails
     #[allow(clippy::too_many_lines)]
     pub async fn start(&mut self) -> Result<()> {
-        // Purge discovery sessions from any previous session to prevent memory exhaustion.
-        self.discovery_sessions = HashMap::new();
+        // Purge discovery sessions from any previous session to prevent memory exhaustion,
+        // except recent ones to prevent duplicate offers after a quick reconnection.
+        let grace = self.discovery_grace;
+        self.discovery_sessions
+            .retain(|_, session| session.offered_at.elapsed() < grace);
+        self.recent_messages.clear();
+        self.reporting_failures = 0;
 
         let arl = match self.credentials.clone() {
             Credentials::Login { email, password } => {
@@ -724,32 +1147,68 @@ impl Client {
         let jwt_expiry = tokio::time::sleep(jwt_ttl);
         tokio::pin!(jwt_expiry);
 
+        // The connection itself, if limited
+        let session_limit = self.max_session_duration;
+        let session_end = tokio::time::sleep(session_limit.unwrap_or(Duration::MAX));
+        tokio::pin!(session_end);
+
         let config = Some(
             WebSocketConfig::default()
-                .max_write_buffer_size(Self::MESSAGE_BUFFER_MAX)
-                .max_message_size(Some(Self::MESSAGE_SIZE_MAX))
-                .max_frame_size(Some(Self::FRAME_SIZE_MAX)),
+                // The write buffer must stay below its maximum, which is at
+                // least twice the message size.
+                .write_buffer_size(self.websocket_message_size_max)
+                .max_write_buffer_size(self.websocket_buffer_max)
+                .max_message_size(Some(self.websocket_message_size_max))
+                .max_frame_size(Some(self.websocket_frame_size_max)),
         );
 
-        let (ws_stream, _) = if let Some(proxy) = proxy::Http::from_env() {
+        // Connect from the bind address, like the HTTP clients do.
+        let tcp_stream = if let Some(proxy) = proxy::Http::from_env() {
             info!("using proxy: {proxy}");
-            let tcp_stream = proxy.connect_async(&uri).await?;
-            tokio_tungstenite::client_async_tls_with_config(request, tcp_stream, config, None)
-                .await?
+            proxy.connect_async(&uri, self.bind_address).await?
+        } else if let Some(resolver) = &self.resolver {
+            resolver.connect_async(&uri).await?
         } else {
-            tokio_tungstenite::connect_async_with_config(request, config, false).await?
+            dns::connect_async(&uri, self.bind_address).await?
         };
 
+        let (ws_stream, _) = tokio_tungstenite::client_async_tls_with_config(
+            request,
+            tcp_stream,
+            config,
+            self.tls_connector.clone(),
+        )
+        .await?;
+
         let (websocket_tx, mut websocket_rx) = ws_stream.split();
         self.websocket_tx = Some(websocket_tx);
 
-        self.subscribe(Ident::Stream).await?;
-        self.subscribe(Ident::RemoteDiscover).await?;
+        // Subscriptions of any previous websocket do not carry over.
+        self.subscriptions.reset();
+        if let Err(e) = self.subscribe_with_retry(Ident::Stream).await {
+            Self::degrade(&mut self.subscriptions, e, self.allow_degraded)?;
+        }
+        self.subscribe_with_retry(Ident::RemoteDiscover).await?;
+        self.resubscribe().await?;
 
         if self.eavesdrop {
             warn!("not discoverable: eavesdropping on websocket");
         } else {
             info!("ready for discovery");
+            self.reset_announce_timer();
+        }
+
+        if !self.ignore_message_types.is_empty() {
+            let mut message_types: Vec<_> = self
+                .ignore_message_types
+                .iter()
+                .map(ToString::to_string)
+                .collect();
+            message_types.sort();
+            warn!(
+                "ignoring {} messages: normal operation will break",
+                message_types.join(", ")
+            );
         }
 
         let loop_result = loop {
@@ -780,6 +1239,10 @@ impl Client {
                                     debug!("session renewed");
                                     session_ttl = self.session_ttl();
                                 }
+                                // A rejected session will not renew: log in again.
+                                Err(e) if matches!(e.kind, ErrorKind::PermissionDenied | ErrorKind::Unauthenticated) => {
+                                    break Err(Error::unauthenticated(format!("session revoked: {e}")));
+                                }
                                 Err(e) => {
                                     error!("session renewal failed: {e}");
                                 }
@@ -817,15 +1280,71 @@ impl Client {
                     }
                 }
 
+                // Wait for playback to stop, so that it is not interrupted.
+                () = &mut session_end, if session_limit.is_some() && !self.player.is_playing() => {
+                    break Err(Error::deadline_exceeded("maximum session duration reached"));
+                }
+
                 Some(token_ttl) = self.time_to_live_rx.recv() => {
                     if let Some(deadline) = tokio::time::Instant::now().checked_add(token_ttl) {
                         token_expiry.as_mut().reset(deadline);
                     }
                 }
 
+                () = &mut self.announce_timer, if self.is_announcing() => {
+                    if let Err(e) = self.announce().await {
+                        error!("error announcing availability: {e}");
+                    }
+                    self.reset_announce_timer();
+                }
+
+                () = Self::export_requested(&mut self.export_requests), if self.export_requests.is_some() => {
+                    self.export_queue();
+                }
+
+                () = &mut self.stop_timer, if self.stop_timer_armed => {
+                    self.stop_timer_armed = false;
+                    self.stop_requested = true;
+                }
+
+                () = &mut self.flow_extend_timer, if self.flow_extend_pending => {
+                    self.flow_extend_pending = false;
+                    // The queue may have been replaced or skipped back meanwhile.
+                    if self.is_flow() && self.is_near_end() {
+                        self.request_extend_queue().await;
+                    }
+                }
+
                 () = &mut self.reporting_timer, if self.is_connected() && self.player.is_playing() => {
-                    if let Err(e) = self.report_playback_progress().await {
-                        error!("error reporting playback progress: {e}");
+                    // Reset the timer regardless of success or failure, to prevent getting
+                    // stuck in a reporting state.
+                    self.reset_reporting_timer();
+
+                    // Only failures to send count towards reconnecting: local errors,
+                    // like a missing queue, would not be solved by it.
+                    match self.playback_progress() {
+                        Ok(Some(command)) => match self.send_message(command).await {
+                            Ok(()) => self.reporting_failures = 0,
+                            Err(e) => {
+                                error!("error reporting playback progress: {e}");
+
+                                // The controller no longer receives updates:
+                                // reconnect instead of failing forever.
+                                self.reporting_failures = self.reporting_failures.saturating_add(1);
+                                if self.reporting_failures >= Self::REPORTING_FAILURES_MAX {
+                                    break Err(Error::unavailable(format!(
+                                        "playback progress reports failed {} times in a row",
+                                        self.reporting_failures
+                                    )));
+                                }
+                            }
+                        },
+                        Ok(None) => (),
+                        Err(e) => error!("error reporting playback progress: {e}"),
+                    }
+
+                    if let Err(e) = self.log_listen().await {
+                        error!("error logging listen: {e}");
                     }
                 }
 
@@ -835,7 +1354,7 @@ impl Client {
                             // Do not parse exceedingly large messages to
                             // prevent out of memory conditions.
                             let message_size = message.len();
-                            if message_size > Self::MESSAGE_SIZE_MAX {
+                            if message_size > self.websocket_message_size_max {
                                 error!("ignoring oversized message with {message_size} bytes");
                                 continue;
                             }
@@ -859,6 +1378,15 @@ impl Client {
                     self.handle_event(event).await;
                 }
             }
+
+            if self.stop_requested {
+                self.stop_requested = false;
+                self.stop_at_limit().await;
+                if self.exit_after_stop {
+                    self.stopped = true;
+                    break Ok(());
+                }
+            }
         };
 
         self.stop().await;
@@ -874,43 +1402,51 @@ impl Client {
     /// * Connected - Controller connected
     /// * Disconnected - Controller disconnected
     ///
-    /// Executes hook script if configured.
+    /// Executes hook script if configured, and prints the event with its
+    /// hook variables if enabled.
     ///
     /// # Arguments
     ///
     /// * `event` - Event to process
     #[allow(clippy::too_many_lines)]
     async fn handle_event(&mut self, event: Event) {
-        let mut command = self.hook.as_ref().map(Command::new);
+        // Without a hook script, a command that is never spawned still
+        // collects the variables to print.
+        let mut command = match &self.hook {
+            Some(hook) => Some(Command::new(hook)),
+            None if self.print_events => Some(Command::new("")),
+            None => None,
+        };
         let track_id = self.player.track().map(Track::id);
 
         debug!("handling event: {event:?}");
 
         match event {
             Event::Play => {
+                if let Some(StopAfter::Time(duration)) = self.stop_after {
+                    if !self.stop_timer_armed {
+                        if let Some(deadline) = from_now(duration) {
+                            debug!("stopping playback in {}s", duration.as_secs());
+                            self.stop_timer.as_mut().reset(deadline);
+                            self.stop_timer_armed = true;
+                        }
+                    }
+                }
+
                 if let Some(track_id) = track_id {
                     // Report playback progress without waiting for the next
                     // reporting interval, so the UI refreshes immediately.
-                    let _ = self.report_playback_progress().await;
+                    if let Err(e) = self.report_playback_progress().await {
+                        error!("error reporting playback progress: {e}");
+                    }
 
                     // Report the playback stream.
                     if let Err(e) = self.report_playback(track_id).await {
                         error!("error streaming {track_id}: {e}");
                     }
 
-                    if self.is_flow() {
-                        // Extend the queue if the player is near the end.
-                        if self
-                            .queue
-                            .as_ref()
-                            .map_or(0, |queue| queue.tracks.len())
-                            .saturating_sub(self.player.position())
-                            <= 2
-                        {
-                            if let Err(e) = self.extend_queue().await {
-                                error!("error extending queue: {e}");
-                            }
-                        }
+                    if self.is_flow() && self.is_near_end() {
+                        self.request_extend_queue().await;
                     }
 
                     if let Some(command) = command.as_mut() {
@@ -927,72 +1463,135 @@ impl Client {
                 }
             }
 
-            Event::TrackChanged => {
-                if let Some(track) = self.player.track() {
-                    if let Some(command) = command.as_mut() {
-                        let codec = track.codec().map_or("Unknown".to_string(), |codec| {
-                            codec.to_string().to_uppercase()
-                        });
-
-                        let bitrate = track.bitrate();
-                        let bitrate = match bitrate {
-                            Some(bitrate) => {
-                                if bitrate >= 1000 {
-                                    format!(" {}M", bitrate.to_f32_lossy() / 1000.)
-                                } else {
-                                    format!(" {bitrate}K")
-                                }
-                            }
-                            // If bitrate is unknown, show codec only.
-                            None => String::default(),
-                        };
-
-                        let channels =
-                            match track.channels.unwrap_or(track.typ().default_channels()) {
-                                1 => "Mono".to_string(),
-                                2 => "Stereo".to_string(),
-                                3 => "2.1 Stereo".to_string(),
-                                6 => "5.1 Surround Sound".to_string(),
-                                other => format!("{other} channels"),
-                            };
-                        let decoded = format!(
-                            "PCM {} bit {} kHz, {channels}",
-                            track.bits_per_sample.unwrap_or(DEFAULT_BITS_PER_SAMPLE),
-                            track
-                                .sample_rate
-                                .unwrap_or(DEFAULT_SAMPLE_RATE)
-                                .to_f32_lossy()
-                                / 1000.0,
-                        );
+            Event::Buffering | Event::Buffered => {
+                // Report the buffered duration without waiting for the next
+                // reporting interval, so the UI shows the stall immediately.
+                if let Err(e) = self.report_playback_progress().await {
+                    error!("error reporting playback progress: {e}");
+                }
 
-                        command
-                            .env("EVENT", "track_changed")
-                            .env("TRACK_TYPE", track.typ().to_string())
-                            .env("TRACK_ID", track.id().to_string())
-                            .env("ARTIST", track.artist())
-                            .env("COVER_ID", track.cover_id())
-                            .env("FORMAT", format!("{codec}{bitrate}"))
-                            .env("DECODER", decoded);
-
-                        if let Some(title) = track.title() {
-                            command.env("TITLE", title);
-                        }
-                        if let Some(album_title) = track.album_title() {
-                            command.env("ALBUM_TITLE", album_title);
+                if let Some(command) = command.as_mut() {
+                    command.env(
+                        "EVENT",
+                        if event == Event::Buffering {
+                            "buffering"
+                        } else {
+                            "buffered"
+                        },
+                    );
+                    if let Some(track_id) = track_id {
+                        command.env("TRACK_ID", track_id.to_string());
+                    }
+                }
+            }
+
+            Event::TrackChanged(track) => {
+                self.listen_logged = false;
+
+                if track.typ != TrackType::Livestream {
+                    self.tracks_started = self.tracks_started.saturating_add(1);
+                    if let Some(StopAfter::Tracks(tracks)) = self.stop_after {
+                        if self.tracks_started > tracks {
+                            self.stop_requested = true;
                         }
-                        if let Some(duration) = track.duration() {
-                            command.env("DURATION", duration.as_secs().to_string());
+                    }
+                }
+                self.write_now_playing();
+
+                #[cfg(feature = "notify")]
+                if self.notify {
+                    notify::track_changed(&track);
+                }
+
+                if let Err(e) = self.refresh_track_tokens().await {
+                    error!("error refreshing track tokens: {e}");
+                }
+
+                if let Some(command) = command.as_mut() {
+                    let codec = track.codec.map_or("Unknown".to_string(), |codec| {
+                        codec.to_string().to_uppercase()
+                    });
+
+                    let bitrate = match track.bitrate {
+                        Some(bitrate) => {
+                            if bitrate >= 1000 {
+                                format!(" {}M", bitrate.to_f32_lossy() / 1000.)
+                            } else {
+                                format!(" {bitrate}K")
+                            }
                         }
+                        // If bitrate is unknown, show codec only.
+                        None => String::default(),
+                    };
+
+                    let channels = match track.channels.unwrap_or(track.typ.default_channels()) {
+                        1 => "Mono".to_string(),
+                        2 => "Stereo".to_string(),
+                        3 => "2.1 Stereo".to_string(),
+                        6 => "5.1 Surround Sound".to_string(),
+                        other => format!("{other} channels"),
+                    };
+                    let decoded = format!(
+                        "PCM {} bit {} kHz, {channels}",
+                        track.bits_per_sample.unwrap_or(DEFAULT_BITS_PER_SAMPLE),
+                        track
+                            .sample_rate
+                            .unwrap_or(DEFAULT_SAMPLE_RATE)
+                            .to_f32_lossy()
+                            / 1000.0,
+                    );
+
+                    command
+                        .env("EVENT", "track_changed")
+                        .env("TRACK_TYPE", track.typ.to_string())
+                        .env("TRACK_ID", track.id.to_string())
+                        .env("ARTIST", &track.artist)
+                        .env("COVER_ID", &track.cover_id)
+                        .env("FORMAT", format!("{codec}{bitrate}"))
+                        .env("DECODER", decoded);
+
+                    if let Some(title) = &track.title {
+                        command.env("TITLE", title);
+                    }
+                    if let Some(album_title) = &track.album_title {
+                        command.env("ALBUM_TITLE", album_title);
                     }
+                    if let Some(duration) = track.duration {
+                        command.env("DURATION", duration.as_secs().to_string());
+                    }
+                }
+            }
+
+            Event::Skipped(track_id, reason) => {
+                if let Some(command) = command.as_mut() {
+                    command
+                        .env("EVENT", "skipped")
+                        .env("TRACK_ID", track_id.to_string())
+                        .env("REASON", reason.to_string());
                 }
             }
 
-            Event::Connected => {
+            Event::Connected(controller) => {
+                // The stop limit counts from every new connection.
+                self.tracks_started = 0;
+                self.stop_timer_armed = false;
+
                 if let Some(command) = command.as_mut() {
                     command
                         .env("EVENT", "connected")
                         .env("USER_ID", self.user_id().to_string())
-                        .env("USER_NAME", self.gateway.user_name().unwrap_or_default());
+                        .env("USER_NAME", self.gateway.user_name().unwrap_or_default())
+                        .env("CONTROLLER_ID", controller.to_string());
+
+                    // Only known when the controller offered connections itself.
+                    if let Some(session) = self.discovery_sessions.get(&controller) {
+                        if let Some(device_name) = &session.device_name {
+                            command.env("CONTROLLER_NAME", device_name);
+                        }
+                        if let Some(device_type) = session.device_type {
+                            command.env("CONTROLLER_TYPE", device_type.to_string());
+                        }
+                    }
                 }
             }
 
@@ -1001,38 +1600,288 @@ impl Client {
                     command.env("EVENT", "disconnected");
                 }
             }
+
+            Event::Favorite(track_id) => {
+                if let Some(command) = command.as_mut() {
+                    command
+                        .env("EVENT", "favorite")
+                        .env("TRACK_ID", track_id.to_string());
+                }
+            }
+
+            Event::Unfavorite(track_id) => {
+                if let Some(command) = command.as_mut() {
+                    command
+                        .env("EVENT", "unfavorite")
+                        .env("TRACK_ID", track_id.to_string());
+                }
+            }
+
+            Event::VolumeChanged(volume) => {
+                if let Some(command) = command.as_mut() {
+                    command
+                        .env("EVENT", "volume_changed")
+                        .env("VOLUME", Self::per_mille_to_percent(volume));
+                }
+            }
+
+            Event::Observed(playback) => {
+                if let Some(command) = command.as_mut() {
+                    command
+                        .env("EVENT", "observed")
+                        .env("DEVICE_ID", playback.device.to_string())
+                        .env("TRACK_ID", playback.track_id.to_string())
+                        .env("PLAYING", playback.is_playing.to_string())
+                        .env("VOLUME", Self::per_mille_to_percent(playback.volume));
+                    if let Some(progress) = playback.progress {
+                        command.env("PROGRESS", Self::per_mille_to_percent(progress));
+                    }
+                    if let Some(duration) = playback.duration {
+                        command.env("DURATION", duration.as_secs().to_string());
+                    }
+                }
+            }
         }
 
         if let Some(command) = command.as_mut() {
-            if let Err(e) = command.spawn() {
-                error!("failed to spawn hook script: {e}");
+            if self.print_events {
+                Self::print_event(command);
+            }
+
+            if self.hook.is_some() {
+                match command.spawn() {
+                    Ok(child) => {
+                        if self.hook_mode == HookMode::Blocking {
+                            self.wait_for_hook(child).await;
+                        }
+                    }
+                    Err(e) => error!("failed to spawn hook script: {e}"),
+                }
             }
         }
     }
 
-    /// Returns whether current queue is a Flow (personalized radio).
-    ///
-    /// Examines queue context to identify Flow queues by checking:
-    /// * Queue has contexts
-    /// * First context is a user mix
+    /// Clamps a controller volume to the range from muted to unity gain.
     ///
     /// # Returns
     ///
-    /// * `true` - Queue is a Flow queue
-    /// * `false` - Queue is not Flow or no queue exists
-    #[inline]
-    fn is_flow(&self) -> bool {
-        self.queue.as_ref().is_some_and(|queue| {
-            queue
-                .contexts
-                .first()
-                .unwrap_or_default()
-                .container
-                .mix
-                .typ
-                .enum_value_or_default()
-                == MixType::MIX_TYPE_USER
-        })
+    /// * `Some(volume)` - The clamped volume, if out of range; NaN mutes
+    /// * `None` - The volume is in range
+    fn clamp_volume(volume: Percentage) -> Option<Percentage> {
+        let ratio = volume.as_ratio();
+        if ratio.is_nan() {
+            Some(Percentage::ZERO)
+        } else if (0.0..=UNITY_GAIN).contains(&ratio) {
+            None
+        } else {
+            Some(Percentage::from_ratio(ratio.clamp(0.0, UNITY_GAIN)))
+        }
+    }
+
+    /// Formats a per mille value of an event as a whole percentage.
+    fn per_mille_to_percent(per_mille: u16) -> String {
+        format!("{:.0}", f32::from(per_mille) / 10.0)
+    }
+
+    /// Prints the hook variables of an event to stdout.
+    ///
+    /// Prints `EVENT` first, followed by the other variables in alphabetical
+    /// order, one per line, and an empty line to separate events. Prints
+    /// nothing for events that would not set any variables.
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - Hook command with the event variables set
+    fn print_event(command: &Command) {
+        let mut vars: Vec<_> = command
+            .as_std()
+            .get_envs()
+            .filter_map(|(key, value)| Some((key.to_string_lossy(), value?.to_string_lossy())))
+            .collect();
+        if vars.is_empty() {
+            return;
+        }
+
+        vars.sort_by_key(|(key, _)| *key != "EVENT");
+        for (key, value) in vars {
+            println!("{key}={value}");
+        }
+        println!();
+    }
+
+    /// Waits for a queue export request.
+    ///
+    /// Never returns when exports are disabled.
+    async fn export_requested(export_requests: &mut Option<ExportHandler>) {
+        match export_requests {
+            Some(export_requests) => export_requests.recv().await,
+            None => std::future::pending().await,
+        }
+    }
+
+    /// Exports the complete queue to the export file, if any.
+    ///
+    /// Failures are logged and otherwise ignored.
+    fn export_queue(&self) {
+        if let Some(path) = &self.export_file {
+            match export::write(
+                path,
+                self.export_format,
+                self.player.queue(),
+                self.player.position(),
+            ) {
+                Ok(()) => info!(
+                    "exported {} tracks to {}",
+                    self.player.queue().len(),
+                    path.display()
+                ),
+                Err(e) => error!("failed to export queue to {}: {e}", path.display()),
+            }
+        }
+    }
+
+    /// Writes the current track and queue to the now-playing file, if any.
+    ///
+    /// Failures are logged and otherwise ignored, so that a missing
+    /// directory or full disk does not interrupt playback.
+    fn write_now_playing(&self) {
+        if let Some(path) = &self.now_playing_file {
+            if let Err(e) = now_playing::write(
+                path,
+                self.now_playing_format,
+                self.player.queue(),
+                self.player.position(),
+            ) {
+                error!("failed to write now-playing file {}: {e}", path.display());
+            }
+        }
+    }
+
+    /// Waits for a hook script to finish.
+    ///
+    /// Kills the script if it does not finish within the hook timeout,
+    /// so that a slow script cannot stall event handling indefinitely.
+    ///
+    /// # Arguments
+    ///
+    /// * `child` - Spawned hook script process
+    async fn wait_for_hook(&self, mut child: tokio::process::Child) {
+        match tokio::time::timeout(self.hook_timeout, child.wait()).await {
+            Ok(Ok(status)) => {
+                if !status.success() {
+                    warn!("hook script exited with {status}");
+                }
+            }
+            Ok(Err(e)) => error!("failed to wait for hook script: {e}"),
+            Err(_) => {
+                warn!(
+                    "hook script did not finish within {}ms: killing it",
+                    self.hook_timeout.as_millis()
+                );
+                if let Err(e) = child.kill().await {
+                    error!("failed to kill hook script: {e}");
+                }
+            }
+        }
+    }
+
+    /// Returns whether current queue is a Flow (personalized radio).
+    ///
+    /// Examines queue context to identify Flow queues by checking:
+    /// * Queue has contexts
+    /// * First context is a user mix
+    ///
+    /// # Returns
+    ///
+    /// * `true` - Queue is a Flow queue
+    /// * `false` - Queue is not Flow or no queue exists
+    #[inline]
+    fn is_flow(&self) -> bool {
+        self.queue.as_ref().is_some_and(|queue| {
+            queue
+                .contexts
+                .first()
+                .unwrap_or_default()
+                .container
+                .mix
+                .typ
+                .enum_value_or_default()
+                == MixType::MIX_TYPE_USER
+        })
+    }
+
+    /// Emits an event for playback observed while eavesdropping.
+    ///
+    /// Turns progress reports of other devices into
+    /// [`Event::Observed`] events, without acting on them. Other messages
+    /// and duplicate deliveries are ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - Device that sent the message
+    /// * `body` - Observed message body
+    fn observe(&mut self, from: DeviceId, body: &Body) {
+        if let Body::PlaybackProgress {
+            message_id,
+            track,
+            duration,
+            progress,
+            volume,
+            is_playing,
+            ..
+        } = body
+        {
+            if !self.remember_message(message_id) {
+                return;
+            }
+
+            let playback = ObservedPlayback {
+                device: from,
+                track_id: track.track_id,
+                is_playing: *is_playing,
+                progress: progress.map(|progress| progress.as_per_mille()),
+                duration: *duration,
+                volume: volume.as_per_mille(),
+            };
+            if let Err(e) = self.event_tx.send(Event::Observed(Box::new(playback))) {
+                error!("failed to send observed event: {e}");
+            }
+        }
+    }
+
+    /// Records a received message ID for duplicate detection.
+    ///
+    /// Also records the IDs of sent close messages, so that echoes of them
+    /// are dropped as duplicates.
+    ///
+    /// Keeps at most [`Self::RECENT_MESSAGES_MAX`] IDs, evicting the oldest.
+    ///
+    /// # Returns
+    ///
+    /// * `true` - Message ID was not seen before
+    /// * `false` - Message ID is a duplicate
+    fn remember_message(&mut self, message_id: &str) -> bool {
+        Self::remember(&mut self.recent_messages, message_id)
+    }
+
+    /// Records a message ID in a list of recent message IDs.
+    ///
+    /// Keeps at most [`Self::RECENT_MESSAGES_MAX`] IDs, evicting the oldest.
+    ///
+    /// # Returns
+    ///
+    /// * `true` - Message ID was not in the list
+    /// * `false` - Message ID is a duplicate
+    fn remember(recent_messages: &mut VecDeque<String>, message_id: &str) -> bool {
+        if recent_messages.iter().any(|id| id == message_id) {
+            return false;
+        }
+
+        if recent_messages.len() >= Self::RECENT_MESSAGES_MAX {
+            recent_messages.pop_front();
+        }
+        recent_messages.push_back(message_id.to_string());
+        true
     }
 
     /// Resets the receive watchdog timer.
@@ -1065,11 +1914,152 @@ impl Client {
         }
     }
 
+    /// Resets the availability announcement timer.
+    ///
+    /// Schedules the next announcement according to the announce interval,
+    /// if configured.
+    #[inline]
+    fn reset_announce_timer(&mut self) {
+        if let Some(deadline) = self.announce_interval.and_then(from_now) {
+            self.announce_timer.as_mut().reset(deadline);
+        }
+    }
+
+    /// Fades out playback and reports the pause to the controller.
+    ///
+    /// Intended to be called before `stop()` on shutdown, so the controller
+    /// reflects that playback stopped and the audio does not cut abruptly.
+    /// Does nothing if not playing.
+    ///
+    /// # Arguments
+    ///
+    /// * `duration` - Duration of the fade
+    pub async fn fade_out(&mut self, duration: Duration) {
+        if !self.player.is_playing() {
+            return;
+        }
+
+        self.player.fade_out(duration).await;
+
+        if self.is_connected() {
+            if let Err(e) = self.report_playback_progress().await {
+                error!("error reporting playback progress: {e}");
+            }
+        }
+    }
+
+    /// Stops playback and disconnects because the stop limit is reached.
+    ///
+    /// Fades out over the exit fade duration, if any, so that the
+    /// controller receives a final pause report, and then disconnects.
+    async fn stop_at_limit(&mut self) {
+        info!(
+            "stop limit of {} reached: stopping playback",
+            self.stop_after
+                .map(|limit| limit.to_string())
+                .unwrap_or_default()
+        );
+
+        match self.stop_fade {
+            Some(duration) => self.fade_out(duration).await,
+            None => {
+                self.player.pause();
+                if self.is_connected() {
+                    if let Err(e) = self.report_playback_progress().await {
+                        error!("error reporting playback progress: {e}");
+                    }
+                }
+            }
+        }
+
+        if self.controller().is_some() {
+            if let Err(e) = self.disconnect().await {
+                error!("error disconnecting: {e}");
+            }
+        }
+    }
+
+    /// Returns whether the client stopped at the stop limit to exit.
+    ///
+    /// Set when the client returns from [`start`](Self::start) because the
+    /// stop limit was reached with exiting enabled.
+    #[must_use]
+    pub fn is_stopped(&self) -> bool {
+        self.stopped
+    }
+
+    /// Captures the playback state to resume after a reload.
+    ///
+    /// # Returns
+    ///
+    /// The current queue, position, progress and playback state, or `None`
+    /// if no queue was published.
+    #[must_use]
+    pub fn resume_state(&self) -> Option<Resume> {
+        let queue = self.queue.clone()?;
+        let progress = self
+            .player
+            .track()
+            .filter(|track| !track.is_livestream())
+            .and_then(|_| self.player.progress_percentage());
+
+        Some(Resume {
+            queue,
+            position: self.player.position(),
+            progress,
+            playing: self.player.is_playing(),
+        })
+    }
+
+    /// Sets the playback state to resume when a controller connects.
+    ///
+    /// # Arguments
+    ///
+    /// * `resume` - State captured from a previous client
+    pub fn resume(&mut self, resume: Resume) {
+        self.resume = Some(resume);
+    }
+
+    /// Resumes playback from a state captured before a reload.
+    ///
+    /// Resolves the queue again for fresh track tokens, restores the position
+    /// and progress, and publishes the queue to the controller.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if:
+    /// * Queue resolution fails
+    /// * Queue publication fails
+    /// * Playback cannot start
+    async fn resume_playback(&mut self, resume: Resume) -> Result<()> {
+        info!(
+            "resuming queue {} at position {} after reload",
+            resume.queue.id, resume.position
+        );
+
+        self.handle_publish_queue(resume.queue).await?;
+        self.player.set_position(resume.position);
+        if let Some(progress) = resume.progress {
+            // Deferred until the track is loaded.
+            if let Err(e) = self.player.set_progress(progress) {
+                warn!("failed to resume progress: {e}");
+            }
+        }
+
+        self.publish_queue().await?;
+        if resume.playing {
+            self.player.play()?;
+        }
+
+        Ok(())
+    }
+
     /// Stops the client and cleans up resources.
     ///
     /// * Disconnects from controller if connected
     /// * Processes remaining events
     /// * Unsubscribes from channels
+    /// * Flushes and closes the websocket
     pub async fn stop(&mut self) {
         if self.is_connected() {
             if let Err(e) = self.disconnect().await {
@@ -1086,12 +2076,15 @@ impl Client {
         }
 
         // Cancel any remaining subscriptions not handled by `disconnect`.
-        let subscriptions = self.subscriptions.clone();
-        for ident in subscriptions {
-            if self.unsubscribe(ident).await.is_ok() {
-                self.subscriptions.remove(&ident);
+        // Pending subscriptions are dropped, as the next session starts over.
+        for ident in self.subscriptions.active() {
+            if let Err(e) = self.unsubscribe(ident).await {
+                debug!("error unsubscribing from {ident}: {e}");
             }
         }
+        self.subscriptions = Subscriptions::default();
+
+        self.close_websocket().await;
 
         // Soft failure: JWT logins are not required to interact with the gateway.
         match tokio::time::timeout(Self::NETWORK_TIMEOUT, self.gateway.logout()).await {
@@ -1190,6 +2183,54 @@ impl Client {
         }
     }
 
+    /// Toggles whether a song is in the user's favorites.
+    ///
+    /// Emits a `Favorite` or `Unfavorite` event on success, so that hook
+    /// scripts are notified of the change.
+    ///
+    /// # Arguments
+    ///
+    /// * `track_id` - ID of the song to toggle
+    ///
+    /// # Returns
+    ///
+    /// Returns whether the song is a favorite after toggling.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if:
+    /// * Track is an episode or livestream
+    /// * Gateway request fails
+    pub async fn toggle_favorite(&mut self, track_id: TrackId) -> Result<bool> {
+        let track_type = self.queue.as_ref().and_then(|queue| {
+            queue
+                .tracks
+                .iter()
+                .find(|track| track.id == track_id.to_string())
+                .map(|track| track.typ.enum_value_or_default())
+        });
+        if track_type.is_some_and(|typ| typ != queue::TrackType::TRACK_TYPE_SONG) {
+            return Err(Error::unimplemented(format!(
+                "only songs can be favorited, not {track_id}"
+            )));
+        }
+
+        let favorite = self.gateway.toggle_favorite(track_id).await?;
+        let event = if favorite {
+            info!("added song {track_id} to favorites");
+            Event::Favorite(track_id)
+        } else {
+            info!("removed song {track_id} from favorites");
+            Event::Unfavorite(track_id)
+        };
+
+        if let Err(e) = self.event_tx.send(event) {
+            error!("failed to send favorite event: {e}");
+        }
+
+        Ok(favorite)
+    }
+
     /// Disconnects from the current controller.
     ///
     /// Sends a close message to the controller and resets connection state.
@@ -1227,6 +2268,10 @@ impl Client {
     /// Caching by device ID rather than session ID is more memory efficient since the same
     /// controllers typically reconnect multiple times with different session IDs.
     ///
+    /// To harden against discovery floods, offers to the same controller are throttled to
+    /// one per [`Self::DISCOVERY_OFFER_INTERVAL`] regardless of session, and the cache is
+    /// bounded to [`Self::DISCOVERY_SESSIONS_MAX`] controllers.
+    ///
     /// # Errors
     ///
     /// Returns error if message send fails
@@ -1235,14 +2280,110 @@ impl Client {
         from: DeviceId,
         discovery_session_id: String,
     ) -> Result<()> {
-        if self
+        // Other controllers could not connect anyway.
+        if self.hide_when_taken
+            && self.discovery_state == DiscoveryState::Taken
+            && self
+                .controller()
+                .is_none_or(|controller| controller != from)
+        {
+            trace!("hiding from {from} while taken");
+            return Ok(());
+        }
+
+        if let Some(session) = self.discovery_sessions.get(&from) {
+            if session.id == discovery_session_id {
+                return Ok(());
+            }
+
+            if session.offered_at.elapsed() < Self::DISCOVERY_OFFER_INTERVAL {
+                trace!("throttling discovery request from {from}");
+                return Ok(());
+            }
+        }
+
+        // Controllers keep sending discovery requests about every two seconds
+        // until it accepts some offer. Sometimes they take up on old requests,
+        // and we don't really care as long as it is directed to us.
+        let offer = Body::ConnectionOffer {
+            message_id: crate::Uuid::fast_v4().to_string(),
+            from: self.device_id.clone(),
+            device_name: self.device_name.clone(),
+            device_type: self.device_type,
+        };
+
+        let discover = self.discover(from.clone(), offer);
+        self.send_message(discover).await?;
+
+        // Cache the discovery session ID to prevent multiple offers showing up in the Deezer
+        // app. Newer versions of the app will ignore multiple offers from the same remote, but
+        // older versions will show the same remote multiple times.
+        if !self.discovery_sessions.contains_key(&from)
+            && self.discovery_sessions.len() >= Self::DISCOVERY_SESSIONS_MAX
+        {
+            // Evict the least recently offered controller.
+            let oldest = self
+                .discovery_sessions
+                .iter()
+                .min_by_key(|(_, session)| session.offered_at)
+                .map(|(controller, _)| controller.clone());
+            if let Some(oldest) = oldest {
+                trace!("evicting discovery session of {oldest}");
+                self.discovery_sessions.remove(&oldest);
+            }
+        }
+
+        let (device_name, device_type) = self
             .discovery_sessions
             .get(&from)
-            .is_none_or(|session_id| *session_id != discovery_session_id)
-        {
-            // Controllers keep sending discovery requests about every two seconds
-            // until it accepts some offer. Sometimes they take up on old requests,
-            // and we don't really care as long as it is directed to us.
+            .map_or((None, None), |session| {
+                (session.device_name.clone(), session.device_type)
+            });
+        self.discovery_sessions.insert(
+            from,
+            DiscoverySession {
+                id: discovery_session_id,
+                offered_at: tokio::time::Instant::now(),
+                device_name,
+                device_type,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Whether availability should be re-announced.
+    ///
+    /// Only when an announce interval is configured, and the client is
+    /// available for discovery and not connected to any controller.
+    #[must_use]
+    fn is_announcing(&self) -> bool {
+        self.announce_interval.is_some()
+            && !self.eavesdrop
+            && !self.is_connected()
+            && self.discovery_state == DiscoveryState::Available
+    }
+
+    /// Re-announces availability to controllers that requested discovery.
+    ///
+    /// Sends a fresh connection offer to every controller in the discovery
+    /// session cache, for controllers that missed or discarded the initial
+    /// offer. Controllers that did not send a discovery request during this
+    /// session are unknown and cannot be announced to.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if message send fails
+    async fn announce(&mut self) -> Result<()> {
+        let controllers: Vec<_> = self.discovery_sessions.keys().cloned().collect();
+        if !controllers.is_empty() {
+            trace!(
+                "announcing availability to {} controllers",
+                controllers.len()
+            );
+        }
+
+        for controller in controllers {
             let offer = Body::ConnectionOffer {
                 message_id: crate::Uuid::fast_v4().to_string(),
                 from: self.device_id.clone(),
@@ -1250,13 +2391,8 @@ impl Client {
                 device_type: self.device_type,
             };
 
-            let discover = self.discover(from.clone(), offer);
+            let discover = self.discover(controller, offer);
             self.send_message(discover).await?;
-
-            // Cache the discovery session ID to prevent multiple offers showing up in the Deezer
-            // app. Newer versions of the app will ignore multiple offers from the same remote, but
-            // older versions will show the same remote multiple times.
-            self.discovery_sessions.insert(from, discovery_session_id);
         }
 
         Ok(())
@@ -1291,6 +2427,30 @@ impl Client {
             return Ok(());
         }
 
+        if let (Some(cooldown), Some(connected_at), Some(controller)) =
+            (self.takeover_cooldown, self.connected_at, self.controller())
+        {
+            if self.is_connected()
+                && !controller.is_same(&from)
+                && connected_at.elapsed() < cooldown
+            {
+                info!(
+                    "not allowing takeover by {from} within {}s of connecting",
+                    cooldown.as_secs()
+                );
+
+                // Like interruptions, this is a valid condition.
+                return Ok(());
+            }
+        }
+
+        if !self.is_admitted(&from).await {
+            info!("connection from {from} rejected by policy");
+
+            // Like interruptions, this is a valid condition.
+            return Ok(());
+        }
+
         // Subscribe to both channels. If one fails, try to roll back.
         self.subscribe(Ident::RemoteQueue).await?;
         if let Err(e) = self.subscribe(Ident::RemoteCommand).await {
@@ -1314,6 +2474,61 @@ impl Client {
         Ok(())
     }
 
+    /// Checks whether the connection policy accepts a controller.
+    ///
+    /// Runs the connection policy script, if any, with `CONTROLLER_ID` and
+    /// `USER_ID` in its environment. The controller is accepted when the
+    /// script exits with status 0. Scripts that fail to run or do not finish
+    /// within [`CONNECT_POLICY_TIMEOUT`](Self::CONNECT_POLICY_TIMEOUT) reject
+    /// the controller, so that a broken policy does not let anyone in.
+    ///
+    /// # Arguments
+    ///
+    /// * `from` - Controller that wants to connect
+    ///
+    /// # Returns
+    ///
+    /// * true - No policy is set, or the policy accepts the controller
+    /// * false - The policy rejects the controller or failed
+    async fn is_admitted(&self, from: &DeviceId) -> bool {
+        let Some(policy) = &self.connect_policy else {
+            return true;
+        };
+
+        let mut child = match Command::new(policy)
+            .env("CONTROLLER_ID", from.to_string())
+            .env("USER_ID", self.user_id().to_string())
+            .spawn()
+        {
+            Ok(child) => child,
+            Err(e) => {
+                error!("failed to run connection policy {policy}: {e}");
+                return false;
+            }
+        };
+
+        match tokio::time::timeout(Self::CONNECT_POLICY_TIMEOUT, child.wait()).await {
+            Ok(Ok(status)) => {
+                debug!("connection policy for {from} exited with {status}");
+                status.success()
+            }
+            Ok(Err(e)) => {
+                error!("failed to wait for connection policy: {e}");
+                false
+            }
+            Err(_) => {
+                warn!(
+                    "connection policy did not finish within {}ms: killing it",
+                    Self::CONNECT_POLICY_TIMEOUT.as_millis()
+                );
+                if let Err(e) = child.kill().await {
+                    error!("failed to kill connection policy: {e}");
+                }
+                false
+            }
+        }
+    }
+
     /// Checks if client has active controller connection.
     ///
     /// # Returns
@@ -1360,14 +2575,22 @@ impl Client {
     /// * An active controller connection
     /// * A pending controller connection
     ///
+    /// The message ID is remembered, so that an echo of the close is not
+    /// handled as a controller-initiated close.
+    ///
     /// # Errors
     ///
     /// Returns error if message send fails
     async fn send_close(&mut self) -> Result<()> {
         if let Some(controller) = self.controller() {
-            let close = Body::Close {
-                message_id: crate::Uuid::fast_v4().to_string(),
-            };
+            let message_id = crate::Uuid::fast_v4().to_string();
+
+            // Controllers may echo the close back. Remembering its ID drops
+            // the echo as a duplicate, instead of handling it as a
+            // controller-initiated close of a connection that is gone.
+            self.remember_message(&message_id);
+
+            let close = Body::Close { message_id };
 
             let command = self.command(controller.clone(), close);
             self.send_message(command).await?;
@@ -1430,9 +2653,10 @@ impl Client {
                     controller: from,
                     session_id: crate::Uuid::fast_v4().into(),
                 };
+                self.connected_at = Some(tokio::time::Instant::now());
 
                 info!("connected to {controller}");
-                if let Err(e) = self.event_tx.send(Event::Connected) {
+                if let Err(e) = self.event_tx.send(Event::Connected(controller)) {
                     error!("failed to send connected event: {e}");
                 }
 
@@ -1451,6 +2675,12 @@ impl Client {
                 self.user_token = Some(user_token?);
                 self.set_player_settings();
 
+                if let Some(resume) = self.resume.take() {
+                    if let Err(e) = self.resume_playback(resume).await {
+                        error!("failed to resume playback after reload: {e}");
+                    }
+                }
+
                 return Ok(());
             }
 
@@ -1510,9 +2740,12 @@ impl Client {
         // Ensure the player releases the output device.
         self.player.stop();
 
-        // Restore the initial volume for the next connection.
+        // Restore the initial volume for the next connection, unless it
+        // should only be applied once.
         if let InitialVolume::Inactive(initial_volume) = self.initial_volume {
-            self.initial_volume = InitialVolume::Active(initial_volume);
+            if self.initial_volume_mode == InitialVolumeMode::Sticky {
+                self.initial_volume = InitialVolume::Active(initial_volume);
+            }
         }
 
         // Force the user token to be reloaded on the next connection.
@@ -1549,14 +2782,43 @@ impl Client {
         let queue = tokio::time::timeout(Self::NETWORK_TIMEOUT, self.gateway.list_to_queue(&list))
             .await??;
 
-        let tracks: Vec<_> = queue.into_iter().map(Track::from).collect();
+        let mut tracks: Vec<_> = queue.into_iter().map(Track::from).collect();
 
+        if list.shuffled {
+            match Self::playback_order(&list) {
+                Some(order) if order.len() == tracks.len() => {
+                    let mut slots: Vec<_> = tracks.into_iter().map(Some).collect();
+                    tracks = order
+                        .into_iter()
+                        .filter_map(|index| slots[index].take())
+                        .collect();
+                }
+                _ => warn!(
+                    "shuffle order of queue {} does not match its {} tracks; playing in original order",
+                    list.id,
+                    list.tracks.len()
+                ),
+            }
+        }
+
+        // Keep playing the current track when the same queue is republished,
+        // for example after the controller edited it.
+        let republished = self.queue.as_ref().is_some_and(|queue| queue.id == list.id);
         self.queue = Some(list);
-        self.player.set_queue(tracks);
+        if republished {
+            if self.player.update_queue(tracks) {
+                debug!("preserved current track in republished queue");
+            }
+        } else {
+            // Recommendations kept for a previous Flow queue are stale.
+            self.flow_surplus.clear();
+            self.player.set_queue(tracks);
+        }
 
         if let Some(position) = self.deferred_position.take() {
             self.set_position(position);
         }
+        self.write_now_playing();
 
         if self.is_flow() {
             self.extend_queue().await?;
@@ -1565,6 +2827,76 @@ impl Client {
         Ok(())
     }
 
+    /// Refreshes track tokens of queued songs that are about to expire.
+    ///
+    /// Fetches fresh list data for all expiring songs in as few requests
+    /// as possible, instead of letting tracks fail to load one by one.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if:
+    /// * Request times out
+    /// * All batches fail
+    async fn refresh_track_tokens(&mut self) -> Result<()> {
+        let expiring = self
+            .player
+            .expiring_songs(Self::TRACK_TOKEN_REFRESH_THRESHOLD);
+        if expiring.is_empty() {
+            return Ok(());
+        }
+
+        debug!("refreshing track tokens of {} songs", expiring.len());
+        let items =
+            tokio::time::timeout(Self::NETWORK_TIMEOUT, self.gateway.songs(&expiring)).await??;
+        self.player.refresh_tokens(&items);
+
+        Ok(())
+    }
+
+    /// Logs the current track to the listening history once played long enough.
+    ///
+    /// A track is logged at most once, after it has been played for
+    /// [`Self::LISTEN_THRESHOLD`], not counting seeks. Livestreams are not logged. Does nothing
+    /// if listening history is disabled.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if:
+    /// * Gateway request fails or times out
+    /// * Deezer did not accept the listen
+    async fn log_listen(&mut self) -> Result<()> {
+        if !self.listening_history || self.listen_logged {
+            return Ok(());
+        }
+
+        let Some(track) = self.player.track() else {
+            return Ok(());
+        };
+        if track.is_livestream() {
+            return Ok(());
+        }
+        let (track_id, typ, quality) = (track.id(), track.typ(), track.quality());
+
+        // Count the time actually played, so that seeking ahead does not
+        // count as listening.
+        let listening_time = self.player.played();
+        if listening_time < Self::LISTEN_THRESHOLD {
+            return Ok(());
+        }
+
+        // Mark as logged before the request, so a failure does not cause
+        // a retry on every progress report.
+        self.listen_logged = true;
+
+        debug!("logging listen of {typ} {track_id}");
+        tokio::time::timeout(
+            Self::NETWORK_TIMEOUT,
+            self.gateway
+                .log_listen(track_id, typ, quality, listening_time),
+        )
+        .await?
+    }
+
     /// Sends ping message to controller.
     ///
     /// Part of connection keepalive mechanism.
@@ -1589,6 +2921,70 @@ impl Client {
         ))
     }
 
+    /// Whether the player is near the end of the queue.
+    ///
+    /// Near the end means that at most the Flow lookahead of tracks is left,
+    /// so that the queue can be extended before playback runs out.
+    #[must_use]
+    fn is_near_end(&self) -> bool {
+        let len = self.queue.as_ref().map_or(0, |queue| queue.tracks.len());
+        Self::near_end(len, self.player.position(), self.flow_lookahead)
+    }
+
+    /// Whether at most `lookahead` tracks are left from `position` in a
+    /// queue of `len` tracks.
+    #[must_use]
+    fn near_end(len: usize, position: usize, lookahead: usize) -> bool {
+        len.saturating_sub(position) <= lookahead
+    }
+
+    /// Returns when a throttled queue extension may run.
+    ///
+    /// # Returns
+    ///
+    /// * `Some(deadline)` - The previous extension is within `interval` of
+    ///   `now`, so the extension must wait until `deadline`
+    /// * `None` - The queue may be extended right away
+    #[must_use]
+    fn extend_deadline(
+        extended_at: Option<tokio::time::Instant>,
+        interval: Duration,
+        now: tokio::time::Instant,
+    ) -> Option<tokio::time::Instant> {
+        extended_at
+            .and_then(|extended_at| extended_at.checked_add(interval))
+            .filter(|deadline| *deadline > now)
+    }
+
+    /// Extends Flow queue, throttled to the Flow extension interval.
+    ///
+    /// Extends the queue right away if the previous extension is longer ago
+    /// than the interval. Otherwise schedules a single extension for when
+    /// the interval has passed, so that bursts of requests from rapid skips
+    /// are coalesced. Errors are logged.
+    async fn request_extend_queue(&mut self) {
+        let deadline = Self::extend_deadline(
+            self.flow_extended_at,
+            self.flow_extend_interval,
+            tokio::time::Instant::now(),
+        );
+
+        match deadline {
+            Some(deadline) => {
+                if !self.flow_extend_pending {
+                    debug!("throttling queue extension");
+                    self.flow_extend_timer.as_mut().reset(deadline);
+                    self.flow_extend_pending = true;
+                }
+            }
+            None => {
+                if let Err(e) = self.extend_queue().await {
+                    error!("error extending queue: {e}");
+                }
+            }
+        }
+    }
+
     /// Extends Flow queue and notifies controller.
     ///
     /// Fetches more personalized recommendations when:
@@ -1607,15 +3003,29 @@ impl Client {
     /// * No active queue exists
     /// * Track fetch fails
     /// * Controller communication fails
+    #[expect(clippy::cast_possible_truncation)]
     async fn extend_queue(&mut self) -> Result<()> {
         let user_id = self.user_id();
 
         if let Some(list) = self.queue.as_mut() {
-            let new_queue =
-                tokio::time::timeout(Self::NETWORK_TIMEOUT, self.gateway.user_radio(user_id))
-                    .await??;
+            // Also failed extensions count, so that errors are not retried
+            // in quick succession.
+            self.flow_extended_at = Some(tokio::time::Instant::now());
+            self.flow_extend_pending = false;
+
+            // Keep the queue bounded, as Flow never ends. Recommendations
+            // beyond the lookahead are kept for the next extensions, and only
+            // when those run out are new ones fetched.
+            if self.flow_surplus.len() < self.flow_lookahead {
+                let new_queue =
+                    tokio::time::timeout(Self::NETWORK_TIMEOUT, self.gateway.user_radio(user_id))
+                        .await??;
+                self.flow_surplus
+                    .extend(new_queue.into_iter().map(Track::from));
+            }
 
-            let new_tracks: Vec<_> = new_queue.into_iter().map(Track::from).collect();
+            let count = self.flow_lookahead.min(self.flow_surplus.len());
+            let new_tracks: Vec<_> = self.flow_surplus.drain(..count).collect();
 
             let new_list: Vec<_> = new_tracks
                 .iter()
@@ -1627,8 +3037,16 @@ impl Client {
 
             debug!("extending queue with {} tracks", new_tracks.len());
 
+            // New tracks are played last, also when shuffled.
+            if Self::shuffle_order(list).is_some() {
+                let len = list.tracks.len();
+                list.tracks_order
+                    .extend((len..len + new_list.len()).map(|position| position as u32));
+            }
+
             list.tracks.extend(new_list);
             self.player.extend_queue(new_tracks);
+            self.write_now_playing();
             self.refresh_queue().await
         } else {
             Err(Error::failed_precondition(
@@ -1865,12 +3283,63 @@ impl Client {
         }
     }
 
+    /// Returns the shuffle order of a queue, if it is shuffled and valid.
+    ///
+    /// In a shuffled queue, `tracks` stays in its original order and
+    /// `tracks_order[i]` holds the playback position of `tracks[i]`. The
+    /// order is only valid when it is a permutation of the track indices;
+    /// otherwise the queue is played in its original order.
+    ///
+    /// # Arguments
+    ///
+    /// * `list` - Queue to get the shuffle order of
+    fn shuffle_order(list: &queue::List) -> Option<&[u32]> {
+        if !list.shuffled {
+            return None;
+        }
+
+        let len = list.tracks.len();
+        if list.tracks_order.len() != len {
+            return None;
+        }
+
+        let mut seen = vec![false; len];
+        for position in &list.tracks_order {
+            match seen.get_mut(*position as usize) {
+                Some(seen @ false) => *seen = true,
+                _ => return None,
+            }
+        }
+
+        Some(&list.tracks_order)
+    }
+
+    /// Returns the indices into `tracks` of a shuffled queue in playback order.
+    ///
+    /// Returns `None` if the queue is not shuffled or its shuffle order is
+    /// invalid, in which case playback order equals the original order.
+    ///
+    /// # Arguments
+    ///
+    /// * `list` - Queue to get the playback order of
+    fn playback_order(list: &queue::List) -> Option<Vec<usize>> {
+        Self::shuffle_order(list).map(|order| {
+            let mut indices = vec![0; order.len()];
+            for (index, position) in order.iter().enumerate() {
+                indices[*position as usize] = index;
+            }
+            indices
+        })
+    }
+
     /// Sets the current playback position in the queue.
     ///
     /// Handles position conversion for shuffled queues:
     /// * For unshuffled queues - Uses position directly
     /// * For shuffled queues - Maps position through shuffle order
     ///
+    /// Positions outside of the shuffle order are used directly.
+    ///
     /// # Arguments
     ///
     /// * `position` - Target position in the queue (in display order)
@@ -1878,14 +3347,95 @@ impl Client {
     /// After position calculation, updates the player's actual queue position.
     #[inline]
     fn set_position(&mut self, position: usize) {
-        let mut position = position;
-        if let Some(queue) = self.queue.as_ref() {
-            if queue.shuffled {
-                position = queue.tracks_order[position] as usize;
-            }
+        let position = self
+            .queue
+            .as_ref()
+            .and_then(Self::shuffle_order)
+            .and_then(|order| order.get(position))
+            .map_or(position, |position| *position as usize);
+
+        self.player.set_position(position);
+    }
+
+    /// Returns the queue item that is currently playing.
+    ///
+    /// The position is in display order: for shuffled queues, the player
+    /// position is mapped back through the shuffle order.
+    ///
+    /// Returns `None` if there is no active queue or no current track.
+    #[must_use]
+    pub fn current_item(&self) -> Option<QueueItem> {
+        let queue = self.queue.as_ref()?;
+        let track = self.player.track()?;
+
+        let player_position = self.player.position();
+        let position = Self::shuffle_order(queue)
+            .and_then(|order| order.iter().position(|i| *i as usize == player_position))
+            .unwrap_or(player_position);
+
+        Some(QueueItem {
+            queue_id: queue.id.to_string(),
+            track_id: track.id(),
+            position,
+        })
+    }
+
+    /// Jumps to a queue item.
+    ///
+    /// Unlike [`set_player_state`](Self::set_player_state), which defers
+    /// positions for queues that have not been published yet, this validates
+    /// that the item belongs to the current queue and fails otherwise. The
+    /// position is in display order and mapped through the shuffle order like
+    /// controller skips.
+    ///
+    /// # Arguments
+    ///
+    /// * `item` - Queue item to jump to
+    ///
+    /// # Errors
+    ///
+    /// Returns error if:
+    /// * There is no active queue
+    /// * The item belongs to a different queue
+    /// * The position is outside the queue
+    /// * The track at the position does not match the item
+    pub fn play_item(&mut self, item: &QueueItem) -> Result<()> {
+        let queue = self
+            .queue
+            .as_ref()
+            .ok_or_else(|| Error::failed_precondition("no active queue".to_string()))?;
+
+        if queue.id != item.queue_id {
+            return Err(Error::invalid_argument(format!(
+                "queue item belongs to queue {} but current queue is {}",
+                item.queue_id, queue.id
+            )));
+        }
+
+        let position = Self::shuffle_order(queue)
+            .and_then(|order| order.get(item.position))
+            .map_or(item.position, |position| *position as usize);
+
+        let track_id = self
+            .player
+            .queue()
+            .get(position)
+            .map(Track::id)
+            .ok_or_else(|| {
+                Error::out_of_range(format!(
+                    "queue position {} is outside the queue",
+                    item.position
+                ))
+            })?;
+        if track_id != item.track_id {
+            return Err(Error::invalid_argument(format!(
+                "queue position {} holds track {track_id}, not {}",
+                item.position, item.track_id
+            )));
         }
 
         self.player.set_position(position);
+        Ok(())
     }
 
     /// Updates player state based on controller commands.
@@ -1977,22 +3527,40 @@ impl Client {
                     self.shuffle_queue(ShuffleAction::Unshuffle);
                 }
 
-                if let Some(queue) = self.queue.as_mut() {
-                    let reordered_queue: Vec<_> = queue
-                        .tracks
-                        .iter()
-                        .filter_map(|track| track.id.parse().ok())
-                        .collect();
+                if let Some(queue) = self.queue.as_ref() {
+                    let reordered_queue: Vec<_> = match Self::playback_order(queue) {
+                        Some(order) => order
+                            .into_iter()
+                            .filter_map(|index| queue.tracks[index].id.parse().ok())
+                            .collect(),
+                        None => queue
+                            .tracks
+                            .iter()
+                            .filter_map(|track| track.id.parse().ok())
+                            .collect(),
+                    };
                     self.player.reorder_queue(&reordered_queue);
+                    self.write_now_playing();
                 }
             }
         }
 
         if let Some(repeat_mode) = set_repeat_mode {
-            self.player.set_repeat_mode(repeat_mode);
+            if repeat_mode == RepeatMode::Unrecognized {
+                warn!("ignoring unrecognized repeat mode");
+            } else {
+                self.player.set_repeat_mode(repeat_mode);
+            }
         }
 
         if let Some(mut volume) = set_volume {
+            // Clamp volumes out of range, so that a misbehaving controller
+            // cannot apply extreme gain or report it back.
+            if let Some(clamped) = Self::clamp_volume(volume) {
+                volume = clamped;
+                warn!("controller set volume out of range: clamping to {volume}");
+            }
+
             if let InitialVolume::Active(initial_volume) = self.initial_volume {
                 if volume < Percentage::ONE_HUNDRED {
                     // If the volume is set to a value less than 1.0, we stop using the initial
@@ -2020,6 +3588,11 @@ impl Client {
                                 error!("error setting initial volume: {e}");
                                 result = Err(e);
                             }
+
+                            // Yield to the controller from now on.
+                            if self.initial_volume_mode == InitialVolumeMode::Once {
+                                self.initial_volume = InitialVolume::Inactive(initial_volume);
+                            }
                         }
                     }
                     Err(e) => {
@@ -2045,16 +3618,16 @@ impl Client {
     /// * `action` - Whether to shuffle or unshuffle the queue
     ///
     /// When shuffling:
-    /// * Randomizes track order
-    /// * Stores original order for unshuffling
+    /// * Randomizes playback order
+    /// * Keeps tracks in their original order
     /// * Updates shuffle state
     ///
     /// When unshuffling:
-    /// * Restores original track order
-    /// * Clears stored order
+    /// * Clears playback order
     /// * Updates shuffle state
     ///
-    /// No effect if no queue exists.
+    /// This matches how controllers publish shuffled queues, see
+    /// [`Self::shuffle_order`]. No effect if no queue exists.
     #[expect(clippy::cast_possible_truncation)]
     fn shuffle_queue(&mut self, action: ShuffleAction) {
         if let Some(queue) = self.queue.as_mut() {
@@ -2062,36 +3635,16 @@ impl Client {
                 ShuffleAction::Shuffle => {
                     info!("shuffling queue");
 
-                    let len = queue.tracks.len();
-                    let mut order: Vec<usize> = (0..len).collect();
+                    let mut order: Vec<u32> = (0..queue.tracks.len() as u32).collect();
                     fastrand::shuffle(&mut order);
 
-                    let mut tracks = Vec::with_capacity(len);
-                    for i in &order {
-                        tracks.push(queue.tracks[*i].clone());
-                    }
-
-                    queue.tracks = tracks;
-                    queue.tracks_order = order.iter().map(|position| *position as u32).collect();
+                    queue.tracks_order = order;
                     queue.shuffled = true;
                 }
 
                 ShuffleAction::Unshuffle => {
                     info!("unshuffling queue");
 
-                    let len = queue.tracks.len();
-                    let mut tracks = Vec::with_capacity(len);
-                    for i in 0..len {
-                        if let Some(position) = queue
-                            .tracks_order
-                            .iter()
-                            .position(|position| *position == i as u32)
-                        {
-                            tracks.push(queue.tracks[position].clone());
-                        }
-                    }
-
-                    queue.tracks = tracks;
                     queue.tracks_order = Vec::new();
                     queue.shuffled = false;
                 }
@@ -2130,13 +3683,8 @@ impl Client {
 
     /// Reports current playback state to controller.
     ///
-    /// Sends current:
-    /// * Track information
-    /// * Playback progress
-    /// * Buffer status
-    /// * Volume level
-    /// * Playback state
-    /// * Shuffle/repeat modes
+    /// Sends the report built by [`playback_progress`](Self::playback_progress).
+    /// Does nothing when progress reports are disabled.
     ///
     /// # Errors
     ///
@@ -2145,67 +3693,88 @@ impl Client {
     /// * No active queue
     /// * No current track
     /// * Message send fails
-    #[expect(clippy::cast_possible_truncation)]
     async fn report_playback_progress(&mut self) -> Result<()> {
         // Reset the timer regardless of success or failure, to prevent getting
         // stuck in a reporting state.
         self.reset_reporting_timer();
 
-        // TODO : replace `if let Some(x) = y` with `let x = y.ok_or(z)?`
-        if let Some(controller) = self.controller() {
-            if let Some(track) = self.player.track() {
-                let queue = self
-                    .queue
-                    .as_ref()
-                    .ok_or_else(|| Error::internal("no active queue"))?;
-
-                let player_position = self.player.position();
-                let mut position = player_position;
-                if queue.shuffled {
-                    position = queue
-                        .tracks_order
-                        .iter()
-                        .position(|i| *i == player_position as u32)
-                        .unwrap_or_default();
-                }
+        if let Some(command) = self.playback_progress()? {
+            self.send_message(command).await?;
+        }
 
-                let item = QueueItem {
-                    queue_id: queue.id.to_string(),
-                    track_id: track.id(),
-                    position,
-                };
+        Ok(())
+    }
 
-                let progress = Body::PlaybackProgress {
-                    message_id: crate::Uuid::fast_v4().to_string(),
-                    track: item,
-                    quality: track.quality(),
-                    duration: self.player.duration(),
-                    buffered: track.buffered(),
-                    progress: self.player.progress(),
-                    volume: self.player.volume(),
-                    is_playing: self.player.is_playing(),
-                    is_shuffle: queue.shuffled,
-                    repeat_mode: self.player.repeat_mode(),
-                };
+    /// Builds a playback progress report for the controller.
+    ///
+    /// Reports current:
+    /// * Track information
+    /// * Playback progress
+    /// * Buffer status
+    /// * Volume level
+    /// * Playback state
+    /// * Shuffle/repeat modes
+    ///
+    /// Returns `None` when progress reports are disabled or no track is
+    /// loaded.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if:
+    /// * No active controller
+    /// * No active queue
+    /// * No current queue item
+    fn playback_progress(&self) -> Result<Option<Message>> {
+        if !self.progress_reports {
+            return Ok(None);
+        }
 
-                let command = self.command(controller.clone(), progress);
-                self.send_message(command).await?;
-            }
+        let controller = self.controller().ok_or_else(|| {
+            Error::failed_precondition("playback progress should have an active connection")
+        })?;
+        let Some(track) = self.player.track() else {
+            return Ok(None);
+        };
 
-            Ok(())
-        } else {
-            Err(Error::failed_precondition(
-                "playback progress should have an active connection".to_string(),
-            ))
-        }
+        let queue = self
+            .queue
+            .as_ref()
+            .ok_or_else(|| Error::internal("no active queue"))?;
+
+        let item = self
+            .current_item()
+            .ok_or_else(|| Error::internal("no current queue item"))?;
+
+        let progress = Body::PlaybackProgress {
+            message_id: crate::Uuid::fast_v4().to_string(),
+            track: item,
+            quality: track.quality(),
+            // Livestreams have no duration, so report the time since the
+            // stream started playing, at which they are always at the end.
+            duration: if track.is_livestream() {
+                self.player.progress()
+            } else {
+                self.player.duration()
+            },
+            buffered: track.buffered(),
+            progress: self.player.progress_percentage(),
+            volume: self.player.volume(),
+            is_playing: self.player.is_playing(),
+            is_shuffle: queue.shuffled,
+            repeat_mode: self.player.repeat_mode(),
+        };
+
+        Ok(Some(self.command(controller, progress)))
     }
 
     /// Handles incoming websocket messages.
     ///
     /// Processes:
-    /// * Text messages (JSON protocol messages)
-    /// * Ping frames (RFC 6455 compliance)
+    /// * Text messages (JSON protocol messages, including application-level
+    ///   `Ping` bodies)
+    /// * Ping and pong frames (websocket-level keepalive)
     /// * Close frames (connection termination)
+    /// * Binary frames (not used by Deezer Connect, ignored)
     ///
     /// # Arguments
     ///
@@ -2225,14 +3794,18 @@ impl Client {
                                 let from = contents.headers.from;
 
                                 // Ignore echoes of own messages.
-                                if from == self.device_id {
+                                if from.is_same(&self.device_id) {
+                                    trace!(
+                                        "ignoring echo of own {} message",
+                                        contents.body.message_type()
+                                    );
                                     return ControlFlow::Continue(());
                                 }
 
-                                let for_another = contents
-                                    .headers
-                                    .destination
-                                    .is_some_and(|destination| destination != self.device_id);
+                                let for_another =
+                                    contents.headers.destination.is_some_and(|destination| {
+                                        !destination.is_same(&self.device_id)
+                                    });
 
                                 // Only log messages intended for this device or eavesdropping.
                                 if !for_another || self.eavesdrop {
@@ -2243,11 +3816,33 @@ impl Client {
                                     }
                                 }
 
+                                if self.eavesdrop && self.eavesdrop_events {
+                                    self.observe(from.clone(), &contents.body);
+                                }
+
                                 // Ignore messages not intended for this device.
                                 if for_another || self.eavesdrop {
                                     return ControlFlow::Continue(());
                                 }
 
+                                // Ignore messages that were delivered before.
+                                if !self.remember_message(contents.body.message_id()) {
+                                    trace!(
+                                        "ignoring duplicate {} message from {from}",
+                                        contents.body.message_type()
+                                    );
+                                    return ControlFlow::Continue(());
+                                }
+
+                                // Drop message types withheld for protocol research.
+                                let message_type = contents.body.message_type();
+                                if self.ignore_message_types.contains(&message_type) {
+                                    debug!(
+                                        "ignoring {message_type} message from {from} as configured"
+                                    );
+                                    return ControlFlow::Continue(());
+                                }
+
                                 if self
                                     .controller()
                                     .is_some_and(|controller| controller == from)
@@ -2284,24 +3879,38 @@ impl Client {
                 }
             }
 
-            // Deezer Connect sends pings as text message payloads, but so far
-            // not as websocket frames. Aim for RFC 6455 compliance anyway.
+            // Deezer Connect sends pings as text message payloads, which are
+            // handled as `Body::Ping` above. Websocket ping frames are answered
+            // with a pong by tungstenite itself on the next read or write, so
+            // replying here would send a second pong.
             WebsocketMessage::Ping(payload) => {
-                debug!("ping -> pong");
-                let pong = Frame::pong(payload.clone());
-                if let Err(e) = self.send_frame(WebsocketMessage::Frame(pong)).await {
-                    error!("{e}");
-                }
+                trace!("websocket ping frame ({} bytes)", payload.len());
+            }
+
+            // Unsolicited pongs are allowed by RFC 6455, and we never send
+            // websocket pings of our own.
+            WebsocketMessage::Pong(payload) => {
+                trace!("websocket pong frame ({} bytes)", payload.len());
             }
 
+            // The close handshake is completed by tungstenite.
             WebsocketMessage::Close(payload) => {
+                debug!("websocket close frame: {payload:?}");
                 return ControlFlow::Break(Error::aborted(format!(
                     "connection closed by server: {payload:?}"
-                )))
+                )));
+            }
+
+            WebsocketMessage::Binary(payload) => {
+                warn!(
+                    "ignoring unexpected binary message ({} bytes)",
+                    payload.len()
+                );
             }
 
-            _ => {
-                trace!("ignoring unimplemented frame: {message:#?}");
+            // Raw frames are only used for sending and never returned by reads.
+            WebsocketMessage::Frame(_) => {
+                trace!("ignoring raw frame: {message:#?}");
             }
         }
 
@@ -2385,13 +3994,70 @@ impl Client {
                 Ok(())
             }
 
-            Body::ConnectionOffer { .. } | Body::PlaybackProgress { .. } | Body::Ready { .. } => {
+            Body::ConnectionOffer {
+                from: device,
+                device_name,
+                device_type,
+                ..
+            } => {
+                // Devices that both control and play, like the mobile apps,
+                // offer connections too. Remember how a controller identifies
+                // itself, for the hook when it connects.
+                if let Some(session) = self.discovery_sessions.get_mut(&device) {
+                    trace!("{device} is {device_type} device {device_name}");
+                    session.device_name = Some(device_name);
+                    session.device_type = Some(device_type);
+                }
+                Ok(())
+            }
+
+            Body::PlaybackProgress { .. } | Body::Ready { .. } => {
                 trace!("ignoring message intended for a controller");
                 Ok(())
             }
         }
     }
 
+    /// Flushes pending frames and closes the websocket.
+    ///
+    /// Frames still buffered in the sink, like the final close or status
+    /// to the controller, are sent before the websocket is dropped. Bounded
+    /// by the network timeout, so shutdown stays prompt when the websocket
+    /// is dead.
+    async fn close_websocket(&mut self) {
+        let Some(tx) = self.websocket_tx.take() else {
+            return;
+        };
+
+        match Self::close_sink(tx, Self::NETWORK_TIMEOUT).await {
+            Ok(()) => debug!("websocket closed"),
+            Err(e) if e.kind == ErrorKind::DeadlineExceeded => {
+                warn!("websocket close timed out");
+            }
+            Err(e) => debug!("error closing websocket: {e}"),
+        }
+    }
+
+    /// Flushes and closes a websocket sink within a timeout.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if:
+    /// * Flushing or closing fails
+    /// * Flushing and closing take longer than `timeout` (`DeadlineExceeded`)
+    async fn close_sink<S>(mut tx: S, timeout: Duration) -> Result<()>
+    where
+        S: futures_util::Sink<WebsocketMessage> + Unpin,
+        Error: From<S::Error>,
+    {
+        let drain = async {
+            tx.flush().await?;
+            tx.close().await
+        };
+
+        Ok(tokio::time::timeout(timeout, drain).await??)
+    }
+
     /// Sends a websocket frame.
     ///
     /// # Arguments
@@ -2431,20 +4097,68 @@ impl Client {
         // stuck in a reporting state.
         self.reset_watchdog_tx();
 
+        let frame = Self::encode(&message)?;
+        self.send_frame(frame).await
+    }
+
+    /// Serializes a protocol message to a JSON text frame.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if JSON serialization fails
+    fn encode(message: &Message) -> Result<WebsocketMessage> {
         if log_enabled!(Level::Trace) {
             trace!("{message:#?}");
         } else {
             debug!("{message}");
         }
 
-        let json = serde_json::to_string(&message)?;
-        let frame = WebsocketMessage::Text(json.into());
-        self.send_frame(frame).await
+        let json = serde_json::to_string(message)?;
+        Ok(WebsocketMessage::Text(json.into()))
+    }
+
+    /// Sends a frame, retrying with backoff when sending fails.
+    ///
+    /// Makes up to `attempts` attempts, waiting `delay` before the first
+    /// retry and twice as long before every next one. Does not retry when
+    /// the websocket is closed, as no attempt can succeed.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the last attempt.
+    async fn send_with_retry<S>(
+        tx: &mut S,
+        frame: WebsocketMessage,
+        attempts: u32,
+        mut delay: Duration,
+    ) -> Result<()>
+    where
+        S: futures_util::Sink<WebsocketMessage> + Unpin,
+        Error: From<S::Error>,
+    {
+        let mut attempt = 1;
+        loop {
+            match tx.send(frame.clone()).await.map_err(Error::from) {
+                Ok(()) => return Ok(()),
+                Err(e)
+                    if attempt < attempts
+                        && !matches!(e.kind, ErrorKind::Cancelled | ErrorKind::Unavailable) =>
+                {
+                    warn!("{e}; retrying ({attempt}/{attempts})");
+                    tokio::time::sleep(delay).await;
+                    delay = delay.saturating_mul(2);
+                    attempt += 1;
+                }
+                Err(e) => return Err(e),
+            }
+        }
     }
 
     /// Subscribes to a protocol channel.
     ///
-    /// Only subscribes if not already subscribed.
+    /// Only subscribes if not already subscribed on the current websocket.
+    /// If sending fails, the subscription stays pending and is retried by
+    /// [`resubscribe`](Self::resubscribe).
     ///
     /// # Arguments
     ///
@@ -2452,23 +4166,114 @@ impl Client {
     ///
     /// # Errors
     ///
-    /// Returns error if subscription message fails
+    /// Returns error naming the channel if subscription message fails
     async fn subscribe(&mut self, ident: Ident) -> Result<()> {
-        if !self.subscriptions.contains(&ident) {
-            let channel = self.channel(ident);
+        self.send_subscribe(ident, 1).await
+    }
+
+    /// Subscribes to a protocol channel, retrying when sending fails.
+    ///
+    /// Like [`subscribe`](Self::subscribe), but makes up to
+    /// [`SUBSCRIBE_ATTEMPTS`](Self::SUBSCRIBE_ATTEMPTS) attempts, so that a
+    /// single flaky send does not fail the whole connection.
+    ///
+    /// # Arguments
+    ///
+    /// * `ident` - Channel identifier
+    ///
+    /// # Errors
+    ///
+    /// Returns error naming the channel if all attempts fail
+    async fn subscribe_with_retry(&mut self, ident: Ident) -> Result<()> {
+        self.send_subscribe(ident, Self::SUBSCRIBE_ATTEMPTS).await
+    }
+
+    /// Subscribes to a protocol channel in up to `attempts` attempts.
+    ///
+    /// # Errors
+    ///
+    /// Returns error naming the channel if all attempts fail
+    async fn send_subscribe(&mut self, ident: Ident, attempts: u32) -> Result<()> {
+        self.reset_watchdog_tx();
+
+        let subscribe = Message::Subscribe {
+            channel: self.channel(ident),
+        };
+        let frame = Self::encode(&subscribe)?;
+
+        let tx = self
+            .websocket_tx
+            .as_mut()
+            .ok_or_else(|| Error::unavailable("websocket stream unavailable".to_string()))?;
+        Self::subscribe_on(
+            tx,
+            &mut self.subscriptions,
+            ident,
+            frame,
+            attempts,
+            Self::SUBSCRIBE_RETRY_DELAY,
+        )
+        .await
+    }
+
+    /// Sends a subscription frame on a websocket sink, if the channel is not
+    /// subscribed to yet.
+    ///
+    /// Records the subscription when sending succeeds. When all attempts
+    /// fail, the subscription stays pending.
+    ///
+    /// # Errors
+    ///
+    /// Returns error naming the channel if all attempts fail
+    async fn subscribe_on<S>(
+        tx: &mut S,
+        subscriptions: &mut Subscriptions,
+        ident: Ident,
+        frame: WebsocketMessage,
+        attempts: u32,
+        delay: Duration,
+    ) -> Result<()>
+    where
+        S: futures_util::Sink<WebsocketMessage> + Unpin,
+        Error: From<S::Error>,
+    {
+        if subscriptions.want(ident) {
+            Self::send_with_retry(tx, frame, attempts, delay)
+                .await
+                .map_err(|e| Error::new(e.kind, format!("error subscribing to {ident}: {e}")))?;
 
-            let subscribe = Message::Subscribe { channel };
-            self.send_message(subscribe).await?;
+            subscriptions.subscribed(ident);
+        }
+
+        Ok(())
+    }
 
-            self.subscriptions.insert(ident);
+    /// Handles a failure to subscribe to the stream channel when connecting.
+    ///
+    /// Playback progress is reported over the remote command channel, and
+    /// stream reports can be sent without subscribing. Only notices of
+    /// playback on other devices are not received. So when degraded mode is
+    /// allowed, the connection continues and the stream channel is no longer
+    /// wanted, so that it is not retried with the pending channels.
+    ///
+    /// # Errors
+    ///
+    /// Returns the subscription error if degraded mode is not allowed.
+    fn degrade(subscriptions: &mut Subscriptions, e: Error, allow_degraded: bool) -> Result<()> {
+        if !allow_degraded {
+            return Err(e);
         }
 
+        warn!("{e}; continuing without stream limitation notices");
+        subscriptions.unwant(Ident::Stream);
         Ok(())
     }
 
     /// Unsubscribes from a protocol channel.
     ///
-    /// Only unsubscribes if currently subscribed.
+    /// Only unsubscribes if subscribed on the current websocket. The channel
+    /// is no longer wanted even if sending fails, so it is not subscribed to
+    /// again.
     ///
     /// # Arguments
     ///
@@ -2478,18 +4283,41 @@ impl Client {
     ///
     /// Returns error if unsubscribe message fails
     async fn unsubscribe(&mut self, ident: Ident) -> Result<()> {
-        if self.subscriptions.contains(&ident) {
+        if self.subscriptions.unwant(ident) {
             let channel = self.channel(ident);
 
             let unsubscribe = Message::Unsubscribe { channel };
             self.send_message(unsubscribe).await?;
 
-            self.subscriptions.remove(&ident);
+            self.subscriptions.unsubscribed(ident);
         }
 
         Ok(())
     }
 
+    /// Subscribes to all pending channels.
+    ///
+    /// Pending channels are wanted but not subscribed to on the current
+    /// websocket, because a previous attempt failed or the websocket was
+    /// replaced.
+    ///
+    /// # Errors
+    ///
+    /// Returns the first error of any subscription message that fails, after
+    /// attempting all channels.
+    async fn resubscribe(&mut self) -> Result<()> {
+        let mut result = Ok(());
+        for ident in self.subscriptions.pending() {
+            if let Err(e) = self.subscribe(ident).await {
+                error!("{e}");
+                if result.is_ok() {
+                    result = Err(e);
+                }
+            }
+        }
+        result
+    }
+
     /// Returns current user ID.
     ///
     /// Returns unspecified ID if no user token available.
@@ -2531,3 +4359,384 @@ impl Client {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clamp(ratio: f32) -> Option<f32> {
+        Client::clamp_volume(Percentage::from_ratio(ratio)).map(|volume| volume.as_ratio())
+    }
+
+    #[test]
+    fn clamp_volume_keeps_volumes_in_range() {
+        for ratio in [0.0, 0.5, UNITY_GAIN] {
+            assert_eq!(clamp(ratio), None, "{ratio}");
+        }
+    }
+
+    #[test]
+    fn clamp_volume_clamps_volumes_out_of_range() {
+        assert_eq!(clamp(1.5), Some(UNITY_GAIN));
+        assert_eq!(clamp(f32::INFINITY), Some(UNITY_GAIN));
+        assert_eq!(clamp(-0.5), Some(0.0));
+        assert_eq!(clamp(f32::NEG_INFINITY), Some(0.0));
+    }
+
+    #[test]
+    fn clamp_volume_mutes_nan() {
+        assert_eq!(clamp(f32::NAN), Some(0.0));
+    }
+
+    #[test]
+    fn subscriptions_send_only_changes() {
+        let mut subscriptions = Subscriptions::default();
+        assert!(subscriptions.want(Ident::RemoteCommand));
+        subscriptions.subscribed(Ident::RemoteCommand);
+        assert!(!subscriptions.want(Ident::RemoteCommand));
+        assert_eq!(subscriptions.active(), [Ident::RemoteCommand]);
+
+        assert!(subscriptions.unwant(Ident::RemoteCommand));
+        subscriptions.unsubscribed(Ident::RemoteCommand);
+        assert!(!subscriptions.unwant(Ident::RemoteCommand));
+        assert!(subscriptions.active().is_empty());
+    }
+
+    #[test]
+    fn subscriptions_keep_failed_subscriptions_pending() {
+        let mut subscriptions = Subscriptions::default();
+        assert!(subscriptions.want(Ident::RemoteCommand));
+        assert!(subscriptions.want(Ident::RemoteDiscover));
+        subscriptions.subscribed(Ident::RemoteDiscover);
+        assert_eq!(subscriptions.pending(), [Ident::RemoteCommand]);
+
+        assert!(!subscriptions.unwant(Ident::RemoteCommand));
+        assert!(subscriptions.pending().is_empty());
+    }
+
+    #[test]
+    fn subscriptions_reset_makes_wanted_pending() {
+        let mut subscriptions = Subscriptions::default();
+        subscriptions.want(Ident::RemoteCommand);
+        subscriptions.subscribed(Ident::RemoteCommand);
+        subscriptions.want(Ident::RemoteDiscover);
+        subscriptions.subscribed(Ident::RemoteDiscover);
+        subscriptions.unwant(Ident::RemoteDiscover);
+        subscriptions.unsubscribed(Ident::RemoteDiscover);
+
+        subscriptions.reset();
+        assert!(subscriptions.active().is_empty());
+        assert_eq!(subscriptions.pending(), [Ident::RemoteCommand]);
+        assert!(!subscriptions.unwant(Ident::RemoteDiscover));
+    }
+
+    #[test]
+    fn near_end_within_lookahead() {
+        assert!(Client::near_end(0, 0, 1));
+        assert!(Client::near_end(10, 9, 1));
+        assert!(Client::near_end(10, 8, 2));
+        assert!(!Client::near_end(10, 8, 1));
+        assert!(Client::near_end(10, 12, 1));
+    }
+
+    #[test]
+    fn extend_deadline_throttles_within_interval() {
+        let now = tokio::time::Instant::now();
+        let interval = Duration::from_secs(10);
+        assert_eq!(Client::extend_deadline(None, interval, now), None);
+
+        let extended_at = now.checked_sub(Duration::from_secs(4)).unwrap();
+        assert_eq!(
+            Client::extend_deadline(Some(extended_at), interval, now),
+            Some(extended_at + interval)
+        );
+
+        let extended_at = now.checked_sub(interval).unwrap();
+        assert_eq!(
+            Client::extend_deadline(Some(extended_at), interval, now),
+            None
+        );
+    }
+
+    #[test]
+    fn extend_deadline_without_interval() {
+        let now = tokio::time::Instant::now();
+        assert_eq!(
+            Client::extend_deadline(Some(now), Duration::ZERO, now),
+            None
+        );
+    }
+
+    /// Websocket sink that records sends, flushes and closes.
+    ///
+    /// Sends fail with the queued errors, in order, before succeeding.
+    #[derive(Default)]
+    struct Recorder {
+        sent: u32,
+        errors: VecDeque<Error>,
+        flushed: bool,
+        closed: bool,
+        stalled: bool,
+    }
+
+    impl Recorder {
+        fn failing(errors: impl IntoIterator<Item = Error>) -> Self {
+            Self {
+                errors: errors.into_iter().collect(),
+                ..Self::default()
+            }
+        }
+    }
+
+    impl futures_util::Sink<WebsocketMessage> for Recorder {
+        type Error = Error;
+
+        fn poll_ready(
+            self: Pin<&mut Self>,
+            _: &mut std::task::Context<'_>,
+        ) -> std::task::Poll<Result<()>> {
+            std::task::Poll::Ready(Ok(()))
+        }
+
+        fn start_send(mut self: Pin<&mut Self>, _: WebsocketMessage) -> Result<()> {
+            self.sent += 1;
+            self.errors.pop_front().map_or(Ok(()), Err)
+        }
+
+        fn poll_flush(
+            mut self: Pin<&mut Self>,
+            _: &mut std::task::Context<'_>,
+        ) -> std::task::Poll<Result<()>> {
+            self.flushed = true;
+            if self.stalled {
+                std::task::Poll::Pending
+            } else {
+                std::task::Poll::Ready(Ok(()))
+            }
+        }
+
+        fn poll_close(
+            mut self: Pin<&mut Self>,
+            _: &mut std::task::Context<'_>,
+        ) -> std::task::Poll<Result<()>> {
+            self.closed = true;
+            std::task::Poll::Ready(Ok(()))
+        }
+    }
+
+    #[tokio::test]
+    async fn close_sink_flushes_and_closes() {
+        let mut tx = Recorder::default();
+        Client::close_sink(&mut tx, Client::NETWORK_TIMEOUT)
+            .await
+            .unwrap();
+        assert!(tx.flushed);
+        assert!(tx.closed);
+    }
+
+    #[tokio::test]
+    async fn close_sink_times_out() {
+        let mut tx = Recorder {
+            stalled: true,
+            ..Recorder::default()
+        };
+        let e = Client::close_sink(&mut tx, Duration::from_millis(10))
+            .await
+            .unwrap_err();
+        assert_eq!(e.kind, ErrorKind::DeadlineExceeded);
+        assert!(tx.flushed);
+        assert!(!tx.closed);
+    }
+
+    #[test]
+    fn remember_drops_echoes_of_sent_messages() {
+        let mut recent_messages = VecDeque::new();
+        assert!(Client::remember(&mut recent_messages, "close"));
+        assert!(!Client::remember(&mut recent_messages, "close"));
+        assert!(Client::remember(&mut recent_messages, "other"));
+    }
+
+    #[test]
+    fn remember_evicts_oldest_messages() {
+        let mut recent_messages = VecDeque::new();
+        for id in 0..=Client::RECENT_MESSAGES_MAX {
+            assert!(Client::remember(&mut recent_messages, &id.to_string()));
+        }
+        assert_eq!(recent_messages.len(), Client::RECENT_MESSAGES_MAX);
+        assert!(Client::remember(&mut recent_messages, "0"));
+        assert!(!Client::remember(
+            &mut recent_messages,
+            &Client::RECENT_MESSAGES_MAX.to_string()
+        ));
+    }
+
+    const RETRY_DELAY: Duration = Duration::from_millis(1);
+
+    fn subscribe_frame() -> WebsocketMessage {
+        WebsocketMessage::Text("subscribe".into())
+    }
+
+    fn write_buffer_full() -> Error {
+        Error::resource_exhausted("write buffer full".to_string())
+    }
+
+    #[tokio::test]
+    async fn subscribe_on_retries_transient_failure() {
+        let mut tx = Recorder::failing([write_buffer_full()]);
+        let mut subscriptions = Subscriptions::default();
+        Client::subscribe_on(
+            &mut tx,
+            &mut subscriptions,
+            Ident::Stream,
+            subscribe_frame(),
+            Client::SUBSCRIBE_ATTEMPTS,
+            RETRY_DELAY,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(tx.sent, 2);
+        assert_eq!(subscriptions.active(), [Ident::Stream]);
+        assert!(subscriptions.pending().is_empty());
+    }
+
+    #[tokio::test]
+    async fn subscribe_on_fails_after_exhausting_retries() {
+        let mut tx =
+            Recorder::failing((0..Client::SUBSCRIBE_ATTEMPTS).map(|_| write_buffer_full()));
+        let mut subscriptions = Subscriptions::default();
+        let e = Client::subscribe_on(
+            &mut tx,
+            &mut subscriptions,
+            Ident::RemoteDiscover,
+            subscribe_frame(),
+            Client::SUBSCRIBE_ATTEMPTS,
+            RETRY_DELAY,
+        )
+        .await
+        .unwrap_err();
+
+        assert_eq!(tx.sent, Client::SUBSCRIBE_ATTEMPTS);
+        assert_eq!(e.kind, ErrorKind::ResourceExhausted);
+        assert!(e
+            .to_string()
+            .contains(&format!("error subscribing to {}", Ident::RemoteDiscover)));
+        assert_eq!(subscriptions.pending(), [Ident::RemoteDiscover]);
+    }
+
+    #[tokio::test]
+    async fn subscribe_on_does_not_retry_closed_websocket() {
+        let mut tx = Recorder::failing([Error::cancelled("connection closed")]);
+        let mut subscriptions = Subscriptions::default();
+        let e = Client::subscribe_on(
+            &mut tx,
+            &mut subscriptions,
+            Ident::Stream,
+            subscribe_frame(),
+            Client::SUBSCRIBE_ATTEMPTS,
+            RETRY_DELAY,
+        )
+        .await
+        .unwrap_err();
+
+        assert_eq!(tx.sent, 1);
+        assert_eq!(e.kind, ErrorKind::Cancelled);
+    }
+
+    #[tokio::test]
+    async fn subscribe_on_skips_active_subscription() {
+        let mut tx = Recorder::default();
+        let mut subscriptions = Subscriptions::default();
+        subscriptions.want(Ident::Stream);
+        subscriptions.subscribed(Ident::Stream);
+        Client::subscribe_on(
+            &mut tx,
+            &mut subscriptions,
+            Ident::Stream,
+            subscribe_frame(),
+            1,
+            RETRY_DELAY,
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(tx.sent, 0);
+    }
+
+    #[tokio::test]
+    async fn degrade_continues_without_stream_when_allowed() {
+        for allow_degraded in [false, true] {
+            let mut tx =
+                Recorder::failing((0..Client::SUBSCRIBE_ATTEMPTS).map(|_| write_buffer_full()));
+            let mut subscriptions = Subscriptions::default();
+            let e = Client::subscribe_on(
+                &mut tx,
+                &mut subscriptions,
+                Ident::Stream,
+                subscribe_frame(),
+                Client::SUBSCRIBE_ATTEMPTS,
+                RETRY_DELAY,
+            )
+            .await
+            .unwrap_err();
+
+            let result = Client::degrade(&mut subscriptions, e, allow_degraded);
+            if allow_degraded {
+                assert!(result.is_ok());
+                assert!(subscriptions.pending().is_empty());
+            } else {
+                assert_eq!(result.unwrap_err().kind, ErrorKind::ResourceExhausted);
+                assert_eq!(subscriptions.pending(), [Ident::Stream]);
+            }
+        }
+    }
+
+    fn shuffled_list(len: usize, tracks_order: Vec<u32>) -> queue::List {
+        queue::List {
+            tracks: (0..len)
+                .map(|id| queue::Track {
+                    id: id.to_string(),
+                    ..Default::default()
+                })
+                .collect(),
+            tracks_order,
+            shuffled: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn shuffle_order_of_matching_permutation() {
+        let list = shuffled_list(3, vec![2, 0, 1]);
+        assert_eq!(Client::shuffle_order(&list), Some(&[2, 0, 1][..]));
+
+        // `tracks[1]` plays first, `tracks[2]` second and `tracks[0]` last.
+        assert_eq!(Client::playback_order(&list), Some(vec![1, 2, 0]));
+    }
+
+    #[test]
+    fn shuffle_order_of_unshuffled_queue() {
+        let mut list = shuffled_list(3, vec![2, 0, 1]);
+        list.shuffled = false;
+        assert_eq!(Client::shuffle_order(&list), None);
+        assert_eq!(Client::playback_order(&list), None);
+    }
+
+    #[test]
+    fn shuffle_order_of_mismatched_length_falls_back_to_identity() {
+        for tracks_order in [vec![], vec![1, 0], vec![2, 0, 1, 3]] {
+            let list = shuffled_list(3, tracks_order);
+            assert_eq!(Client::shuffle_order(&list), None);
+            assert_eq!(Client::playback_order(&list), None);
+        }
+    }
+
+    #[test]
+    fn shuffle_order_with_invalid_positions_falls_back_to_identity() {
+        for tracks_order in [vec![0, 1, 3], vec![0, 1, u32::MAX], vec![0, 1, 1]] {
+            let list = shuffled_list(3, tracks_order);
+            assert_eq!(Client::shuffle_order(&list), None);
+            assert_eq!(Client::playback_order(&list), None);
+        }
+    }
+}
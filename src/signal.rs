@@ -4,6 +4,8 @@
 //! * Unix: SIGTERM, SIGHUP, and Ctrl-C (SIGINT)
 //! * Windows: Ctrl-C only
 //!
+//! On Unix, SIGUSR1 requests an export of the queue, see [`ExportHandler`].
+//!
 //! # Example
 //!
 //! ```no_run
@@ -110,6 +112,47 @@ impl Handler {
     }
 }
 
+/// Handles requests to export the queue.
+///
+/// On Unix, requests are sent with SIGUSR1. On Windows, there is no signal
+/// to request an export with, and [`recv`](Self::recv) never returns.
+pub struct ExportHandler {
+    #[cfg(unix)]
+    sigusr1: Signal,
+}
+
+impl ExportHandler {
+    /// Creates a new export request handler.
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the signal handler cannot be registered.
+    pub fn new() -> Result<Self> {
+        #[cfg(unix)]
+        {
+            Ok(Self {
+                sigusr1: signal(SignalKind::user_defined1())?,
+            })
+        }
+
+        #[cfg(not(unix))]
+        Ok(Self {})
+    }
+
+    /// Waits for the next export request.
+    pub async fn recv(&mut self) {
+        #[cfg(unix)]
+        {
+            if self.sigusr1.recv().await.is_none() {
+                std::future::pending::<()>().await;
+            }
+        }
+
+        #[cfg(not(unix))]
+        std::future::pending::<()>().await;
+    }
+}
+
 /// Formats the shutdown signal in a human-readable form.
 ///
 /// Returns:
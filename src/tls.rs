@@ -0,0 +1,100 @@
+//! TLS trust configuration with custom certificate authorities.
+//!
+//! By default, server certificates are validated against the certificate
+//! authorities of the operating system. In networks with TLS-intercepting
+//! proxies, like many corporate networks, connections then fail validation.
+//! This module loads additional certificate authorities from a PEM file, to
+//! be trusted alongside those of the operating system.
+//!
+//! Custom certificate authorities are added to the trust store: validation is
+//! never disabled. They are used by the HTTP clients for the gateway and
+//! downloads, and for the websocket connection.
+//!
+//! # Example
+//!
+//! ```rust
+//! use pleezer::tls;
+//!
+//! let certificates = tls::load_certificates("corporate-ca.pem".as_ref())?;
+//! let connector = tls::websocket_connector(&certificates)?;
+//! ```
+
+use std::{fs::File, io::BufReader, path::Path, sync::Arc};
+
+use rustls::{pki_types::CertificateDer, ClientConfig, RootCertStore};
+use tokio_tungstenite::Connector;
+
+use crate::error::{Error, Result};
+
+/// Loads certificate authorities from a PEM file.
+///
+/// The file may contain multiple certificates, for example a certificate
+/// chain or bundle. Other PEM sections, like private keys, are ignored.
+///
+/// # Arguments
+///
+/// * `path` - PEM file with one or more certificates
+///
+/// # Errors
+///
+/// Returns error if:
+/// * The file cannot be read
+/// * The file is not valid PEM
+/// * The file contains no certificates
+pub fn load_certificates(path: &Path) -> Result<Vec<CertificateDer<'static>>> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let certificates = rustls_pemfile::certs(&mut reader)
+        .collect::<std::result::Result<Vec<_>, _>>()
+        .map_err(|e| {
+            Error::invalid_argument(format!(
+                "{} is not a valid PEM file: {e}",
+                path.to_string_lossy()
+            ))
+        })?;
+
+    if certificates.is_empty() {
+        return Err(Error::invalid_argument(format!(
+            "{} contains no PEM certificates",
+            path.to_string_lossy()
+        )));
+    }
+
+    Ok(certificates)
+}
+
+/// Creates a websocket TLS connector that trusts additional certificates.
+///
+/// Trusts the certificate authorities of the operating system, like the
+/// default connector, and those in `certificates`. Operating system
+/// certificates that fail to load are logged and skipped.
+///
+/// # Arguments
+///
+/// * `certificates` - Additional certificate authorities to trust
+///
+/// # Errors
+///
+/// Returns error if a certificate is not a valid certificate authority.
+pub fn websocket_connector(certificates: &[CertificateDer<'static>]) -> Result<Connector> {
+    let mut roots = RootCertStore::empty();
+
+    let native = rustls_native_certs::load_native_certs();
+    for e in native.errors {
+        warn!("error loading system certificate: {e}");
+    }
+    let (_, ignored) = roots.add_parsable_certificates(native.certs);
+    if ignored > 0 {
+        debug!("ignored {ignored} unparsable system certificates");
+    }
+
+    for certificate in certificates {
+        roots
+            .add(certificate.clone())
+            .map_err(|e| Error::invalid_argument(format!("invalid certificate authority: {e}")))?;
+    }
+
+    let config = ClientConfig::builder()
+        .with_root_certificates(roots)
+        .with_no_client_auth();
+    Ok(Connector::Rustls(Arc::new(config)))
+}
@@ -96,10 +96,14 @@ use std::{
 };
 
 use stream_download::{
-    self, http::HttpStream, source::SourceStream, storage::StorageProvider, StreamDownload,
-    StreamHandle, StreamPhase, StreamState,
+    self,
+    http::{HttpStream, HttpStreamError},
+    source::SourceStream,
+    storage::StorageProvider,
+    StreamDownload, StreamHandle, StreamPhase, StreamState,
 };
 use time::OffsetDateTime;
+use tokio::sync::OwnedSemaphorePermit;
 use url::Url;
 use veil::Redact;
 
@@ -206,6 +210,20 @@ impl FromStr for TrackType {
     }
 }
 
+/// Sub-range of a track to play instead of the whole track.
+///
+/// For previews or custom excerpts: playback starts at the start offset and
+/// advances to the next track after the clip duration. Progress and duration
+/// are reported relative to the clip.
+#[derive(Copy, Clone, Debug, Hash, PartialEq, Eq)]
+pub struct Clip {
+    /// Offset into the track to start playing at.
+    pub start: Duration,
+
+    /// Length to play from the start offset.
+    pub duration: Duration,
+}
+
 /// Represents a Deezer track with metadata and download/buffering state.
 ///
 /// Combines track metadata (title, artist, etc) with:
@@ -292,6 +310,10 @@ pub struct Track {
     /// Protected by mutex for concurrent access from download task.
     buffered: Arc<Mutex<Option<Duration>>>,
 
+    /// Error of a download that completed before receiving the whole file.
+    /// Set from the download task, and taken by [`check_download`](Self::check_download).
+    truncated: Arc<Mutex<Option<Error>>>,
+
     /// Total size of the audio file in bytes.
     /// Available only after download begins.
     /// Not available for livestreams.
@@ -335,6 +357,14 @@ pub struct Track {
     /// Set by player after decoder initialization.
     pub channels: Option<u16>,
 
+    /// Duration of the decoded audio stream, if the decoder knows it.
+    /// Set by player after decoder initialization.
+    pub decoded_duration: Option<Duration>,
+
+    /// Sub-range of the track to play, if not the whole track.
+    /// Ignored for livestreams, which have no fixed duration.
+    pub clip: Option<Clip>,
+
     /// Fallback track to use when primary track is unavailable.
     /// * Contains complete track metadata
     /// * Used for alternative versions of same song
@@ -372,8 +402,9 @@ struct StreamUrl {
 pub enum MediumType {
     /// Medium for the primary requested track
     Primary(Medium),
-    /// Medium for the fallback version when primary is unavailable
-    Fallback(Medium),
+    /// Medium for the fallback version when primary is unavailable,
+    /// and the depth of that version in the fallback chain (starting at 1)
+    Fallback(Medium, usize),
 }
 
 /// Provides direct access to the underlying `Medium` regardless of variant.
@@ -399,7 +430,7 @@ impl Deref for MediumType {
     #[inline]
     fn deref(&self) -> &Self::Target {
         match self {
-            Self::Primary(medium) | Self::Fallback(medium) => medium,
+            Self::Primary(medium) | Self::Fallback(medium, _) => medium,
         }
     }
 }
@@ -424,6 +455,19 @@ impl Track {
     /// Value of 60KB matches official client behavior.
     const PREFETCH_DEFAULT: usize = 60 * 1024;
 
+    /// Base URL of cover art images on the Deezer CDN.
+    const COVER_ENDPOINT: &'static str = "https://cdn-images.dzcdn.net/images";
+
+    /// Maximum resolution of cover art images in pixels.
+    pub const COVER_RESOLUTION_MAX: u16 = 1920;
+
+    /// Maximum difference between metadata and decoded durations that is
+    /// attributed to rounding.
+    ///
+    /// Metadata durations are in whole seconds, so smaller differences are
+    /// expected and do not warrant correction.
+    const DURATION_TOLERANCE: Duration = Duration::from_secs(1);
+
     /// Returns the track's unique identifier.
     #[must_use]
     #[inline]
@@ -441,6 +485,60 @@ impl Track {
         self.duration
     }
 
+    /// Returns the duration to report for playback.
+    ///
+    /// Prefers the decoded duration when it differs from the metadata
+    /// duration by more than rounding: the metadata is occasionally wrong,
+    /// which would make progress bars of controllers end early or late.
+    /// Falls back to the metadata duration when the decoded duration is
+    /// unknown, for example before the track is loaded.
+    ///
+    /// For clipped tracks, returns the clip duration, limited to the part
+    /// of the track after the clip start.
+    ///
+    /// Never returns a zero duration, so that it is safe to divide by.
+    #[must_use]
+    pub fn playback_duration(&self) -> Option<Duration> {
+        let duration = match (self.duration, self.decoded_duration) {
+            (Some(metadata), Some(decoded))
+                if metadata.abs_diff(decoded) <= Self::DURATION_TOLERANCE =>
+            {
+                Some(metadata)
+            }
+            (metadata, decoded) => decoded.or(metadata),
+        };
+
+        let duration = match self.clip() {
+            Some(clip) => Some(duration.map_or(clip.duration, |duration| {
+                clip.duration.min(duration.saturating_sub(clip.start))
+            })),
+            None => duration,
+        };
+        duration.filter(|duration| !duration.is_zero())
+    }
+
+    /// Returns whether playback reached the end of the clip.
+    ///
+    /// `elapsed` is the playback position relative to the clip start. Always
+    /// returns `false` for tracks that are not clipped, as those end when
+    /// their audio does.
+    #[must_use]
+    pub fn is_clip_end(&self, elapsed: Duration) -> bool {
+        self.clip().is_some()
+            && self
+                .playback_duration()
+                .is_some_and(|duration| elapsed >= duration)
+    }
+
+    /// Returns the sub-range of the track to play, if clipped.
+    ///
+    /// Livestreams are never clipped.
+    #[must_use]
+    #[inline]
+    pub fn clip(&self) -> Option<Clip> {
+        self.clip.filter(|_| !self.is_livestream())
+    }
+
     /// Returns whether this content is accessible.
     ///
     /// Always true for songs. Episodes and livestreams may be
@@ -509,6 +607,37 @@ impl Track {
         &self.cover_id
     }
 
+    /// Returns the URL of the cover art in JPEG format.
+    ///
+    /// Builds the CDN URL from the cover art identifier, using the image
+    /// path that matches the content type.
+    ///
+    /// # Arguments
+    ///
+    /// * `resolution` - Width and height in pixels, clamped to
+    ///   [`COVER_RESOLUTION_MAX`](Self::COVER_RESOLUTION_MAX)
+    ///
+    /// Returns `None` if the track has no cover art.
+    #[must_use]
+    pub fn cover_url(&self, resolution: u16) -> Option<Url> {
+        if self.cover_id.is_empty() {
+            return None;
+        }
+
+        let path = match self.typ {
+            TrackType::Episode => "talk",
+            TrackType::Song | TrackType::Livestream => "cover",
+        };
+        let resolution = resolution.min(Self::COVER_RESOLUTION_MAX);
+        format!(
+            "{}/{path}/{}/{resolution}x{resolution}.jpg",
+            Self::COVER_ENDPOINT,
+            self.cover_id
+        )
+        .parse()
+        .ok()
+    }
+
     /// Returns the track's expiration time.
     ///
     /// After this time, the track becomes unavailable for download
@@ -519,6 +648,22 @@ impl Track {
         self.expiry
     }
 
+    /// Updates the track token and its expiration time.
+    ///
+    /// Used to refresh tokens of queued tracks before they expire,
+    /// without rebuilding the track and losing its download state.
+    /// Ignores list data of a different track.
+    pub fn refresh_token(&mut self, item: &gateway::ListData) {
+        if item.id() != self.id {
+            return;
+        }
+
+        if let Some(track_token) = item.track_token() {
+            self.track_token = Some(track_token.to_owned());
+        }
+        self.expiry = item.expiry();
+    }
+
     /// Returns whether this is a livestream.
     ///
     /// Livestreams have different behaviors:
@@ -660,6 +805,20 @@ impl Track {
     /// API endpoint for retrieving media sources.
     const MEDIA_ENDPOINT: &'static str = "v1/get_url";
 
+    /// Maximum depth of the fallback chain to request media for.
+    ///
+    /// Fallbacks can be nested. This caps how many are tried, in case a
+    /// catalog has long or repeating chains.
+    const FALLBACK_DEPTH_MAX: usize = 4;
+
+    /// Returns whether a URL points to an HLS playlist.
+    fn is_hls(url: &Url) -> bool {
+        url.path()
+            .rsplit('.')
+            .next()
+            .is_some_and(|extension| extension.eq_ignore_ascii_case("m3u8"))
+    }
+
     fn get_external_medium(&self, quality: AudioQuality) -> Result<MediumType> {
         let external_url = self.external_url.as_ref().ok_or_else(|| {
             Error::unavailable(format!("external {} {self} has no urls", self.typ))
@@ -667,23 +826,34 @@ impl Track {
 
         let sources = match external_url {
             ExternalUrl::Direct(url) => {
+                // HLS playlists list segments that would have to be fetched
+                // one by one, often in MPEG-TS containers that the decoder
+                // does not support. Fail early instead of trying to decode
+                // the playlist itself as audio.
+                if Self::is_hls(url) {
+                    return Err(Error::unimplemented(format!(
+                        "{} {self} is an HLS stream, which is not supported yet",
+                        self.typ
+                    )));
+                }
+
                 vec![media::Source {
                     url: url.clone(),
                     provider: String::default(),
                 }]
             }
             ExternalUrl::WithQuality(codec_urls) => {
-                // Filter out sources that are of higher quality than requested.
+                // Prefer the highest bitrate up to the requested quality, and
+                // fall back to higher bitrates only if there are no lower ones.
                 let mut urls = Vec::new();
-                for (bitrate, codec_url) in codec_urls.sort_by_bitrate().into_iter().rev() {
-                    if quality.bitrate().is_none_or(|kbps| bitrate <= kbps) {
-                        // Prefer AAC over MP3 if both are available for the same bitrate.
-                        if let Some(url) = codec_url.aac.or(codec_url.mp3) {
-                            urls.push(media::Source {
-                                url,
-                                provider: String::default(),
-                            });
-                        }
+                for (bitrate, codec_url) in codec_urls.by_preference(quality.bitrate()) {
+                    // Prefer AAC over MP3 if both are available for the same bitrate.
+                    if let Some(url) = codec_url.aac.or(codec_url.mp3) {
+                        trace!("livestream source candidate: {bitrate} kbps");
+                        urls.push(media::Source {
+                            url,
+                            provider: String::default(),
+                        });
                     }
                 }
                 urls
@@ -744,6 +914,10 @@ impl Track {
     /// exists and has available media, returns `MediumType::Fallback`. The
     /// track's metadata will be swapped with the fallback version when
     /// playback begins.
+    ///
+    /// Fallbacks can be nested, for example in heavily geo-restricted
+    /// catalogs. The chain is walked up to [`Self::FALLBACK_DEPTH_MAX`]
+    /// versions deep, and the first available version is chosen.
     pub async fn get_medium(
         &self,
         client: &http::Client,
@@ -776,11 +950,23 @@ impl Track {
             Error::permission_denied(format!("{} {self} does not have a track token", self.typ))
         })?;
 
+        // Walk the fallback chain. Stop at a version without a track token,
+        // because the response is matched to the chain by position.
         let mut track_tokens = vec![track_token.to_owned()];
-        if let Some(fallback) = &self.fallback {
-            if let Some(fallback_token) = fallback.track_token.as_ref() {
-                track_tokens.push(fallback_token.to_owned());
+        let mut track_ids = vec![self.id];
+        let mut fallback = self.fallback.as_deref();
+        while let Some(track) = fallback {
+            if track_tokens.len() > Self::FALLBACK_DEPTH_MAX || track_ids.contains(&track.id) {
+                break;
             }
+
+            let Some(fallback_token) = track.track_token.as_ref() else {
+                break;
+            };
+
+            track_tokens.push(fallback_token.to_owned());
+            track_ids.push(track.id);
+            fallback = track.fallback.as_deref();
         }
 
         let cipher_formats = match quality {
@@ -818,13 +1004,17 @@ impl Track {
         // Find the first media source that is available.
         // There are as many media objects as there are track tokens.
         let mut result = None;
-        for i in 0..items.data.len() {
+        for (i, track_id) in track_ids.iter().enumerate().take(items.data.len()) {
             if let Data::Media { media } = &items.data[i] {
                 if let Some(medium) = media.first().cloned() {
                     let medium_type = if i == 0 {
                         MediumType::Primary(medium)
                     } else {
-                        MediumType::Fallback(medium)
+                        debug!(
+                            "{} {self} is unavailable, using fallback {track_id} at depth {i}",
+                            self.typ
+                        );
+                        MediumType::Fallback(medium, i)
                     };
                     result = Some(medium_type);
                     break;
@@ -891,12 +1081,21 @@ impl Track {
     /// * Network error occurs
     /// * HTTP response status is not successful (not 2xx)
     /// * Download cannot start
+    ///
+    /// Sources returning an empty body are skipped. When all sources do,
+    /// returns a `DataLoss` error, which callers may retry.
+    ///
+    /// Episodes may be forbidden in the region of the user, which their
+    /// metadata does not tell. When an episode source refuses the download
+    /// with HTTP 403 Forbidden, returns a `PermissionDenied` error instead.
     async fn open_stream(&self, client: &http::Client, medium: &Medium) -> Result<StreamUrl> {
         let now = SystemTime::now();
 
         // Deezer usually returns multiple sources for a track. The official
         // client seems to always use the first one. We start with the first
         // and continue with the next one if the first one fails to start.
+        let mut empty = false;
+        let mut forbidden = false;
         for source in &medium.sources {
             // URLs can theoretically be non-HTTP, and we only support HTTP(S) URLs.
             let Some(host_str) = source.url.host_str() else {
@@ -931,6 +1130,13 @@ impl Track {
 
             // Perform the request and stream the response.
             match HttpStream::new(client.unlimited.clone(), source.url.clone()).await {
+                Ok(stream) if stream.content_length() == Some(0) => {
+                    // Some CDN nodes return a successful response without content,
+                    // which the decoder would fail on obscurely.
+                    warn!("empty response for {} {self} from {host_str}", self.typ);
+                    empty = true;
+                    continue;
+                }
                 Ok(stream) => {
                     debug!("starting download of {} {self} from {host_str}", self.typ);
                     return Ok(StreamUrl {
@@ -943,17 +1149,54 @@ impl Track {
                         "failed to start download of {} {self} from {host_str}: {err}",
                         self.typ
                     );
+
+                    // The stream error does not expose the response status,
+                    // so probe the source to tell a regional block apart.
+                    if self.typ == TrackType::Episode
+                        && matches!(err, HttpStreamError::ResponseFailure(_))
+                        && Self::is_forbidden(client, &source.url).await
+                    {
+                        forbidden = true;
+                    }
                     continue;
                 }
             };
         }
 
+        if forbidden {
+            return Err(Error::permission_denied(format!(
+                "{} {self} is not available in your region",
+                self.typ
+            )));
+        }
+
+        if empty {
+            return Err(Error::data_loss(format!(
+                "empty response for {} {self} from all sources",
+                self.typ
+            )));
+        }
+
         Err(Error::unavailable(format!(
             "no valid sources found for {} {self}",
             self.typ
         )))
     }
 
+    /// Returns whether a source refuses downloads with HTTP 403 Forbidden.
+    ///
+    /// Requests only the first byte, to keep the probe lightweight. Network
+    /// errors are not considered forbidden.
+    async fn is_forbidden(client: &http::Client, url: &Url) -> bool {
+        client
+            .unlimited
+            .get(url.clone())
+            .header(reqwest::header::RANGE, "bytes=0-0")
+            .send()
+            .await
+            .is_ok_and(|response| response.status() == reqwest::StatusCode::FORBIDDEN)
+    }
+
     fn init_download(&mut self, url: &Url) {
         // Determine the codec and bitrate of the track.
         if let Some(ExternalUrl::WithQuality(urls)) = &self.external_url {
@@ -1024,6 +1267,8 @@ impl Track {
     /// * `client` - HTTP client for download
     /// * `medium` - Media source information
     /// * `storage` - Storage provider with prefetch buffer
+    /// * `permit` - Download slot that is held until the download completes
+    ///   or is cancelled, bounding the number of concurrent downloads
     ///
     /// # Returns
     ///
@@ -1036,10 +1281,11 @@ impl Track {
     /// # Fallback Handling
     ///
     /// If a fallback medium is provided, the track's metadata will be swapped
-    /// with its fallback version before download begins. This ensures the
-    /// playing track matches the actual content being downloaded.
+    /// with the fallback version at its depth in the fallback chain before
+    /// download begins. This ensures the playing track matches the actual
+    /// content being downloaded.
     ///
-    /// The original track metadata is preserved in the fallback field and can
+    /// The original track metadata is preserved in the fallback chain and can
     /// be restored if needed.
     ///
     /// # Errors
@@ -1049,6 +1295,7 @@ impl Track {
     /// * Track unavailable
     /// * Network error occurs
     /// * Download cannot start
+    /// * All sources return an empty body (`DataLoss`)
     ///
     /// # Progress Tracking
     ///
@@ -1066,6 +1313,7 @@ impl Track {
         client: &http::Client,
         medium: &MediumType,
         storage: P,
+        permit: OwnedSemaphorePermit,
     ) -> Result<AudioFile>
     where
         P: StorageProvider + Sync + 'static,
@@ -1073,8 +1321,13 @@ impl Track {
     {
         let medium = match medium {
             MediumType::Primary(medium) => medium,
-            MediumType::Fallback(medium) => {
-                if let Some(fallback) = &mut self.fallback {
+            MediumType::Fallback(medium, depth) => {
+                let mut fallback = self.fallback.as_deref_mut();
+                for _ in 1..*depth {
+                    fallback = fallback.and_then(|track| track.fallback.as_deref_mut());
+                }
+
+                if let Some(fallback) = fallback {
                     warn!("falling back {} {} to {fallback}", self.typ, self.id);
                     std::mem::swap(&mut self.id, &mut fallback.id);
                     std::mem::swap(&mut self.artist, &mut fallback.artist);
@@ -1098,6 +1351,41 @@ impl Track {
         self.quality = medium.format.into();
         self.cipher = medium.cipher.typ;
 
+        self.download(stream, &url, storage, permit).await
+    }
+
+    /// Downloads an opened stream in the background.
+    ///
+    /// Tracks the download progress, and records a truncated download for
+    /// [`check_download`](Self::check_download) when it completes.
+    ///
+    /// # Arguments
+    ///
+    /// * `stream` - Opened HTTP stream of the track
+    /// * `url` - Source URL of the stream
+    /// * `storage` - Storage provider with prefetch buffer
+    /// * `permit` - Download slot that is held until the download completes
+    ///   or is cancelled
+    ///
+    /// # Errors
+    ///
+    /// Returns error if the download cannot start.
+    ///
+    /// # Panics
+    ///
+    /// * When the buffered duration mutex is poisoned in the progress callback
+    /// * When duration calculation overflows during progress calculation
+    async fn download<P>(
+        &mut self,
+        stream: HttpStream<reqwest::Client>,
+        url: &Url,
+        storage: P,
+        permit: OwnedSemaphorePermit,
+    ) -> Result<AudioFile>
+    where
+        P: StorageProvider + Sync + 'static,
+        P::Reader: Sync,
+    {
         // Set the file size if known. This is used to calculate the prefetch size.
         if let Some(file_size) = stream.content_length() {
             info!("downloading {file_size} bytes for {} {self}", self.typ);
@@ -1106,7 +1394,7 @@ impl Track {
             info!("downloading {} {self} with unknown file size", self.typ);
         }
 
-        self.init_download(&url);
+        self.init_download(url);
 
         // Calculate the prefetch size based on the bitrate and duration.
         let prefetch_size = self.prefetch_size();
@@ -1120,19 +1408,34 @@ impl Track {
         let track_typ = self.typ.to_string();
         let duration = self.duration;
         let buffered = Arc::clone(&self.buffered);
+        let truncated = Arc::clone(&self.truncated);
         let file_size = self.file_size;
+        let permit = Mutex::new(Some(permit));
         let callback = move |_: &HttpStream<_>,
                              stream: StreamState,
                              _: &tokio_util::sync::CancellationToken| {
             match stream.phase {
                 StreamPhase::Complete => {
-                    info!("completed download of {track_typ} {track_str}");
+                    match Self::check_complete(stream.current_position, file_size) {
+                        Ok(()) => info!("completed download of {track_typ} {track_str}"),
+                        Err(e) => {
+                            // The decoder reaches the end of the data early, so the
+                            // track ends before its time. Leave it to the player to
+                            // retry when it does.
+                            error!("{e} for {track_typ} {track_str}");
+                            *truncated.lock().unwrap() = Some(e);
+                        }
+                    }
 
                     // Prevent rounding errors and set the buffered duration
                     // equal to the total duration. It's OK to unwrap here: if
                     // the mutex is poisoned, then the main thread panicked and
                     // we should propagate the error.
                     *buffered.lock().unwrap() = duration;
+
+                    // Release the download slot. When the download is cancelled instead,
+                    // the permit is released when the callback is dropped.
+                    drop(permit.lock().unwrap().take());
                 }
                 StreamPhase::Downloading { .. } => {
                     if let Some(file_size) = file_size {
@@ -1222,6 +1525,42 @@ impl Track {
         self.handle = None;
         self.file_size = None;
         *self.buffered.lock().unwrap() = None;
+        *self.truncated.lock().unwrap() = None;
+    }
+
+    /// Checks whether a completed download received the whole file.
+    ///
+    /// Downloads of unknown size are always considered complete.
+    ///
+    /// # Errors
+    ///
+    /// Returns `DataLoss` if the download ended at `position`, before
+    /// reaching `file_size`.
+    fn check_complete(position: u64, file_size: Option<u64>) -> Result<()> {
+        match file_size {
+            Some(file_size) if position < file_size => Err(Error::data_loss(format!(
+                "download truncated at {position} of {file_size} bytes"
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks whether the download of this track was truncated.
+    ///
+    /// A truncated download makes the track end before its time. The
+    /// truncation is reported only once, so that a retried download starts
+    /// with a clean slate.
+    ///
+    /// # Errors
+    ///
+    /// Returns `DataLoss` if the download completed before receiving the
+    /// whole file.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the truncation lock is poisoned.
+    pub fn check_download(&self) -> Result<()> {
+        self.truncated.lock().unwrap().take().map_or(Ok(()), Err)
     }
 
     /// Returns the total file size if known.
@@ -1333,7 +1672,9 @@ impl From<gateway::ListData> for Track {
         };
 
         let (available, external, external_url, fallback) = match &item {
-            gateway::ListData::Song { fallback, .. } => (true, false, None, fallback.clone()),
+            gateway::ListData::Song { fallback, .. } => {
+                (!item.is_unavailable_song(), false, None, fallback.clone())
+            }
             gateway::ListData::Episode {
                 available,
                 external,
@@ -1372,6 +1713,7 @@ impl From<gateway::ListData> for Track {
             expiry: item.expiry(),
             quality: AudioQuality::Unknown,
             buffered: Arc::new(Mutex::new(None)),
+            truncated: Arc::new(Mutex::new(None)),
             file_size: None,
             cipher: Cipher::BF_CBC_STRIPE,
             handle: None,
@@ -1383,6 +1725,8 @@ impl From<gateway::ListData> for Track {
             sample_rate: None,
             bits_per_sample: None,
             channels: None,
+            decoded_duration: None,
+            clip: None,
             fallback: fallback.map(|boxed| Box::new((*boxed).into())),
         }
     }
@@ -1435,3 +1779,226 @@ fn find_codec_bitrate(haystack: &LivestreamUrls, needle: &Url) -> Option<(Codec,
 
     None
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use stream_download::storage::memory::MemoryStorageProvider;
+
+    use crate::{error::ErrorKind, player::Player, protocol::gateway::ListData};
+
+    fn song(duration: Duration) -> Track {
+        let mut track = Track::from(ListData::unavailable_song(TrackId::new(1).unwrap()));
+        track.duration = Some(duration);
+        track
+    }
+
+    fn clip(start: u64, duration: u64) -> Option<Clip> {
+        Some(Clip {
+            start: Duration::from_secs(start),
+            duration: Duration::from_secs(duration),
+        })
+    }
+
+    #[test]
+    fn playback_duration_prefers_metadata_within_tolerance() {
+        let mut track = song(Duration::from_secs(180));
+        assert_eq!(track.playback_duration(), Some(Duration::from_secs(180)));
+
+        for decoded in [179_000, 180_400, 181_000] {
+            track.decoded_duration = Some(Duration::from_millis(decoded));
+            assert_eq!(
+                track.playback_duration(),
+                Some(Duration::from_secs(180)),
+                "{decoded}"
+            );
+        }
+    }
+
+    #[test]
+    fn playback_duration_prefers_decoded_beyond_tolerance() {
+        let mut track = song(Duration::from_secs(180));
+        for decoded in [178_999, 181_001, 30_000] {
+            track.decoded_duration = Some(Duration::from_millis(decoded));
+            assert_eq!(
+                track.playback_duration(),
+                Some(Duration::from_millis(decoded)),
+                "{decoded}"
+            );
+        }
+
+        track.duration = None;
+        assert_eq!(track.playback_duration(), Some(Duration::from_secs(30)));
+    }
+
+    #[test]
+    fn playback_duration_of_clip() {
+        let mut track = song(Duration::from_secs(180));
+        track.clip = clip(30, 60);
+        assert_eq!(track.playback_duration(), Some(Duration::from_secs(60)));
+    }
+
+    #[test]
+    fn playback_duration_of_clip_past_track_end() {
+        let mut track = song(Duration::from_secs(180));
+        track.clip = clip(150, 60);
+        assert_eq!(track.playback_duration(), Some(Duration::from_secs(30)));
+
+        track.clip = clip(180, 60);
+        assert_eq!(track.playback_duration(), None);
+    }
+
+    #[test]
+    fn playback_stops_at_clip_end() {
+        let mut track = song(Duration::from_secs(180));
+        track.clip = clip(30, 60);
+        assert!(!track.is_clip_end(Duration::from_secs(59)));
+        assert!(track.is_clip_end(Duration::from_secs(60)));
+        assert!(track.is_clip_end(Duration::from_secs(61)));
+    }
+
+    #[test]
+    fn playback_without_clip_never_reaches_clip_end() {
+        let track = song(Duration::from_secs(180));
+        assert!(!track.is_clip_end(Duration::from_secs(180)));
+        assert!(!track.is_clip_end(Duration::from_secs(600)));
+    }
+
+    #[test]
+    fn check_complete_accepts_whole_file() {
+        assert!(Track::check_complete(1024, Some(1024)).is_ok());
+    }
+
+    #[test]
+    fn check_complete_accepts_unknown_size() {
+        assert!(Track::check_complete(0, None).is_ok());
+        assert!(Track::check_complete(1024, None).is_ok());
+    }
+
+    /// Size announced by [`serve_truncated`] in `Content-Length`.
+    const FILE_SIZE: usize = 1024;
+
+    /// Serves responses announcing [`FILE_SIZE`] bytes, but closing the
+    /// connection after half of them.
+    fn serve_truncated() -> Url {
+        use std::io::{Read, Write};
+
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/track.mp3", listener.local_addr().unwrap());
+
+        std::thread::spawn(move || {
+            for mut socket in listener.incoming().map_while(std::io::Result::ok) {
+                std::thread::spawn(move || {
+                    let mut request = [0; 4096];
+                    let _ = socket.read(&mut request);
+
+                    let header = format!(
+                        "HTTP/1.1 200 OK\r\n\
+                         Content-Type: audio/mpeg\r\n\
+                         Content-Length: {FILE_SIZE}\r\n\
+                         Connection: close\r\n\r\n"
+                    );
+                    let _ = socket.write_all(header.as_bytes());
+                    let _ = socket.write_all(&[0; FILE_SIZE / 2]);
+                    let _ = socket.shutdown(std::net::Shutdown::Both);
+                });
+            }
+        });
+
+        url.parse().unwrap()
+    }
+
+    /// Waits for the download of a track to complete truncated.
+    async fn truncation(track: &Track) -> Error {
+        tokio::time::timeout(Duration::from_secs(30), async {
+            loop {
+                if let Err(e) = track.check_download() {
+                    return e;
+                }
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("download should complete truncated")
+    }
+
+    #[tokio::test]
+    async fn truncated_download_is_retried_once() {
+        let url = serve_truncated();
+        let slots = Arc::new(tokio::sync::Semaphore::new(1));
+
+        let mut track = song(Duration::from_secs(180));
+        track.cipher = Cipher::NONE;
+
+        let mut downloads = 0;
+        let mut retried = None;
+        let e = loop {
+            // Like the player, which clears the track before reloading it.
+            track.reset_download();
+            let stream = HttpStream::new(reqwest::Client::new(), url.clone())
+                .await
+                .unwrap();
+            let permit = Arc::clone(&slots).acquire_owned().await.unwrap();
+
+            // Keep the file open: dropping it cancels the download.
+            let _file = track
+                .download(stream, &url, MemoryStorageProvider, permit)
+                .await
+                .unwrap();
+            downloads += 1;
+            assert_eq!(track.file_size(), u64::try_from(FILE_SIZE).ok());
+
+            let e = truncation(&track).await;
+            match Player::truncation_retry(retried, track.id()) {
+                Some(retries) => retried = Some((track.id(), retries)),
+                None => break e,
+            }
+        };
+
+        assert_eq!(downloads, 1 + Player::TRUNCATED_DOWNLOAD_RETRIES);
+        assert_eq!(retried, Some((track.id(), 1)));
+        assert_eq!(e.kind, ErrorKind::DataLoss);
+        assert_eq!(
+            e.error.to_string(),
+            format!(
+                "download truncated at {} of {FILE_SIZE} bytes",
+                FILE_SIZE / 2
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn download_releases_slot_on_completion() {
+        let url = serve_truncated();
+        let slots = Arc::new(tokio::sync::Semaphore::new(1));
+
+        let mut track = song(Duration::from_secs(180));
+        track.cipher = Cipher::NONE;
+
+        let stream = HttpStream::new(reqwest::Client::new(), url.clone())
+            .await
+            .unwrap();
+        let permit = Arc::clone(&slots).acquire_owned().await.unwrap();
+
+        // Keep the file open: the slot is freed when the download completes,
+        // not when the track stops playing.
+        let _file = track
+            .download(stream, &url, MemoryStorageProvider, permit)
+            .await
+            .unwrap();
+        truncation(&track).await;
+
+        let slot = tokio::time::timeout(Duration::from_secs(1), slots.acquire()).await;
+        assert!(slot.is_ok(), "download slot should be free");
+    }
+
+    #[test]
+    fn check_complete_rejects_truncated_file() {
+        let e = Track::check_complete(512, Some(1024)).unwrap_err();
+        assert_eq!(e.kind, ErrorKind::DataLoss);
+        assert_eq!(
+            e.error.to_string(),
+            "download truncated at 512 of 1024 bytes"
+        );
+    }
+}